@@ -6,7 +6,7 @@ fn criterion_benchmark(c: &mut Criterion) {
     const TOKEN: &str = env!("GRAPHQL_TOKEN");
 
     let auth = Auth::Enabled {
-        header: &format!("Authorization: Bearer {TOKEN}"),
+        header: format!("Authorization: Bearer {TOKEN}").into(),
     };
 
     c.bench_function("simple_public_server", |b| {
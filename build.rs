@@ -0,0 +1,32 @@
+use std::env;
+use std::fs::read_to_string;
+use std::process::Command;
+
+/// Embeds the commit being built as `GIT_SHA`, for `--version`.
+fn main() {
+    // Declaring any of these stops Cargo rerunning this on every change to the package, so the
+    // commit checked out is watched too.
+    println!("cargo:rerun-if-env-changed=GITHUB_SHA");
+    println!("cargo:rerun-if-changed=.git/HEAD");
+    if let Some(reference) = read_to_string(".git/HEAD")
+        .ok()
+        .and_then(|head| Some(head.strip_prefix("ref: ")?.trim().to_string()))
+    {
+        println!("cargo:rerun-if-changed=.git/{reference}");
+    }
+    let sha = env::var("GITHUB_SHA")
+        .ok()
+        .or_else(|| {
+            let output = Command::new("git")
+                .args(["rev-parse", "HEAD"])
+                .output()
+                .ok()?;
+            output
+                .status
+                .success()
+                .then(|| String::from_utf8_lossy(&output.stdout).trim().to_string())
+        })
+        .filter(|sha| sha.len() >= 7)
+        .map_or_else(|| "unknown".to_string(), |sha| sha[..7].to_string());
+    println!("cargo:rustc-env=GIT_SHA={sha}");
+}
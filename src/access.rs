@@ -0,0 +1,191 @@
+use std::collections::BTreeMap;
+use std::fmt::{self, Debug, Formatter};
+
+use serde::{Deserialize, Serialize, Serializer};
+use serde_json::json;
+
+use crate::http::Context;
+use crate::{describe_rejection, post_json, redacted_header, split_header, Auth, Error, Outcome};
+
+/// Which roles may run which operations, checked by [`Checker::access`](crate::Checker::access).
+///
+/// ```json
+/// {
+///     "operations": {
+///         "listUsers": "query { users { id } }",
+///         "auditLog": "query { auditLog { id } }"
+///     },
+///     "roles": {
+///         "admin": {"auth": "Authorization: Bearer admin", "allow": ["listUsers", "auditLog"]},
+///         "viewer": {"auth": "Authorization: Bearer viewer", "allow": ["listUsers"], "deny": ["auditLog"]}
+///     }
+/// }
+/// ```
+#[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct AccessMatrix {
+    /// Each operation by name. Every allowed operation is actually run, so they should be
+    /// harmless.
+    pub operations: BTreeMap<String, String>,
+    pub roles: BTreeMap<String, Role>,
+}
+
+/// When serialized or debug-formatted, the value of `auth` is redacted.
+#[derive(Clone, Default, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Role {
+    /// The header which authenticates as this role, in the format `name: value`.
+    #[serde(serialize_with = "redact")]
+    pub auth: String,
+    /// Operations this role must be able to run.
+    pub allow: Vec<String>,
+    /// Operations this role must be rejected from running.
+    pub deny: Vec<String>,
+}
+
+impl AccessMatrix {
+    /// Finds headers which can't be sent and operations which aren't in `operations`.
+    pub fn validate(&self) -> Vec<Error> {
+        let mut problems = Vec::new();
+        for (name, role) in &self.roles {
+            if let Err(err) = split_header(&role.auth) {
+                problems.push(err);
+            }
+            for operation in role.allow.iter().chain(&role.deny) {
+                if !self.operations.contains_key(operation) {
+                    problems.push(Error::BadInput(format!(
+                        "role `{name}` refers to the unknown operation `{operation}`"
+                    )));
+                }
+            }
+        }
+        problems
+    }
+}
+
+/// Runs every allowed and denied operation as each role, failing with every role which could run
+/// something it shouldn't or couldn't run something it should.
+pub(crate) fn check_access(context: &Context, url: &str, matrix: &AccessMatrix) -> Outcome {
+    let mut violations = Vec::new();
+    for (name, role) in &matrix.roles {
+        let auth = Auth::Enabled {
+            header: role.auth.as_str().into(),
+        };
+        let expectations = role
+            .allow
+            .iter()
+            .map(|operation| (operation, true))
+            .chain(role.deny.iter().map(|operation| (operation, false)));
+        for (operation, allowed) in expectations {
+            let Some(query) = matrix.operations.get(operation) else {
+                continue;
+            };
+            let result = post_json(context, url, &auth, json!({ "query": query }));
+            match (result, allowed) {
+                (Ok(_), true) => {}
+                (Ok(_), false) => violations.push(format!("`{name}` could run `{operation}`")),
+                (Err(Error::Cancelled), _) => return Outcome::Failed(Error::Cancelled),
+                (Err(err), true) => violations.push(format!(
+                    "`{name}` couldn't run `{operation}` ({})",
+                    describe_rejection(&err).unwrap_or_else(|| err.to_string())
+                )),
+                (Err(err), false) => {
+                    if describe_rejection(&err).is_none() {
+                        return Outcome::Failed(err);
+                    }
+                }
+            }
+        }
+    }
+    if violations.is_empty() {
+        Outcome::Passed
+    } else {
+        Outcome::Failed(Error::AccessViolations(violations))
+    }
+}
+
+impl Debug for Role {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Role")
+            .field("auth", &redacted_header(&self.auth))
+            .field("allow", &self.allow)
+            .field("deny", &self.deny)
+            .finish()
+    }
+}
+
+fn redact<S: Serializer>(header: &str, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_str(&redacted_header(header))
+}
+
+#[cfg(test)]
+mod test_check_access {
+    use super::*;
+    use crate::testing::{context, query};
+    use crate::HttpResponse;
+
+    fn matrix() -> AccessMatrix {
+        serde_json::from_value(json!({
+            "operations": {
+                "listUsers": "query{users{id}}",
+                "auditLog": "query{auditLog{id}}"
+            },
+            "roles": {
+                "admin": {"auth": "Authorization: Bearer admin", "allow": ["listUsers", "auditLog"]},
+                "viewer": {"auth": "Authorization: Bearer viewer", "allow": ["listUsers"], "deny": ["auditLog"]}
+            }
+        }))
+        .unwrap()
+    }
+
+    /// Only admins can read the audit log, and nobody can list users.
+    fn server() -> Context {
+        context(|request| {
+            let admin = request
+                .headers
+                .iter()
+                .any(|(_, value)| value == "Bearer admin");
+            Ok(match query(request) {
+                Some("query{auditLog{id}}") if admin => {
+                    HttpResponse::json(200, &json!({"data": {"auditLog": []}}))
+                }
+                _ => HttpResponse::json(403, &json!({"errors": [{"message": "Forbidden"}]})),
+            })
+        })
+    }
+
+    #[test]
+    fn violations() {
+        assert_eq!(
+            check_access(&server(), "https://example.com/graphql", &matrix()),
+            Outcome::Failed(Error::AccessViolations(vec![
+                "`admin` couldn't run `listUsers` (status 403)".to_string(),
+                "`viewer` couldn't run `listUsers` (status 403)".to_string(),
+            ]))
+        );
+    }
+
+    #[test]
+    fn validate() {
+        let mut matrix = matrix();
+        matrix
+            .roles
+            .get_mut("viewer")
+            .unwrap()
+            .deny
+            .push("dropTables".to_string());
+        assert_eq!(
+            matrix.validate(),
+            [Error::BadInput(
+                "role `viewer` refers to the unknown operation `dropTables`".to_string()
+            )]
+        );
+    }
+
+    #[test]
+    fn headers_are_redacted() {
+        let json = serde_json::to_string(&matrix()).unwrap();
+        assert!(!json.contains("Bearer"));
+        assert!(!format!("{:?}", matrix()).contains("Bearer"));
+    }
+}
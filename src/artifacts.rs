@@ -0,0 +1,90 @@
+use std::fs::{create_dir_all, write};
+use std::io;
+use std::path::Path;
+
+use graphql_check_action::report::Report;
+use graphql_check_action::schema::Schema;
+use graphql_check_action::Capture;
+use serde_json::json;
+
+/// Writes everything needed to debug a run after the fact to `dir`, for use with
+/// `actions/upload-artifact`:
+///
+/// - `report.json`: the same report written to the `report` input
+/// - `schema.graphql` or `introspection.json`: the schema of the endpoint, if it could be fetched
+/// - `exchanges.json`: every request made during the checks and its response, with secrets redacted
+/// - `timing.json`: how long each request took
+pub fn write_bundle(
+    dir: &Path,
+    report: &Report,
+    schema: Option<&Schema>,
+    capture: &Capture,
+) -> io::Result<()> {
+    create_dir_all(dir)?;
+    write(dir.join("report.json"), report.to_json())?;
+    match schema {
+        Some(Schema::Sdl(sdl)) => write(dir.join("schema.graphql"), sdl)?,
+        Some(Schema::Introspection(data)) => write(
+            dir.join("introspection.json"),
+            serde_json::to_string_pretty(data)?,
+        )?,
+        None => {}
+    }
+    let exchanges = capture.exchanges();
+    write(
+        dir.join("exchanges.json"),
+        serde_json::to_string_pretty(&exchanges)?,
+    )?;
+    let timing = json!({
+        "total_ms": capture.total_duration().as_millis(),
+        "requests": exchanges
+            .iter()
+            .map(|exchange| json!({
+                "method": exchange.method,
+                "url": exchange.url,
+                "status": exchange.status,
+                "duration_ms": exchange.duration_ms,
+            }))
+            .collect::<Vec<_>>(),
+    });
+    write(
+        dir.join("timing.json"),
+        serde_json::to_string_pretty(&timing)?,
+    )
+}
+
+#[cfg(test)]
+mod test_write_bundle {
+    use std::fs::{read_dir, remove_dir_all};
+
+    use graphql_check_action::Auth;
+
+    use super::*;
+
+    #[test]
+    fn files() {
+        let dir = std::env::temp_dir().join(format!("bundle-{}", std::process::id()));
+        write_bundle(
+            &dir,
+            &Report::default(),
+            Some(&Schema::Sdl("type Query { a: Int }".to_string())),
+            &Capture::new(Auth::Disabled),
+        )
+        .unwrap();
+        let mut files: Vec<String> = read_dir(&dir)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().into_string().unwrap())
+            .collect();
+        files.sort();
+        remove_dir_all(&dir).unwrap();
+        assert_eq!(
+            files,
+            [
+                "exchanges.json",
+                "report.json",
+                "schema.graphql",
+                "timing.json"
+            ]
+        );
+    }
+}
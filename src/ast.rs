@@ -0,0 +1,254 @@
+use std::fmt::Write;
+
+use graphql_parser::schema::{parse_schema, Document};
+use serde_json::Value;
+
+use crate::schema::BUILT_IN_SCALARS;
+use crate::Error;
+
+/// Directives every schema has, which introspection lists but SDL doesn't.
+const BUILT_IN_DIRECTIVES: [&str; 4] = ["skip", "include", "deprecated", "specifiedBy"];
+
+/// Parses the `data` of an introspection response into the same AST as SDL, by writing it out as
+/// SDL first. Built-in scalars, directives and introspection types are left out, like
+/// [`Schema::coordinates`](crate::schema::Schema::coordinates) does.
+pub(crate) fn introspection_document(data: &Value) -> Result<Document<'static, String>, Error> {
+    let schema = &data["__schema"];
+    let mut sdl = String::new();
+    let roots: Vec<String> = [
+        ("query", "queryType"),
+        ("mutation", "mutationType"),
+        ("subscription", "subscriptionType"),
+    ]
+    .into_iter()
+    .filter_map(|(operation, key)| Some(format!("{operation}: {}", schema[key]["name"].as_str()?)))
+    .collect();
+    if !roots.is_empty() {
+        writeln!(sdl, "schema {{ {} }}", roots.join(" ")).unwrap();
+    }
+    for directive in array(&schema["directives"]) {
+        let name = directive["name"].as_str().unwrap_or_default();
+        if BUILT_IN_DIRECTIVES.contains(&name) {
+            continue;
+        }
+        description(&mut sdl, directive);
+        let locations: Vec<&str> = array(&directive["locations"])
+            .iter()
+            .filter_map(Value::as_str)
+            .collect();
+        writeln!(
+            sdl,
+            "directive @{name}{} on {}",
+            arguments(&directive["args"]),
+            locations.join(" | ")
+        )
+        .unwrap();
+    }
+    for ty in array(&schema["types"]) {
+        let Some(name) = ty["name"].as_str() else {
+            continue;
+        };
+        if name.starts_with("__") || BUILT_IN_SCALARS.contains(&name) {
+            continue;
+        }
+        description(&mut sdl, ty);
+        let interfaces: Vec<String> = array(&ty["interfaces"]).iter().map(type_ref).collect();
+        let implements = if interfaces.is_empty() {
+            String::new()
+        } else {
+            format!(" implements {}", interfaces.join(" & "))
+        };
+        match ty["kind"].as_str() {
+            Some("OBJECT") => sdl.push_str(&format!("type {name}{implements}")),
+            Some("INTERFACE") => sdl.push_str(&format!("interface {name}{implements}")),
+            Some("UNION") => {
+                let members: Vec<String> =
+                    array(&ty["possibleTypes"]).iter().map(type_ref).collect();
+                sdl.push_str(&format!("union {name} = {}", members.join(" | ")));
+            }
+            Some("ENUM") => sdl.push_str(&format!("enum {name}")),
+            Some("INPUT_OBJECT") => sdl.push_str(&format!("input {name}")),
+            _ => sdl.push_str(&format!("scalar {name}")),
+        }
+        let mut members = String::new();
+        for field in array(&ty["fields"]) {
+            description(&mut members, field);
+            writeln!(
+                members,
+                "{}{}: {}{}",
+                field["name"].as_str().unwrap_or_default(),
+                arguments(&field["args"]),
+                type_ref(&field["type"]),
+                deprecated(field)
+            )
+            .unwrap();
+        }
+        for field in array(&ty["inputFields"]) {
+            description(&mut members, field);
+            writeln!(members, "{}", input_value(field)).unwrap();
+        }
+        for value in array(&ty["enumValues"]) {
+            description(&mut members, value);
+            let value_name = value["name"].as_str().unwrap_or_default();
+            writeln!(members, "{value_name}{}", deprecated(value)).unwrap();
+        }
+        if members.is_empty() {
+            sdl.push('\n');
+        } else {
+            writeln!(sdl, " {{\n{members}}}").unwrap();
+        }
+    }
+    parse_schema::<String>(&sdl)
+        .map(Document::into_static)
+        .map_err(|err| Error::BadSchema(err.to_string()))
+}
+
+fn array(value: &Value) -> &[Value] {
+    value.as_array().map_or(&[], Vec::as_slice)
+}
+
+fn type_ref(value: &Value) -> String {
+    match value["kind"].as_str() {
+        Some("NON_NULL") => format!("{}!", type_ref(&value["ofType"])),
+        Some("LIST") => format!("[{}]", type_ref(&value["ofType"])),
+        _ => value["name"].as_str().unwrap_or_default().to_string(),
+    }
+}
+
+/// A GraphQL string, which escapes the same way as JSON.
+fn string(text: &str) -> String {
+    Value::from(text).to_string()
+}
+
+fn description(sdl: &mut String, definition: &Value) {
+    if let Some(description) = definition["description"].as_str() {
+        writeln!(sdl, "{}", string(description)).unwrap();
+    }
+}
+
+fn deprecated(definition: &Value) -> String {
+    if definition["isDeprecated"].as_bool() != Some(true) {
+        return String::new();
+    }
+    match definition["deprecationReason"].as_str() {
+        Some(reason) => format!(" @deprecated(reason: {})", string(reason)),
+        None => " @deprecated".to_string(),
+    }
+}
+
+/// An argument or input field. Its default value is already GraphQL.
+fn input_value(value: &Value) -> String {
+    let mut input = format!(
+        "{}: {}",
+        value["name"].as_str().unwrap_or_default(),
+        type_ref(&value["type"])
+    );
+    if let Some(default) = value["defaultValue"].as_str() {
+        write!(input, " = {default}").unwrap();
+    }
+    input
+}
+
+fn arguments(args: &Value) -> String {
+    let args: Vec<String> = array(args)
+        .iter()
+        .map(|arg| {
+            let mut sdl = String::new();
+            description(&mut sdl, arg);
+            sdl + &input_value(arg)
+        })
+        .collect();
+    if args.is_empty() {
+        String::new()
+    } else {
+        format!("({})", args.join(", "))
+    }
+}
+
+#[cfg(test)]
+mod test_introspection_document {
+    use serde_json::json;
+
+    use super::*;
+    use crate::schema::Schema;
+
+    fn named(kind: &str, name: &str) -> Value {
+        json!({"kind": kind, "name": name})
+    }
+
+    fn non_null(of_type: Value) -> Value {
+        json!({"kind": "NON_NULL", "name": null, "ofType": of_type})
+    }
+
+    #[test]
+    fn same_as_sdl() {
+        let data = json!({"__schema": {
+            "queryType": {"name": "Query"},
+            "mutationType": null,
+            "subscriptionType": null,
+            "directives": [
+                {"name": "skip", "locations": ["FIELD"], "args": []},
+                {"name": "cost", "description": null, "locations": ["FIELD_DEFINITION", "OBJECT"], "args": [
+                    {"name": "weight", "type": non_null(named("SCALAR", "Int")), "defaultValue": "1"}
+                ]}
+            ],
+            "types": [
+                {"kind": "OBJECT", "name": "Query", "description": "The \"root\"", "interfaces": [], "fields": [
+                    {"name": "user", "args": [
+                        {"name": "id", "type": non_null(named("SCALAR", "ID")), "defaultValue": null}
+                    ], "type": named("OBJECT", "User"), "isDeprecated": false},
+                    {"name": "users", "args": [
+                        {"name": "role", "type": named("ENUM", "Role"), "defaultValue": "ADMIN"}
+                    ], "type": json!({"kind": "LIST", "name": null, "ofType": named("OBJECT", "User")}),
+                    "isDeprecated": true, "deprecationReason": "Use `search`"}
+                ]},
+                {"kind": "INTERFACE", "name": "Node", "interfaces": [], "fields": [
+                    {"name": "id", "args": [], "type": non_null(named("SCALAR", "ID"))}
+                ]},
+                {"kind": "OBJECT", "name": "User", "interfaces": [named("INTERFACE", "Node")], "fields": [
+                    {"name": "id", "args": [], "type": non_null(named("SCALAR", "ID"))}
+                ]},
+                {"kind": "UNION", "name": "Result", "possibleTypes": [named("OBJECT", "User")]},
+                {"kind": "ENUM", "name": "Role", "enumValues": [
+                    {"name": "ADMIN", "isDeprecated": false}
+                ]},
+                {"kind": "INPUT_OBJECT", "name": "Filter", "inputFields": [
+                    {"name": "limit", "type": named("SCALAR", "Int"), "defaultValue": "10"}
+                ]},
+                {"kind": "SCALAR", "name": "DateTime"},
+                {"kind": "SCALAR", "name": "String"},
+                {"kind": "OBJECT", "name": "__Schema", "interfaces": [], "fields": []}
+            ]
+        }});
+        let sdl = r#"
+            schema { query: Query }
+            directive @cost(weight: Int! = 1) on FIELD_DEFINITION | OBJECT
+            "The \"root\""
+            type Query {
+              user(id: ID!): User
+              users(role: Role = ADMIN): [User] @deprecated(reason: "Use `search`")
+            }
+            interface Node { id: ID! }
+            type User implements Node { id: ID! }
+            union Result = User
+            enum Role { ADMIN }
+            input Filter { limit: Int = 10 }
+            scalar DateTime
+        "#;
+        // Positions differ, so the documents are compared as they're printed.
+        assert_eq!(
+            Schema::Introspection(data).document().unwrap().to_string(),
+            Schema::Sdl(sdl.to_string()).document().unwrap().to_string()
+        );
+    }
+
+    #[test]
+    fn not_a_schema() {
+        assert!(matches!(
+            introspection_document(
+                &json!({"__schema": {"types": [{"kind": "OBJECT", "name": "1"}]}})
+            ),
+            Err(Error::BadSchema(_))
+        ));
+    }
+}
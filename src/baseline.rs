@@ -0,0 +1,117 @@
+use crate::report::{Failure, Report};
+use crate::Severity;
+
+/// How the failures of the current run relate to the failures of a previous run.
+///
+/// Failures are matched by [`Failure::check`] and [`Failure::code`], so a message that changes
+/// between runs (like the body of a GraphQL error) doesn't turn a known failure into a new one, but
+/// another check failing the same way does. Failures which didn't come from a check, like every
+/// one in a report saved before checks were recorded, are matched by their code alone.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct Comparison {
+    /// Failures in the current run which were not in the previous run.
+    pub new: Vec<Failure>,
+    /// Failures present in both runs.
+    pub known: Vec<Failure>,
+    /// Failures from the previous run which no longer happen.
+    pub fixed: Vec<Failure>,
+}
+
+impl Comparison {
+    /// Whether any new failure is severe enough to fail the run.
+    pub fn has_regressions(&self) -> bool {
+        self.new
+            .iter()
+            .any(|failure| failure.severity == Severity::Error)
+    }
+}
+
+pub fn compare(previous: &Report, current: &Report) -> Comparison {
+    let same = |a: &Failure, b: &Failure| a.check == b.check && a.code == b.code;
+    let was_failing = |failure: &Failure| previous.failures.iter().any(|f| same(f, failure));
+    let is_failing = |failure: &Failure| current.failures.iter().any(|f| same(f, failure));
+
+    let (known, new) = current
+        .failures
+        .iter()
+        .cloned()
+        .partition(|failure| was_failing(failure));
+    let fixed = previous
+        .failures
+        .iter()
+        .filter(|failure| !is_failing(failure))
+        .cloned()
+        .collect();
+
+    Comparison { new, known, fixed }
+}
+
+#[cfg(test)]
+mod test_compare {
+    use std::time::Duration;
+
+    use super::*;
+    use crate::graphql::GraphQLError;
+    use crate::{Check, Error};
+
+    #[test]
+    fn unchanged() {
+        let report = Report::new("", &[Error::IntrospectionEnabled]);
+        let comparison = compare(&report, &report);
+        assert_eq!(comparison.known, report.failures);
+        assert!(!comparison.has_regressions());
+        assert!(comparison.fixed.is_empty());
+    }
+
+    #[test]
+    fn new_and_fixed() {
+        let previous = Report::new("", &[Error::IntrospectionEnabled]);
+        let current = Report::new("", &[Error::AuthNotEnforced]);
+        let comparison = compare(&previous, &current);
+        assert_eq!(comparison.new, current.failures);
+        assert_eq!(comparison.fixed, previous.failures);
+        assert!(comparison.known.is_empty());
+        assert!(comparison.has_regressions());
+    }
+
+    #[test]
+    fn new_warning_is_not_a_regression() {
+        let previous = Report::new("", &[]);
+        let mut current = Report::new("", &[Error::IntrospectionEnabled]);
+        current.failures[0].severity = Severity::Warning;
+        let comparison = compare(&previous, &current);
+        assert_eq!(comparison.new, current.failures);
+        assert!(!comparison.has_regressions());
+    }
+
+    #[test]
+    fn matched_by_code() {
+        let previous = Report::new("", &[Error::GraphQLError(vec![GraphQLError::new("first")])]);
+        let current = Report::new(
+            "",
+            &[Error::GraphQLError(vec![GraphQLError::new("second")])],
+        );
+        let comparison = compare(&previous, &current);
+        assert_eq!(comparison.known, current.failures);
+        assert!(comparison.fixed.is_empty());
+    }
+
+    #[test]
+    fn matched_by_check() {
+        let timeout = |check| Failure {
+            check: Some(check),
+            ..Failure::from(&Error::timed_out(
+                Duration::from_secs(60),
+                Duration::from_secs(60),
+            ))
+        };
+        let mut previous = Report::new("", &[]);
+        previous.failures = vec![timeout(Check::BasicQuery)];
+        let mut current = Report::new("", &[]);
+        current.failures = vec![timeout(Check::BasicQuery), timeout(Check::CacheControl)];
+        let comparison = compare(&previous, &current);
+        assert_eq!(comparison.known, [timeout(Check::BasicQuery)]);
+        assert_eq!(comparison.new, [timeout(Check::CacheControl)]);
+        assert!(comparison.has_regressions());
+    }
+}
@@ -0,0 +1,236 @@
+use serde_json::json;
+
+use crate::capabilities::sha256_hex;
+use crate::http::{Context, HttpResponse};
+use crate::{authenticate, get_json, make_request, post_json, Auth, Error, Outcome};
+
+const PERSISTED_QUERY: &str = "query{__typename}";
+
+/// Checks that the response to an authenticated `POST` has a `Cache-Control` header with every
+/// directive of `policy` (e.g., `no-store` or `private, max-age=0`), so no cache in between can
+/// hand one user's response to another.
+pub(crate) fn check_cache_control(
+    context: &Context,
+    url: &str,
+    auth: &Auth,
+    policy: &str,
+) -> Outcome {
+    let request = match make_request(context, url, auth) {
+        Ok(request) => request,
+        Err(err) => return Outcome::Failed(err),
+    };
+    let response = match context.send(request, Some(&json!({"query": "query{__typename}"}))) {
+        Ok(response) => response,
+        Err(err) => return Outcome::Failed(err),
+    };
+    let got = response.header("Cache-Control");
+    let actual = got.map(directives).unwrap_or_default();
+    if directives(policy)
+        .iter()
+        .all(|expected| actual.contains(expected))
+    {
+        Outcome::Passed
+    } else {
+        Outcome::Failed(Error::Cacheable {
+            expected: policy.to_string(),
+            got: got.map(String::from),
+        })
+    }
+}
+
+/// The directives of a `Cache-Control` header, normalized so they can be compared.
+fn directives(header: &str) -> Vec<String> {
+    header
+        .split(',')
+        .map(|directive| directive.trim().to_ascii_lowercase().replace(' ', ""))
+        .filter(|directive| !directive.is_empty())
+        .collect()
+}
+
+/// Registers a persisted query, then sends it as a `GET` twice without auth, expecting the second
+/// to be answered from a CDN's cache. With `auth`, also sends it twice with auth, expecting neither
+/// to come from the cache, since a cached authenticated response could be served to anyone.
+pub(crate) fn check_cdn_cache(context: &Context, url: &str, auth: &Auth) -> Outcome {
+    let extensions = json!({
+        "persistedQuery": {
+            "version": 1,
+            "sha256Hash": sha256_hex(PERSISTED_QUERY),
+        }
+    });
+    let register = json!({"query": PERSISTED_QUERY, "extensions": extensions});
+    if let Err(err) = post_json(context, url, auth, register) {
+        return Outcome::Failed(err);
+    }
+    let extensions = extensions.to_string();
+    let persisted_get = |auth: &Auth| -> Result<HttpResponse, Error> {
+        let request = authenticate(
+            context,
+            context.get(url).query("extensions", &extensions),
+            auth,
+        )?;
+        let response = context.send(request, None)?;
+        get_json(context, Ok(response.clone()))?;
+        Ok(response)
+    };
+    let anonymous = persisted_get(&Auth::Disabled).and_then(|_| persisted_get(&Auth::Disabled));
+    match anonymous {
+        Ok(response) if is_cache_hit(&response) => {}
+        Ok(_) => return Outcome::Failed(Error::NotCachedByCdn),
+        Err(err) => return Outcome::Failed(err),
+    }
+    if auth.is_enabled() {
+        for _ in 0..2 {
+            match persisted_get(auth) {
+                Ok(response) if is_cache_hit(&response) => {
+                    return Outcome::Failed(Error::AuthenticatedResponseCached);
+                }
+                Ok(_) => {}
+                Err(err) => return Outcome::Failed(err),
+            }
+        }
+    }
+    Outcome::Passed
+}
+
+/// Whether a CDN says it answered from its cache, going by the headers the common ones set. Some
+/// CDNs send `Age: 0` with responses they just fetched, so only an older one counts.
+fn is_cache_hit(response: &HttpResponse) -> bool {
+    let says_hit = |header: &str| {
+        response
+            .header(header)
+            .is_some_and(|value| value.to_ascii_lowercase().contains("hit"))
+    };
+    response
+        .header("Age")
+        .and_then(|age| age.trim().parse::<u64>().ok())
+        .is_some_and(|age| age > 0)
+        || says_hit("CF-Cache-Status")
+        || says_hit("X-Cache")
+        || says_hit("X-Vercel-Cache")
+        || says_hit("CDN-Cache-Status")
+}
+
+#[cfg(test)]
+mod test_check_cache_control {
+    use super::*;
+    use crate::testing::context;
+    use crate::HttpResponse;
+
+    fn server(cache_control: Option<&'static str>) -> Context {
+        context(move |_| {
+            let response = HttpResponse::json(200, &json!({"data": {"__typename": "Query"}}));
+            Ok(match cache_control {
+                Some(value) => response.with_header("Cache-Control", value),
+                None => response,
+            })
+        })
+    }
+
+    fn check(context: &Context, policy: &str) -> Outcome {
+        check_cache_control(
+            context,
+            "https://example.com/graphql",
+            &Auth::Disabled,
+            policy,
+        )
+    }
+
+    #[test]
+    fn matches() {
+        assert_eq!(
+            check(&server(Some("No-Store, private")), "no-store"),
+            Outcome::Passed
+        );
+        assert_eq!(
+            check(&server(Some("private, max-age=0")), "max-age=0,private"),
+            Outcome::Passed
+        );
+    }
+
+    #[test]
+    fn cacheable() {
+        assert_eq!(
+            check(&server(Some("public, max-age=60")), "no-store"),
+            Outcome::Failed(Error::Cacheable {
+                expected: "no-store".to_string(),
+                got: Some("public, max-age=60".to_string())
+            })
+        );
+        assert_eq!(
+            check(&server(None), "no-store"),
+            Outcome::Failed(Error::Cacheable {
+                expected: "no-store".to_string(),
+                got: None
+            })
+        );
+    }
+}
+
+#[cfg(test)]
+mod test_check_cdn_cache {
+    use std::borrow::Cow;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    use super::*;
+    use crate::testing::context;
+
+    const AUTH: Auth = Auth::Enabled {
+        header: Cow::Borrowed("Authorization: Bearer token"),
+    };
+
+    /// A CDN which caches every `GET` after the first, or only anonymous ones if `vary_on_auth`.
+    fn cdn(vary_on_auth: bool) -> Context {
+        let gets = Arc::new(AtomicUsize::new(0));
+        context(move |request| {
+            let response = HttpResponse::json(200, &json!({"data": {"__typename": "Query"}}));
+            let authenticated = request.header("Authorization").is_some();
+            if request.method != "GET" || (vary_on_auth && authenticated) {
+                return Ok(response);
+            }
+            Ok(if gets.fetch_add(1, Ordering::SeqCst) == 0 {
+                response.with_header("CF-Cache-Status", "MISS")
+            } else {
+                response.with_header("CF-Cache-Status", "HIT")
+            })
+        })
+    }
+
+    #[test]
+    fn cached() {
+        assert_eq!(
+            check_cdn_cache(&cdn(true), "https://example.com/graphql", &AUTH),
+            Outcome::Passed
+        );
+    }
+
+    #[test]
+    fn authenticated_cached() {
+        assert_eq!(
+            check_cdn_cache(&cdn(false), "https://example.com/graphql", &AUTH),
+            Outcome::Failed(Error::AuthenticatedResponseCached)
+        );
+    }
+
+    #[test]
+    fn not_cached() {
+        let context = context(|_| {
+            Ok(HttpResponse::json(
+                200,
+                &json!({"data": {"__typename": "Query"}}),
+            ))
+        });
+        assert_eq!(
+            check_cdn_cache(&context, "https://example.com/graphql", &Auth::Disabled),
+            Outcome::Failed(Error::NotCachedByCdn)
+        );
+    }
+
+    #[test]
+    fn age() {
+        let response = HttpResponse::json(200, &json!({"data": {"__typename": "Query"}}));
+        assert!(!is_cache_hit(&response.clone().with_header("Age", "0")));
+        assert!(!is_cache_hit(&response.clone().with_header("Age", "soon")));
+        assert!(is_cache_hit(&response.with_header("Age", "12")));
+    }
+}
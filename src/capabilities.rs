@@ -0,0 +1,460 @@
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
+
+use crate::http::{Context, HttpResponse};
+use crate::{authenticate, get_json, make_request, post_json, Auth, Error, Outcome};
+
+const QUERY: &str = "query{__typename}";
+
+/// How the body of a `POST` is labeled, probed by [`Capabilities::content_types`] and checked by
+/// [`Checker::content_types`](crate::Checker::content_types).
+#[derive(Clone, Copy, Debug, Deserialize, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize)]
+pub enum ContentType {
+    #[serde(rename = "application/json")]
+    Json,
+    /// The query itself as the body, which some servers accept.
+    #[serde(rename = "application/graphql")]
+    Graphql,
+    /// A JSON body labeled as text. Browsers send these cross-site without a CORS preflight, so a
+    /// server accepting them is open to CSRF.
+    #[serde(rename = "text/plain")]
+    TextPlain,
+    /// A JSON body without a `Content-Type`, which browsers also send without a preflight.
+    #[serde(rename = "none")]
+    Missing,
+}
+
+impl ContentType {
+    pub const ALL: [ContentType; 4] = [
+        ContentType::Json,
+        ContentType::Graphql,
+        ContentType::TextPlain,
+        ContentType::Missing,
+    ];
+
+    pub const fn name(self) -> &'static str {
+        match self {
+            ContentType::Json => "application/json",
+            ContentType::Graphql => "application/graphql",
+            ContentType::TextPlain => "text/plain",
+            ContentType::Missing => "none",
+        }
+    }
+
+    pub fn parse(value: &str) -> Result<Self, Error> {
+        Self::ALL
+            .into_iter()
+            .find(|content_type| content_type.name() == value)
+            .ok_or_else(|| {
+                let names = Self::ALL.map(|content_type| format!("`{}`", content_type.name()));
+                Error::BadInput(format!(
+                    "unknown content type `{value}`, expected one of {}",
+                    names.join(", ")
+                ))
+            })
+    }
+}
+
+/// Optional features of a GraphQL server. None of these are required, so probing for them never
+/// fails a run.
+#[derive(Clone, Debug, Default, Deserialize, Eq, Hash, PartialEq, Serialize)]
+pub struct Capabilities {
+    /// Queries can be sent as `GET` requests with a `query` parameter.
+    pub get_queries: bool,
+    /// [Automatic persisted queries](https://www.apollographql.com/docs/apollo-server/performance/apq/)
+    pub persisted_queries: bool,
+    /// Multiple operations can be sent in a single request as a JSON array.
+    pub batching: bool,
+    /// Incremental delivery with `@defer`.
+    pub defer: bool,
+    /// The WebSocket subprotocol the server accepted for subscriptions, if any.
+    pub subscriptions: Option<String>,
+    /// Responses are compressed when the client asks for it.
+    pub compression: bool,
+    /// Which labels for a `POST` body the server answers queries with. Accepting
+    /// [`ContentType::TextPlain`] or [`ContentType::Missing`] makes the server open to CSRF.
+    pub content_types: Vec<ContentType>,
+}
+
+pub fn probe_capabilities(url: &str, auth: Auth) -> Capabilities {
+    probe_capabilities_with(&Context::default(), url, &auth)
+}
+
+pub(crate) fn probe_capabilities_with(context: &Context, url: &str, auth: &Auth) -> Capabilities {
+    Capabilities {
+        get_queries: supports_get(context, url, auth),
+        persisted_queries: supports_persisted_queries(context, url, auth),
+        batching: supports_batching(context, url, auth),
+        defer: supports_defer(context, url, auth),
+        subscriptions: subscription_protocol(context, url, auth),
+        compression: supports_compression(context, url, auth),
+        content_types: accepted_content_types(context, url, auth),
+    }
+}
+
+fn has_typename(body: &Value) -> bool {
+    matches!(body.pointer("/data/__typename"), Some(Value::String(_)))
+}
+
+fn supports_get(context: &Context, url: &str, auth: &Auth) -> bool {
+    let Ok(request) = authenticate(context, context.get(url).query("query", QUERY), auth) else {
+        return false;
+    };
+    get_json(context, context.send(request, None)).is_ok_and(|body| has_typename(&body))
+}
+
+pub(crate) fn sha256_hex(document: &str) -> String {
+    Sha256::digest(document.as_bytes())
+        .iter()
+        .map(|byte| format!("{byte:02x}"))
+        .collect()
+}
+
+fn supports_persisted_queries(context: &Context, url: &str, auth: &Auth) -> bool {
+    let extensions = json!({
+        "persistedQuery": {
+            "version": 1,
+            "sha256Hash": sha256_hex(QUERY),
+        }
+    });
+    let register = json!({
+        "query": QUERY,
+        "extensions": extensions,
+    });
+    returns_typename(context, url, auth, register)
+        && returns_typename(context, url, auth, json!({ "extensions": extensions }))
+}
+
+fn returns_typename(context: &Context, url: &str, auth: &Auth, body: Value) -> bool {
+    post_json(context, url, auth, body).is_ok_and(|body| has_typename(&body))
+}
+
+/// Sends `body` as JSON, returning any response regardless of status.
+fn post(context: &Context, url: &str, auth: &Auth, body: &Value) -> Option<HttpResponse> {
+    context
+        .send(make_request(context, url, auth).ok()?, Some(body))
+        .ok()
+}
+
+fn is_batch_response(body: &Value, expected_len: usize) -> bool {
+    match body {
+        Value::Array(responses) => {
+            responses.len() == expected_len && responses.iter().all(has_typename)
+        }
+        _ => false,
+    }
+}
+
+fn supports_batching(context: &Context, url: &str, auth: &Auth) -> bool {
+    let body = json!([{ "query": QUERY }, { "query": QUERY }]);
+    post(context, url, auth, &body)
+        .filter(|response| response.status < 400)
+        .and_then(|response| serde_json::from_str::<Value>(&response.body).ok())
+        .is_some_and(|body| is_batch_response(&body, 2))
+}
+
+fn supports_defer(context: &Context, url: &str, auth: &Auth) -> bool {
+    let Ok(request) = make_request(context, url, auth) else {
+        return false;
+    };
+    let request = request.set(
+        "Accept",
+        "multipart/mixed;deferSpec=20220824, application/json",
+    );
+    let body = json!({ "query": "query{... @defer {__typename}}" });
+    context
+        .send(request, Some(&body))
+        .is_ok_and(|response| response.content_type() == "multipart/mixed")
+}
+
+fn subscription_protocol(context: &Context, url: &str, auth: &Auth) -> Option<String> {
+    let request = authenticate(context, context.get(url), auth)
+        .ok()?
+        .set("Connection", "Upgrade")
+        .set("Upgrade", "websocket")
+        .set("Sec-WebSocket-Version", "13")
+        .set("Sec-WebSocket-Key", "dGhlIHNhbXBsZSBub25jZQ==")
+        .set("Sec-WebSocket-Protocol", "graphql-transport-ws, graphql-ws");
+    let response = context.send(request, None).ok()?;
+    if response.status != 101 {
+        return None;
+    }
+    response.header("Sec-WebSocket-Protocol").map(String::from)
+}
+
+fn supports_compression(context: &Context, url: &str, auth: &Auth) -> bool {
+    let Ok(request) = make_request(context, url, auth) else {
+        return false;
+    };
+    let request = request.set("Accept-Encoding", "gzip, br, deflate");
+    context
+        .send(request, Some(&json!({ "query": QUERY })))
+        .is_ok_and(|response| {
+            response.status < 400
+                && response
+                    .header("Content-Encoding")
+                    .is_some_and(|encoding| encoding != "identity")
+        })
+}
+
+/// Sends [`QUERY`] labeled as each of [`ContentType::ALL`], returning the ones which were answered.
+/// A content type which couldn't be sent at all counts as not answered.
+fn accepted_content_types(context: &Context, url: &str, auth: &Auth) -> Vec<ContentType> {
+    ContentType::ALL
+        .into_iter()
+        .filter(|content_type| {
+            answers_content_type(context, url, auth, *content_type).unwrap_or_default()
+        })
+        .collect()
+}
+
+/// Whether [`QUERY`] labeled as `content_type` is answered, or why it couldn't be sent.
+fn answers_content_type(
+    context: &Context,
+    url: &str,
+    auth: &Auth,
+    content_type: ContentType,
+) -> Result<bool, Error> {
+    let request = make_request(context, url, auth)?;
+    let json_body = json!({ "query": QUERY }).to_string();
+    let (request, body) = match content_type {
+        ContentType::Json => (request.set("Content-Type", "application/json"), json_body),
+        ContentType::Graphql => (
+            request.set("Content-Type", "application/graphql"),
+            QUERY.to_string(),
+        ),
+        ContentType::TextPlain => (request.set("Content-Type", "text/plain"), json_body),
+        ContentType::Missing => (request, json_body),
+    };
+    let response = context.send_raw(request, &body)?;
+    Ok(get_json(context, Ok(response)).is_ok_and(|body| has_typename(&body)))
+}
+
+/// Fails unless the server answers exactly the `allowed` content types.
+pub(crate) fn check_content_types(
+    context: &Context,
+    url: &str,
+    auth: &Auth,
+    allowed: &[ContentType],
+) -> Outcome {
+    let mut mismatches = Vec::new();
+    for content_type in ContentType::ALL {
+        let accepted = match answers_content_type(context, url, auth, content_type) {
+            Ok(accepted) => accepted,
+            Err(err) => return Outcome::Failed(err),
+        };
+        if accepted != allowed.contains(&content_type) {
+            let verb = if accepted { "accepted" } else { "rejected" };
+            mismatches.push(format!("`{}` was {verb}", content_type.name()));
+        }
+    }
+    if mismatches.is_empty() {
+        Outcome::Passed
+    } else {
+        Outcome::Failed(Error::ContentTypes(mismatches))
+    }
+}
+
+/// A document no server should have registered, whose hash is sent alongside [`QUERY`] when
+/// checking that registrations are verified.
+const UNREGISTERED: &str = "query GraphQLCheckHashMismatch{__typename}";
+
+/// Tries to register [`QUERY`] as an automatic persisted query under the hash of a different
+/// document. A server which doesn't check the hash would then serve [`QUERY`] to anyone asking for
+/// that other document, so both the registration and a later lookup of the hash must be rejected.
+/// Skipped if the server doesn't support persisted queries at all.
+pub(crate) fn check_persisted_query_hash(context: &Context, url: &str, auth: &Auth) -> Outcome {
+    if !supports_persisted_queries(context, url, auth) {
+        return Outcome::Skipped;
+    }
+    let extensions = json!({
+        "persistedQuery": {
+            "version": 1,
+            "sha256Hash": sha256_hex(UNREGISTERED),
+        }
+    });
+    let register = json!({
+        "query": QUERY,
+        "extensions": extensions,
+    });
+    if returns_typename(context, url, auth, register)
+        || returns_typename(context, url, auth, json!({ "extensions": extensions }))
+    {
+        Outcome::Failed(Error::PersistedQueryHashIgnored)
+    } else {
+        Outcome::Passed
+    }
+}
+
+#[cfg(test)]
+mod test_helpers {
+    use super::*;
+
+    #[test]
+    fn persisted_query_hash() {
+        assert_eq!(
+            sha256_hex(QUERY),
+            "32f947c186caae1cc059fd6c82d6b530d28fab36d6ff9168df1d12c9013dfcce"
+        );
+    }
+
+    #[test]
+    fn batch_response() {
+        let single = json!({"data": {"__typename": "Query"}});
+        assert!(is_batch_response(&json!([single, single]), 2));
+        assert!(!is_batch_response(&json!([single]), 2));
+        assert!(!is_batch_response(&single, 2));
+        assert!(!is_batch_response(&json!([single, {"errors": []}]), 2));
+    }
+}
+
+#[cfg(test)]
+mod test_check_persisted_query_hash {
+    use std::collections::HashMap;
+    use std::sync::Mutex;
+
+    use super::*;
+    use crate::testing::context;
+
+    /// A server supporting persisted queries, which only checks hashes if `verify`.
+    fn server(verify: bool) -> Context {
+        let registered = Mutex::new(HashMap::new());
+        context(move |request| {
+            let body = request.body.clone().unwrap_or_default();
+            let hash = body
+                .pointer("/extensions/persistedQuery/sha256Hash")
+                .and_then(Value::as_str)
+                .map(String::from);
+            let query = body.get("query").and_then(Value::as_str).map(String::from);
+            let mut registered = registered.lock().unwrap();
+            let query = match (hash, query) {
+                (Some(hash), Some(query)) if verify && hash != sha256_hex(&query) => {
+                    return Ok(HttpResponse::json(
+                        400,
+                        &json!({"errors": [{"message": "provided sha does not match query"}]}),
+                    ));
+                }
+                (Some(hash), Some(query)) => {
+                    registered.insert(hash, query.clone());
+                    Some(query)
+                }
+                (Some(hash), None) => registered.get(&hash).cloned(),
+                (None, query) => query,
+            };
+            Ok(match query {
+                Some(_) => HttpResponse::json(200, &json!({"data": {"__typename": "Query"}})),
+                None => HttpResponse::json(
+                    200,
+                    &json!({"errors": [{"message": "PersistedQueryNotFound"}]}),
+                ),
+            })
+        })
+    }
+
+    #[test]
+    fn verified() {
+        assert_eq!(
+            check_persisted_query_hash(
+                &server(true),
+                "https://example.com/graphql",
+                &Auth::Disabled
+            ),
+            Outcome::Passed
+        );
+    }
+
+    #[test]
+    fn ignored() {
+        assert_eq!(
+            check_persisted_query_hash(
+                &server(false),
+                "https://example.com/graphql",
+                &Auth::Disabled
+            ),
+            Outcome::Failed(Error::PersistedQueryHashIgnored)
+        );
+    }
+
+    #[test]
+    fn unsupported() {
+        let context = context(|_| {
+            Ok(HttpResponse::json(
+                200,
+                &json!({"errors": [{"message": "PersistedQueryNotSupported"}]}),
+            ))
+        });
+        assert_eq!(
+            check_persisted_query_hash(&context, "https://example.com/graphql", &Auth::Disabled),
+            Outcome::Skipped
+        );
+    }
+}
+
+#[cfg(test)]
+mod test_check_content_types {
+    use super::*;
+    use crate::testing::context;
+    use crate::TransportError;
+
+    /// Parses any JSON body, whatever it's labeled as, like a server without CSRF protection.
+    fn server() -> Context {
+        context(|request| {
+            let body = request.raw_body.as_deref().unwrap_or_default();
+            Ok(match serde_json::from_str::<Value>(body) {
+                Ok(_) => HttpResponse::json(200, &json!({"data": {"__typename": "Query"}})),
+                Err(_) => HttpResponse::json(400, &json!({"errors": [{"message": "Bad"}]})),
+            })
+        })
+    }
+
+    #[test]
+    fn accepted() {
+        assert_eq!(
+            accepted_content_types(&server(), "https://example.com/graphql", &Auth::Disabled),
+            [
+                ContentType::Json,
+                ContentType::TextPlain,
+                ContentType::Missing
+            ]
+        );
+    }
+
+    #[test]
+    fn mismatches() {
+        assert_eq!(
+            check_content_types(
+                &server(),
+                "https://example.com/graphql",
+                &Auth::Disabled,
+                &[ContentType::Json, ContentType::Graphql]
+            ),
+            Outcome::Failed(Error::ContentTypes(vec![
+                "`application/graphql` was rejected".to_string(),
+                "`text/plain` was accepted".to_string(),
+                "`none` was accepted".to_string(),
+            ]))
+        );
+    }
+
+    #[test]
+    fn unreachable() {
+        let context = context(|_| {
+            Err(Error::ConnectionRefused(TransportError::new(
+                "Connection refused",
+            )))
+        });
+        let url = "https://example.com/graphql";
+        assert!(accepted_content_types(&context, url, &Auth::Disabled).is_empty());
+        assert!(matches!(
+            check_content_types(&context, url, &Auth::Disabled, &[ContentType::Json]),
+            Outcome::Failed(Error::ConnectionRefused(_))
+        ));
+    }
+
+    #[test]
+    fn parse() {
+        assert_eq!(ContentType::parse("none").unwrap(), ContentType::Missing);
+        assert!(ContentType::parse("text/html").is_err());
+    }
+}
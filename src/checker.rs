@@ -0,0 +1,1469 @@
+use std::cell::Cell;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::fmt::{self, Debug, Formatter};
+use std::net::TcpStream;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{channel, Receiver};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use ureq::Agent;
+
+use crate::access::check_access;
+use crate::caching::{check_cache_control, check_cdn_cache};
+use crate::capabilities::{
+    check_content_types, check_persisted_query_hash, probe_capabilities_with, Capabilities,
+    ContentType,
+};
+use crate::compliance::run_compliance_checks;
+use crate::federation::{check_entities, check_smoke_query};
+use crate::graphql::{AllowedError, ErrorsPolicy};
+use crate::http::{
+    jittered, new_agent, secret_of, validate_url, Context, RateLimit, Retried, Throttled,
+};
+use crate::latency::{check_concurrency, check_latency, LatencyStats};
+use crate::profile::run_profile_checks;
+use crate::robustness::run_robustness_checks;
+use crate::schema::{check_schema_matches, fetch_schema_with, AllowedChanges, Schema};
+use crate::stability::{check_stability, Signature};
+use crate::validation::check_unknown_field;
+use crate::{
+    basic_query, check_auth_semantics, check_invalid_auth_rejected, check_router_health,
+    deserialize_millis, run_each_check_with, serialize_millis, AccessMatrix, Auth, Capture, Check,
+    CheckResult, Error, Event, Expectations, Fact, Introspection, Outcome, Profile, Rejection,
+    Severity, Subgraph, TokenProvider, Transport,
+};
+
+/// How long [`Checker::wait_until_healthy`] waits between attempts, before jitter.
+const POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+type OnStarted = Arc<dyn Fn(Check) + Send + Sync>;
+type OnFinished = Arc<dyn Fn(&CheckResult) + Send + Sync>;
+
+/// Configures and runs the checks against a single endpoint.
+///
+/// ```no_run
+/// use graphql_check_action::Checker;
+///
+/// let results = Checker::new("https://example.com/graphql")
+///     .auth(graphql_check_action::Auth::Enabled {
+///         header: "Authorization: Bearer token".into(),
+///     })
+///     .require_subgraph()
+///     .disallow_introspection()
+///     .run();
+/// ```
+#[derive(Clone)]
+pub struct Checker<'a> {
+    url: &'a str,
+    auth: Auth<'a>,
+    invalid_auth: Option<Auth<'a>>,
+    unauthenticated: Option<Rejection>,
+    auth_semantics: Option<(Auth<'a>, String)>,
+    access: Option<AccessMatrix>,
+    cache_control: Option<String>,
+    latency_samples: Option<(u32, Option<Duration>)>,
+    stability_runs: u32,
+    concurrency: Option<(u32, Option<Duration>)>,
+    cdn_cache: bool,
+    persisted_query_hash: bool,
+    unknown_field: bool,
+    content_types: Option<Vec<ContentType>>,
+    robustness: bool,
+    compliance: bool,
+    wrong_variables: Option<Value>,
+    subgraph: Subgraph,
+    introspection: Option<Introspection>,
+    timeout: Option<Duration>,
+    retries: u32,
+    errors_policy: ErrorsPolicy,
+    allowed_errors: Vec<AllowedError>,
+    combine_probes: bool,
+    entities: Option<BTreeMap<String, Value>>,
+    smoke_query: Option<(String, Vec<String>)>,
+    router_health: Option<String>,
+    compare_schema: Option<(String, AllowedChanges)>,
+    profile: Option<Profile>,
+    skipped: BTreeSet<Check>,
+    severities: BTreeMap<Check, Severity>,
+    transport: Option<Arc<dyn Transport>>,
+    capture: Option<Capture>,
+    rate_limit: Option<RateLimit>,
+    cancellation: Option<CancellationToken>,
+    on_check_started: Option<OnStarted>,
+    on_check_finished: Option<OnFinished>,
+}
+
+impl Debug for Checker<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Checker")
+            .field("url", &self.url)
+            .field("auth", &&self.auth)
+            .field("invalid_auth", &self.invalid_auth)
+            .field("unauthenticated", &self.unauthenticated)
+            .field("auth_semantics", &self.auth_semantics)
+            .field("access", &self.access)
+            .field("cache_control", &self.cache_control)
+            .field("latency_samples", &self.latency_samples)
+            .field("stability_runs", &self.stability_runs)
+            .field("concurrency", &self.concurrency)
+            .field("cdn_cache", &self.cdn_cache)
+            .field("persisted_query_hash", &self.persisted_query_hash)
+            .field("unknown_field", &self.unknown_field)
+            .field("content_types", &self.content_types)
+            .field("robustness", &self.robustness)
+            .field("compliance", &self.compliance)
+            .field("wrong_variables", &self.wrong_variables)
+            .field("subgraph", &self.subgraph)
+            .field("introspection", &self.introspection)
+            .field("timeout", &self.timeout)
+            .field("retries", &self.retries)
+            .field("errors_policy", &self.errors_policy)
+            .field("allowed_errors", &self.allowed_errors)
+            .field("combine_probes", &self.combine_probes)
+            .field("entities", &self.entities)
+            .field("smoke_query", &self.smoke_query)
+            .field("router_health", &self.router_health)
+            .field("compare_schema", &self.compare_schema)
+            .field("profile", &self.profile)
+            .field("skipped", &self.skipped)
+            .field("severities", &self.severities)
+            .field("transport", &self.transport)
+            .field("capture", &self.capture)
+            .field("rate_limit", &self.rate_limit)
+            .field("cancellation", &self.cancellation)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<'a> Checker<'a> {
+    /// Creates a checker with no authentication which expects `url` not to be a subgraph.
+    pub const fn new(url: &'a str) -> Self {
+        Self {
+            url,
+            auth: Auth::Disabled,
+            invalid_auth: None,
+            unauthenticated: None,
+            auth_semantics: None,
+            access: None,
+            cache_control: None,
+            latency_samples: None,
+            stability_runs: 1,
+            concurrency: None,
+            cdn_cache: false,
+            persisted_query_hash: false,
+            unknown_field: false,
+            content_types: None,
+            robustness: false,
+            compliance: false,
+            wrong_variables: None,
+            subgraph: Subgraph::NotASubgraph,
+            introspection: None,
+            timeout: None,
+            retries: 0,
+            errors_policy: ErrorsPolicy::Fail,
+            allowed_errors: Vec::new(),
+            combine_probes: false,
+            entities: None,
+            smoke_query: None,
+            router_health: None,
+            compare_schema: None,
+            profile: None,
+            skipped: BTreeSet::new(),
+            severities: BTreeMap::new(),
+            transport: None,
+            capture: None,
+            rate_limit: None,
+            cancellation: None,
+            on_check_started: None,
+            on_check_finished: None,
+        }
+    }
+
+    /// The endpoint being checked.
+    pub const fn url(&self) -> &'a str {
+        self.url
+    }
+
+    #[must_use]
+    pub fn auth(mut self, auth: Auth<'a>) -> Self {
+        self.auth = auth;
+        self
+    }
+
+    /// Also runs [`Check::InvalidAuthRejected`], sending `header` (e.g., an expired token) in
+    /// place of the real auth header and expecting the server to reject it.
+    #[must_use]
+    pub fn invalid_auth(mut self, header: &'a str) -> Self {
+        self.invalid_auth = Some(Auth::Enabled {
+            header: header.into(),
+        });
+        self
+    }
+
+    /// How the server is expected to reject requests without valid auth. Without this, any GraphQL
+    /// error or `4xx` status counts.
+    #[must_use]
+    pub fn unauthenticated(mut self, rejection: Rejection) -> Self {
+        self.unauthenticated = Some(rejection);
+        self
+    }
+
+    /// Also runs [`Check::AuthSemantics`]: a request without credentials must get `401` (or an
+    /// `UNAUTHENTICATED` error) while `privileged_query` sent with `unprivileged_header`, valid
+    /// credentials which aren't allowed to run it, must get `403` (or a `FORBIDDEN` error).
+    #[must_use]
+    pub fn auth_semantics(
+        mut self,
+        unprivileged_header: &'a str,
+        privileged_query: impl Into<String>,
+    ) -> Self {
+        self.auth_semantics = Some((
+            Auth::Enabled {
+                header: unprivileged_header.into(),
+            },
+            privileged_query.into(),
+        ));
+        self
+    }
+
+    /// Also runs [`Check::CacheControl`], expecting the `Cache-Control` header of an authenticated
+    /// response to have every directive of `policy`, e.g., `no-store`.
+    #[must_use]
+    pub fn cache_control(mut self, policy: impl Into<String>) -> Self {
+        self.cache_control = Some(policy.into());
+        self
+    }
+
+    /// Also runs [`Check::Latency`], which sends the basic query `samples` times, one after
+    /// another, reporting the p50, p95 and max in [`CheckReport::latency`]. Fails if the p95 is
+    /// over `max_p95`, which is steadier than timing a single request over a jittery network.
+    #[must_use]
+    pub const fn latency_samples(mut self, samples: u32, max_p95: Option<Duration>) -> Self {
+        self.latency_samples = Some((samples, max_p95));
+        self
+    }
+
+    /// Also runs [`Check::Concurrency`], which sends the basic query `concurrency` times at once
+    /// and fails unless every one succeeds, within `budget` if given. A too small connection pool
+    /// or worker count often only shows up like this. How long each took is reported in
+    /// [`CheckReport::concurrent_latency`].
+    #[must_use]
+    pub const fn concurrency(mut self, concurrency: u32, budget: Option<Duration>) -> Self {
+        self.concurrency = Some((concurrency, budget));
+        self
+    }
+
+    /// Runs every check `runs` times, reporting the results of the first run followed by
+    /// [`Check::Stable`], which fails if any check's outcome changed between runs. Intermittent
+    /// problems, like one replica out of several being broken, often pass a single run.
+    #[must_use]
+    pub const fn stability_runs(mut self, runs: u32) -> Self {
+        self.stability_runs = runs;
+        self
+    }
+
+    /// Also runs [`Check::CdnCache`], for APIs behind a CDN which caches persisted queries sent as
+    /// `GET`s. A persisted query sent twice without auth should be a cache hit the second time,
+    /// and with auth should never be.
+    #[must_use]
+    pub const fn cdn_cache(mut self) -> Self {
+        self.cdn_cache = true;
+        self
+    }
+
+    /// Also runs [`Check::PersistedQueryHash`], which fails if the server registers an automatic
+    /// persisted query under a hash that doesn't match its document. Skipped if the server doesn't
+    /// support persisted queries.
+    #[must_use]
+    pub const fn persisted_query_hash(mut self) -> Self {
+        self.persisted_query_hash = true;
+        self
+    }
+
+    /// Also runs [`Check::UnknownFieldRejected`], which queries a field that doesn't exist and
+    /// fails unless the server rejects the whole operation with a validation error.
+    #[must_use]
+    pub const fn unknown_field(mut self) -> Self {
+        self.unknown_field = true;
+        self
+    }
+
+    /// Also runs [`Check::ContentTypes`], which sends the basic query labeled as each
+    /// [`ContentType`] and fails unless exactly the `allowed` ones are answered. Leaving out
+    /// [`ContentType::TextPlain`] and [`ContentType::Missing`] requires CSRF protection.
+    #[must_use]
+    pub fn content_types(mut self, allowed: Vec<ContentType>) -> Self {
+        self.content_types = Some(allowed);
+        self
+    }
+
+    /// Also runs the robustness checks, which send deliberately broken requests and fail if the
+    /// server crashes or answers them instead of rejecting them, starting with
+    /// [`Check::MalformedJsonRejected`].
+    #[must_use]
+    pub const fn robustness(mut self) -> Self {
+        self.robustness = true;
+        self
+    }
+
+    /// The operation [`Check::WrongVariablesRejected`] sends, as a request body with `query` and
+    /// `variables` of the wrong type for it, e.g., a string where an `Int` is expected. Without
+    /// one, a string is sent for the `Boolean!` of `@skip`, since that works with any schema.
+    #[must_use]
+    pub fn wrong_variables(mut self, body: Value) -> Self {
+        self.wrong_variables = Some(body);
+        self
+    }
+
+    /// Also runs the compliance checks, which fail if the server handles HTTP differently than the
+    /// [GraphQL over HTTP](https://graphql.github.io/graphql-over-http/draft/) spec says, starting
+    /// with [`Check::AcceptNegotiated`].
+    #[must_use]
+    pub const fn compliance(mut self) -> Self {
+        self.compliance = true;
+        self
+    }
+
+    /// Also runs [`Check::RoleAccess`], running each operation of `matrix` as every role which
+    /// should or shouldn't be able to.
+    #[must_use]
+    pub fn access(mut self, matrix: AccessMatrix) -> Self {
+        self.access = Some(matrix);
+        self
+    }
+
+    #[must_use]
+    pub const fn subgraph(mut self, subgraph: Subgraph) -> Self {
+        self.subgraph = subgraph;
+        self
+    }
+
+    /// Requires the endpoint to be a subgraph which is protected by authentication.
+    #[must_use]
+    pub const fn require_subgraph(self) -> Self {
+        self.subgraph(Subgraph::Secure)
+    }
+
+    /// Defaults to [`Introspection::Allow`] for subgraphs and [`Introspection::Disallow`] otherwise.
+    #[must_use]
+    pub const fn introspection(mut self, introspection: Introspection) -> Self {
+        self.introspection = Some(introspection);
+        self
+    }
+
+    #[must_use]
+    pub const fn allow_introspection(self) -> Self {
+        self.introspection(Introspection::Allow)
+    }
+
+    #[must_use]
+    pub const fn disallow_introspection(self) -> Self {
+        self.introspection(Introspection::Disallow)
+    }
+
+    /// Limits how long any single request may take, 60 seconds by default. A request which takes
+    /// longer fails with [`Error::Timeout`].
+    #[must_use]
+    pub const fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Tries each request up to `retries` more times if it failed in a way that may not happen
+    /// again: no response at all, or a 429, 502, 503 or 504. Anything else is never retried, since
+    /// it would only fail the same way. Every retry is listed in [`CheckReport::retried`].
+    #[must_use]
+    pub const fn retries(mut self, retries: u32) -> Self {
+        self.retries = retries;
+        self
+    }
+
+    /// What the checks do with a response which has `errors` next to `data`. By default any
+    /// `errors` fail the check reading the response, which breaks checks against schemas with
+    /// fields known to be flaky.
+    #[must_use]
+    pub const fn errors_policy(mut self, policy: ErrorsPolicy) -> Self {
+        self.errors_policy = policy;
+        self
+    }
+
+    /// The errors which [`ErrorsPolicy::Allowlist`] lets through, e.g., a known deprecation
+    /// warning which the gateway reports as an error, so one known error doesn't mean skipping a
+    /// whole check.
+    #[must_use]
+    pub fn allowed_errors(mut self, allowed: Vec<AllowedError>) -> Self {
+        self.allowed_errors = allowed;
+        self
+    }
+
+    /// Asks for `__typename`, `_service` and `__schema` in a single request, only falling back to
+    /// separate requests for whatever the server won't answer that way. Roughly halves the
+    /// requests made against servers which return partial errors rather than rejecting the whole
+    /// document.
+    #[must_use]
+    pub const fn combine_probes(mut self) -> Self {
+        self.combine_probes = true;
+        self
+    }
+
+    /// Also runs [`Check::EntitiesResolvable`], looking up one entity of each type with a `@key` in
+    /// the subgraph's SDL. `representations` are used for the types they're given for (keyed by
+    /// type name, e.g., `{"__typename": "Product", "upc": "1"}`), every other type gets a
+    /// synthetic representation which shouldn't match anything real, so a working resolver
+    /// returns `null` rather than an error.
+    #[must_use]
+    pub fn check_entities(mut self, representations: BTreeMap<String, Value>) -> Self {
+        self.entities = Some(representations);
+        self
+    }
+
+    /// Also runs [`Check::SmokeQuery`], sending `query` (which should span several subgraphs)
+    /// through the router at the endpoint. If `subgraphs` are given, the router's query plan must
+    /// fetch from each of them, which needs `plugins.experimental.expose_query_plan` enabled.
+    #[must_use]
+    pub fn smoke_query(mut self, query: impl Into<String>, subgraphs: Vec<String>) -> Self {
+        self.smoke_query = Some((query.into(), subgraphs));
+        self
+    }
+
+    /// Also runs [`Check::RouterHealth`] against the health check of an Apollo Router in front of
+    /// the endpoint, e.g., `http://localhost:8088/health`.
+    #[must_use]
+    pub fn router_health(mut self, health_url: impl Into<String>) -> Self {
+        self.router_health = Some(health_url.into());
+        self
+    }
+
+    /// Also runs [`Check::SchemaMatches`], comparing the schema of the endpoint with the one of
+    /// `compare_url` (fetched with the same auth), e.g., staging with production or blue with
+    /// green. The check fails with every difference if there are changes which aren't `allowed`.
+    #[must_use]
+    pub fn compare_schema(
+        mut self,
+        compare_url: impl Into<String>,
+        allowed: AllowedChanges,
+    ) -> Self {
+        self.compare_schema = Some((compare_url.into(), allowed));
+        self
+    }
+
+    /// Also runs the checks of `profile`, for the server behind the endpoint.
+    #[must_use]
+    pub const fn profile(mut self, profile: Profile) -> Self {
+        self.profile = Some(profile);
+        self
+    }
+
+    /// Reports `check` as [`Outcome::Skipped`], whatever its outcome. Its requests are still sent,
+    /// since other checks may share them, so skipping a check doesn't make a run send less.
+    #[must_use]
+    pub fn skip(mut self, check: Check) -> Self {
+        self.skipped.insert(check);
+        self
+    }
+
+    /// Overrides how much a failure of `check` matters, e.g., to only warn about it.
+    #[must_use]
+    pub fn severity(mut self, check: Check, severity: Severity) -> Self {
+        self.severities.insert(check, severity);
+        self
+    }
+
+    /// Sends every request with `agent`, for proxies, custom TLS settings or middleware. By default
+    /// a new agent is created for each run with [`new_agent`](crate::new_agent).
+    ///
+    /// Middleware like retries, logging or request signing is added when building the agent:
+    ///
+    /// ```no_run
+    /// use graphql_check_action::ureq::{AgentBuilder, MiddlewareNext, Request};
+    /// use graphql_check_action::Checker;
+    ///
+    /// let agent = AgentBuilder::new()
+    ///     .middleware(|request: Request, next: MiddlewareNext| {
+    ///         next.handle(request.set("X-Request-Source", "graphql-check"))
+    ///     })
+    ///     .build();
+    /// let results = Checker::new("https://example.com/graphql").agent(agent).run();
+    /// ```
+    #[must_use]
+    pub fn agent(self, agent: Agent) -> Self {
+        self.transport(agent)
+    }
+
+    /// Sends every request with `transport` instead of over HTTP, e.g., to test against canned
+    /// responses.
+    #[must_use]
+    pub fn transport(mut self, transport: impl Transport + 'static) -> Self {
+        self.transport = Some(Arc::new(transport));
+        self
+    }
+
+    /// Records every request and response made by the checks in `capture`.
+    #[must_use]
+    pub fn capture(mut self, capture: &Capture) -> Self {
+        self.capture = Some(capture.clone());
+        self
+    }
+
+    /// Sends no more requests than `rate_limit` allows, counting every probe and retry. Heavy
+    /// checks like [`Checker::latency_samples`] or [`Checker::concurrency`] and a long
+    /// [`Monitor`](crate::Monitor) then stay polite toward a production endpoint. Every run of
+    /// this checker shares the limit.
+    #[must_use]
+    pub fn rate_limit(mut self, rate_limit: &RateLimit) -> Self {
+        self.rate_limit = Some(rate_limit.clone());
+        self
+    }
+
+    /// Stops making requests once `token` is cancelled. Checks which haven't finished by then fail
+    /// with [`Error::Cancelled`](crate::Error::Cancelled).
+    #[must_use]
+    pub fn cancellation(mut self, token: &CancellationToken) -> Self {
+        self.cancellation = Some(token.clone());
+        self
+    }
+
+    /// Called whenever a check starts. Checks which share requests start together.
+    #[must_use]
+    pub fn on_check_started(mut self, callback: impl Fn(Check) + Send + Sync + 'static) -> Self {
+        self.on_check_started = Some(Arc::new(callback));
+        self
+    }
+
+    /// Called with the result of each check as soon as it's known.
+    #[must_use]
+    pub fn on_check_finished(
+        mut self,
+        callback: impl Fn(&CheckResult) + Send + Sync + 'static,
+    ) -> Self {
+        self.on_check_finished = Some(Arc::new(callback));
+        self
+    }
+
+    /// Runs every check, returning the outcome of each, including the ones that passed or were
+    /// skipped.
+    pub fn run(&self) -> Vec<CheckResult> {
+        self.report().checks
+    }
+
+    /// Runs every check, returning everything learned about the endpoint along the way.
+    pub fn report(&self) -> CheckReport {
+        let start = Instant::now();
+        let mut report = CheckReport::default();
+        self.run_with(&mut |event| match event {
+            Event::Finished(result) => report.checks.push(result),
+            Event::Observed(Fact::IsSubgraph(is_subgraph)) => {
+                report.is_subgraph = Some(is_subgraph);
+            }
+            Event::Observed(Fact::IntrospectionEnabled(enabled)) => {
+                report.introspection_enabled = Some(enabled);
+            }
+            Event::Observed(Fact::Negotiated(negotiated)) => report.negotiated = negotiated,
+            Event::Observed(Fact::Latency(stats)) => report.latency = Some(stats),
+            Event::Observed(Fact::ConcurrentLatency(stats)) => {
+                report.concurrent_latency = Some(stats);
+            }
+            Event::Observed(Fact::Retried(retried)) => report.retried = retried,
+            Event::Observed(Fact::Throttled(throttled)) => report.throttled = throttled,
+            Event::Observed(Fact::Schema(schema)) => report.schema = Some(schema),
+            Event::Started(_) => {}
+        });
+        report.duration = start.elapsed();
+        report
+    }
+
+    /// Runs every check on a background thread, sending each result as soon as it's known so
+    /// progress can be shown for slow endpoints. The channel closes once every check has finished.
+    pub fn stream(&self) -> Receiver<CheckResult> {
+        let (sender, receiver) = channel();
+        let url = self.url.to_string();
+        let header = OwnedAuth::from(self.auth.clone());
+        let invalid_header = self.invalid_auth.clone().map(OwnedAuth::from);
+        let auth_semantics = self
+            .auth_semantics
+            .as_ref()
+            .map(|(unprivileged, query)| (OwnedAuth::from(unprivileged.clone()), query.clone()));
+        let checker = Checker {
+            url: "",
+            auth: Auth::Disabled,
+            invalid_auth: None,
+            auth_semantics: None,
+            ..self.clone()
+        };
+        thread::spawn(move || {
+            let checker = Checker {
+                url: &url,
+                auth: header.auth(),
+                invalid_auth: invalid_header.as_ref().map(OwnedAuth::auth),
+                auth_semantics: auth_semantics
+                    .as_ref()
+                    .map(|(header, query)| (header.auth(), query.clone())),
+                ..checker
+            };
+            // The receiver being dropped means nobody is listening anymore, which is fine.
+            checker.run_with(&mut |event| {
+                if let Event::Finished(result) = event {
+                    let _ = sender.send(result);
+                }
+            });
+        });
+        receiver
+    }
+
+    /// Runs every check, calling the configured callbacks and timing each check before the event
+    /// is passed on to `on_event`.
+    fn run_with(&self, on_event: &mut dyn FnMut(Event)) {
+        let context = self.context();
+        let mut started = HashMap::new();
+        let mut handle = |mut event: Event| {
+            match &mut event {
+                Event::Started(check) => {
+                    started.insert(*check, Instant::now());
+                    // Anything sent before belongs to an earlier check.
+                    context.take_last_request();
+                    if let Some(callback) = &self.on_check_started {
+                        callback(*check);
+                    }
+                }
+                Event::Finished(result) => {
+                    if let Some(start) = started.get(&result.check) {
+                        result.duration = start.elapsed();
+                    }
+                    if result.error().is_some() {
+                        result.request = context.take_last_request();
+                    }
+                    if let Outcome::Failed(Error::Timeout { check, .. }) = &mut result.outcome {
+                        *check = Some(result.check);
+                    }
+                    if self.skipped.contains(&result.check) {
+                        result.outcome = Outcome::Skipped;
+                    }
+                    if let Some(severity) = self.severities.get(&result.check) {
+                        result.severity = *severity;
+                    }
+                    if let Some(callback) = &self.on_check_finished {
+                        callback(result);
+                    }
+                }
+                Event::Observed(_) => {}
+            }
+            on_event(event);
+        };
+        let mut first_run = Vec::new();
+        self.run_checks(&context, &mut |event| {
+            if let Event::Finished(result) = &event {
+                first_run.push(Signature::new(result));
+            }
+            handle(event);
+        });
+        if self.stability_runs > 1 {
+            handle(Event::Started(Check::Stable));
+            let mut runs = vec![first_run];
+            for _ in 1..self.stability_runs {
+                let mut run = Vec::new();
+                self.run_checks(&context, &mut |event| {
+                    if let Event::Finished(result) = &event {
+                        run.push(Signature::new(result));
+                    }
+                });
+                runs.push(run);
+            }
+            handle(Event::Finished(CheckResult::new(
+                Check::Stable,
+                check_stability(&runs),
+            )));
+        }
+        let retried = context.retry_history();
+        if !retried.is_empty() {
+            handle(Event::Observed(Fact::Retried(retried)));
+        }
+        let throttled = context.throttle_history();
+        if !throttled.is_empty() {
+            handle(Event::Observed(Fact::Throttled(throttled)));
+        }
+    }
+
+    /// Runs every check once, passing each event straight to `handle`.
+    fn run_checks(&self, context: &Context, on_event: &mut dyn FnMut(Event)) {
+        let basic_query_failed = Cell::new(false);
+        let mut handle = |event: Event| {
+            if let Event::Finished(result) = &event {
+                if result.check == Check::BasicQuery {
+                    basic_query_failed.set(matches!(result.outcome, Outcome::Failed(_)));
+                }
+            }
+            on_event(event);
+        };
+        run_each_check_with(
+            context,
+            self.url,
+            &self.auth,
+            &Expectations {
+                subgraph: self.subgraph,
+                introspection: self.effective_introspection(),
+                unauthenticated: self.unauthenticated.as_ref(),
+            },
+            self.combine_probes,
+            &mut handle,
+        );
+        if let Some((samples, max_p95)) = self.latency_samples {
+            handle(Event::Started(Check::Latency));
+            let (outcome, stats) = check_latency(context, self.url, &self.auth, samples, max_p95);
+            if let Some(stats) = stats {
+                handle(Event::Observed(Fact::Latency(stats)));
+            }
+            handle(Event::Finished(CheckResult::new(Check::Latency, outcome)));
+        }
+        if let Some((concurrency, budget)) = self.concurrency {
+            handle(Event::Started(Check::Concurrency));
+            let (outcome, stats) =
+                check_concurrency(context, self.url, &self.auth, concurrency, budget);
+            if let Some(stats) = stats {
+                handle(Event::Observed(Fact::ConcurrentLatency(stats)));
+            }
+            handle(Event::Finished(CheckResult::new(
+                Check::Concurrency,
+                outcome,
+            )));
+        }
+        if let Some(policy) = &self.cache_control {
+            handle(Event::Started(Check::CacheControl));
+            let outcome = check_cache_control(context, self.url, &self.auth, policy);
+            handle(Event::Finished(CheckResult::new(
+                Check::CacheControl,
+                outcome,
+            )));
+        }
+        if self.cdn_cache {
+            handle(Event::Started(Check::CdnCache));
+            let outcome = check_cdn_cache(context, self.url, &self.auth);
+            handle(Event::Finished(CheckResult::new(Check::CdnCache, outcome)));
+        }
+        if self.persisted_query_hash {
+            handle(Event::Started(Check::PersistedQueryHash));
+            let outcome = check_persisted_query_hash(context, self.url, &self.auth);
+            handle(Event::Finished(CheckResult::new(
+                Check::PersistedQueryHash,
+                outcome,
+            )));
+        }
+        if let Some(allowed) = &self.content_types {
+            handle(Event::Started(Check::ContentTypes));
+            let outcome = check_content_types(context, self.url, &self.auth, allowed);
+            handle(Event::Finished(CheckResult::new(
+                Check::ContentTypes,
+                outcome,
+            )));
+        }
+        if self.unknown_field {
+            handle(Event::Started(Check::UnknownFieldRejected));
+            let outcome = check_unknown_field(context, self.url, &self.auth);
+            handle(Event::Finished(CheckResult::new(
+                Check::UnknownFieldRejected,
+                outcome,
+            )));
+        }
+        if self.compliance {
+            run_compliance_checks(context, self.url, &self.auth, &mut handle);
+        }
+        if self.robustness {
+            run_robustness_checks(
+                context,
+                self.url,
+                &self.auth,
+                self.wrong_variables.as_ref(),
+                &mut handle,
+            );
+        }
+        if let Some(invalid_auth) = &self.invalid_auth {
+            handle(Event::Started(Check::InvalidAuthRejected));
+            let outcome = check_invalid_auth_rejected(
+                context,
+                self.url,
+                invalid_auth,
+                self.unauthenticated.as_ref(),
+            );
+            handle(Event::Finished(CheckResult::new(
+                Check::InvalidAuthRejected,
+                outcome,
+            )));
+        }
+        if let Some((unprivileged, privileged_query)) = &self.auth_semantics {
+            handle(Event::Started(Check::AuthSemantics));
+            let outcome = check_auth_semantics(context, self.url, unprivileged, privileged_query);
+            handle(Event::Finished(CheckResult::new(
+                Check::AuthSemantics,
+                outcome,
+            )));
+        }
+        if let Some(matrix) = &self.access {
+            handle(Event::Started(Check::RoleAccess));
+            let outcome = check_access(context, self.url, matrix);
+            handle(Event::Finished(CheckResult::new(
+                Check::RoleAccess,
+                outcome,
+            )));
+        }
+        if let Some(representations) = &self.entities {
+            handle(Event::Started(Check::EntitiesResolvable));
+            let outcome = check_entities(context, self.url, &self.auth, representations);
+            handle(Event::Finished(CheckResult::new(
+                Check::EntitiesResolvable,
+                outcome,
+            )));
+        }
+        if let Some((query, subgraphs)) = &self.smoke_query {
+            handle(Event::Started(Check::SmokeQuery));
+            let outcome = check_smoke_query(context, self.url, &self.auth, query, subgraphs);
+            handle(Event::Finished(CheckResult::new(
+                Check::SmokeQuery,
+                outcome,
+            )));
+        }
+        if let Some(health_url) = &self.router_health {
+            handle(Event::Started(Check::RouterHealth));
+            let outcome = check_router_health(context, health_url, basic_query_failed.get());
+            handle(Event::Finished(CheckResult::new(
+                Check::RouterHealth,
+                outcome,
+            )));
+        }
+        if let Some((compare_url, allowed)) = &self.compare_schema {
+            handle(Event::Started(Check::SchemaMatches));
+            let outcome =
+                check_schema_matches(context, self.url, &self.auth, compare_url, *allowed);
+            handle(Event::Finished(CheckResult::new(
+                Check::SchemaMatches,
+                outcome,
+            )));
+        }
+        if let Some(profile) = self.profile {
+            run_profile_checks(context, self.url, &self.auth, profile, &mut handle);
+        }
+    }
+
+    /// Probes for optional features of the endpoint, using the same transport as the checks.
+    pub fn capabilities(&self) -> Capabilities {
+        probe_capabilities_with(&self.context(), self.url, &self.auth)
+    }
+
+    /// Fetches the schema of the endpoint, using the same transport as the checks.
+    pub fn schema(&self) -> Option<Schema> {
+        fetch_schema_with(&self.context(), self.url, &self.auth)
+    }
+
+    /// Sends the basic query once, e.g., for a health check.
+    pub fn healthy(&self) -> Result<(), Error> {
+        basic_query(&self.context(), self.url, &self.auth)
+    }
+
+    /// Sends the basic query until the endpoint answers it or `timeout` has passed, returning the
+    /// last error if it never did. Useful for a server which is still starting up.
+    ///
+    /// Every attempt goes through the same transport, so a connection is kept alive between
+    /// attempts instead of reconnecting each time. Attempts are a little jittered so that several
+    /// jobs waiting on the same server don't poll it in lockstep, unless the server said how long
+    /// to wait with `Retry-After`.
+    pub fn wait_until_healthy(&self, timeout: Duration) -> Result<(), Error> {
+        let context = self.context();
+        let start = Instant::now();
+        loop {
+            let throttled = context.throttle_history().len();
+            let err = match basic_query(&context, self.url, &self.auth) {
+                Ok(()) => return Ok(()),
+                Err(err) => err,
+            };
+            let remaining = timeout.saturating_sub(start.elapsed());
+            if remaining.is_zero() || matches!(err, Error::BadUri(_) | Error::Cancelled) {
+                return Err(err);
+            }
+            let interval = context.throttle_history()[throttled..].last().map_or_else(
+                || jittered(POLL_INTERVAL),
+                |throttled| Duration::from_secs(throttled.retry_after_secs),
+            );
+            thread::sleep(interval.min(remaining));
+        }
+    }
+
+    /// Waits until something accepts TCP connections on the endpoint's host and port, for a server
+    /// started earlier in the same job. Fails with [`Error::ServerNotStarted`] if nothing did
+    /// within `timeout`, which says more than every check failing with
+    /// [`Error::ConnectionRefused`].
+    pub fn wait_for_port(&self, timeout: Duration) -> Result<(), Error> {
+        let url = validate_url(self.url).map_err(Error::BadUri)?;
+        let host = url.host_str().unwrap_or_default();
+        let port = url.port_or_known_default().unwrap_or_default();
+        let start = Instant::now();
+        loop {
+            // Resolving may fail too while e.g. a container's name isn't registered yet.
+            let listening = url
+                .socket_addrs(|| None)
+                .into_iter()
+                .flatten()
+                .any(|address| TcpStream::connect_timeout(&address, POLL_INTERVAL).is_ok());
+            if listening {
+                return Ok(());
+            }
+            let remaining = timeout.saturating_sub(start.elapsed());
+            if remaining.is_zero() {
+                return Err(Error::ServerNotStarted {
+                    address: format!("{host}:{port}"),
+                    waited: start.elapsed(),
+                });
+            }
+            thread::sleep(jittered(POLL_INTERVAL).min(remaining));
+        }
+    }
+
+    fn context(&self) -> Context {
+        Context {
+            transport: self
+                .transport
+                .clone()
+                .unwrap_or_else(|| Arc::new(new_agent())),
+            capture: self.capture.clone(),
+            rate_limit: self.rate_limit.clone(),
+            timeout: self.timeout,
+            retries: self.retries,
+            cancellation: self.cancellation.clone(),
+            retried: Arc::default(),
+            throttled: Arc::default(),
+            last_request: Arc::default(),
+            errors_policy: self.errors_policy,
+            allowed_errors: self.allowed_errors.clone(),
+            secrets: self.secrets(),
+            issued_secrets: Arc::default(),
+        }
+    }
+
+    /// The value of every header this checker authenticates with. Headers from a [`TokenProvider`]
+    /// are added to the context as they're fetched.
+    fn secrets(&self) -> Vec<String> {
+        let roles = self.access.iter().flat_map(|access| access.roles.values());
+        [&self.auth]
+            .into_iter()
+            .chain(&self.invalid_auth)
+            .chain(self.auth_semantics.as_ref().map(|(auth, _)| auth))
+            .filter_map(|auth| match auth {
+                Auth::Enabled { header } => secret_of(header),
+                Auth::Disabled | Auth::Dynamic(_) => None,
+            })
+            .chain(roles.filter_map(|role| secret_of(&role.auth)))
+            .map(String::from)
+            .collect()
+    }
+
+    fn effective_introspection(&self) -> Introspection {
+        if self.skipped.contains(&Check::IntrospectionDisabled) {
+            return Introspection::Allow;
+        }
+        self.introspection.unwrap_or(match self.subgraph {
+            Subgraph::NotASubgraph => Introspection::Disallow,
+            Subgraph::Secure | Subgraph::Insecure => Introspection::Allow,
+        })
+    }
+}
+
+/// An [`Auth`] which can be sent to another thread, for [`Checker::stream`].
+enum OwnedAuth {
+    Enabled(String),
+    Disabled,
+    Dynamic(Arc<dyn TokenProvider>),
+}
+
+impl From<Auth<'_>> for OwnedAuth {
+    fn from(auth: Auth) -> Self {
+        match auth {
+            Auth::Enabled { header } => OwnedAuth::Enabled(header.to_string()),
+            Auth::Disabled => OwnedAuth::Disabled,
+            Auth::Dynamic(provider) => OwnedAuth::Dynamic(provider),
+        }
+    }
+}
+
+impl OwnedAuth {
+    fn auth(&self) -> Auth<'_> {
+        match self {
+            OwnedAuth::Enabled(header) => Auth::Enabled {
+                header: header.into(),
+            },
+            OwnedAuth::Disabled => Auth::Disabled,
+            OwnedAuth::Dynamic(provider) => Auth::Dynamic(Arc::clone(provider)),
+        }
+    }
+}
+
+/// Everything learned from a run, including the checks which passed or were skipped.
+#[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+pub struct CheckReport {
+    pub checks: Vec<CheckResult>,
+    /// How long the whole run took.
+    #[serde(
+        rename = "duration_ms",
+        serialize_with = "serialize_millis",
+        deserialize_with = "deserialize_millis"
+    )]
+    pub duration: Duration,
+    /// Whether the endpoint answered a federation `_service { sdl }` query, `None` if it couldn't
+    /// be reached or the run was cancelled before finding out.
+    pub is_subgraph: Option<bool>,
+    /// Whether the endpoint answered an introspection query, `None` if that wasn't checked.
+    pub introspection_enabled: Option<bool>,
+    /// The `Content-Type` (or error status) each `Accept` header got, if
+    /// [`Checker::compliance`] was run.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub negotiated: BTreeMap<String, String>,
+    /// How long the basic query took, if [`Checker::latency_samples`] was set and every sample
+    /// succeeded.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub latency: Option<LatencyStats>,
+    /// How long each of the basic queries sent at once took, if [`Checker::concurrency`] was set.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub concurrent_latency: Option<LatencyStats>,
+    /// Every request which failed in a way that may not happen again and was tried again, if
+    /// [`Checker::retries`] was set.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub retried: Vec<Retried>,
+    /// Every response which asked for requests to slow down, which a retry waited for. Checks
+    /// which are throttled are being rate limited, and may fail for it.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub throttled: Vec<Throttled>,
+    /// The SDL a subgraph answered with, so it can be analyzed further (e.g., with
+    /// `Schema::document` and the `schema-ast` feature) without fetching it again. Other servers
+    /// only list type names during the checks, use [`Checker::schema`] for their introspection.
+    #[serde(skip)]
+    pub schema: Option<Schema>,
+}
+
+impl CheckReport {
+    /// Whether no check failed, not counting the ones which only warn.
+    pub fn passed(&self) -> bool {
+        self.checks
+            .iter()
+            .all(|result| result.error().is_none() || result.severity == Severity::Warning)
+    }
+
+    pub fn failures(&self) -> impl Iterator<Item = &Error> {
+        self.checks.iter().filter_map(CheckResult::error)
+    }
+}
+
+/// Lets another thread stop a run early, see [`Checker::cancellation`].
+///
+/// A request which is already in flight is not interrupted, set a [`Checker::timeout`] to bound
+/// how long that can take.
+#[derive(Clone, Debug, Default)]
+pub struct CancellationToken(Arc<AtomicBool>);
+
+impl CancellationToken {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+#[cfg(test)]
+mod test_introspection {
+    use super::*;
+
+    #[test]
+    fn defaults_to_disallow() {
+        let checker = Checker::new("http://localhost");
+        assert_eq!(checker.effective_introspection(), Introspection::Disallow);
+    }
+
+    #[test]
+    fn subgraphs_default_to_allow() {
+        let checker = Checker::new("http://localhost").require_subgraph();
+        assert_eq!(checker.effective_introspection(), Introspection::Allow);
+    }
+
+    #[test]
+    fn explicit() {
+        let checker = Checker::new("http://localhost")
+            .require_subgraph()
+            .disallow_introspection();
+        assert_eq!(checker.effective_introspection(), Introspection::Disallow);
+    }
+}
+
+#[cfg(test)]
+mod test_overrides {
+    use super::*;
+
+    #[test]
+    fn skip() {
+        let report = Checker::new("http://127.0.0.1:1")
+            .skip(Check::BasicQuery)
+            .report();
+        assert_eq!(report.checks[0].outcome, Outcome::Skipped);
+    }
+
+    #[test]
+    fn severity() {
+        let report = Checker::new("http://127.0.0.1:1")
+            .skip(Check::BasicQuery)
+            .severity(Check::IntrospectionDisabled, Severity::Warning)
+            .report();
+        assert_eq!(report.checks[4].severity, Severity::Warning);
+        assert!(report.passed());
+    }
+}
+
+#[cfg(test)]
+mod test_router_health {
+    use serde_json::json;
+
+    use super::*;
+    use crate::testing::MockTransport;
+    use crate::HttpResponse;
+
+    fn router() -> MockTransport {
+        MockTransport::new(|request| {
+            Ok(if request.url.contains("/health") {
+                HttpResponse::json(200, &json!({"status": "UP"}))
+            } else {
+                HttpResponse::new(502, "Bad Gateway")
+            })
+        })
+    }
+
+    #[test]
+    fn only_when_configured() {
+        let report = Checker::new("http://localhost:4000/graphql")
+            .transport(router())
+            .report();
+        assert!(report
+            .checks
+            .iter()
+            .all(|result| result.check != Check::RouterHealth));
+    }
+
+    #[test]
+    fn correlates_with_basic_query() {
+        let report = Checker::new("http://localhost:4000/graphql")
+            .router_health("http://localhost:8088/health")
+            .transport(router())
+            .report();
+        let router_health = report.checks.last().unwrap();
+        assert_eq!(router_health.check, Check::RouterHealth);
+        assert_eq!(
+            router_health.outcome,
+            Outcome::Failed(Error::SubgraphProblem)
+        );
+        assert_eq!(router_health.severity, Severity::Warning);
+    }
+}
+
+#[cfg(test)]
+mod test_stream {
+    use super::*;
+    use crate::{Check, Error, Outcome, UriProblem};
+
+    #[test]
+    fn every_check_in_order() {
+        let results: Vec<CheckResult> = Checker::new("not a url").stream().into_iter().collect();
+        let checks: Vec<Check> = results.iter().map(|result| result.check).collect();
+        assert_eq!(
+            checks,
+            [
+                Check::BasicQuery,
+                Check::AuthEnforced,
+                Check::Subgraph,
+                Check::SubgraphSecured,
+                Check::IntrospectionDisabled,
+                Check::FederationImports
+            ]
+        );
+        assert_eq!(
+            results[0].outcome,
+            Outcome::Failed(Error::BadUri(UriProblem::MissingScheme))
+        );
+    }
+}
+
+#[cfg(test)]
+mod test_callbacks {
+    use std::sync::Mutex;
+
+    use super::*;
+
+    #[test]
+    fn every_check_starts_before_finishing() {
+        let events = Arc::new(Mutex::new(Vec::new()));
+        let started = Arc::clone(&events);
+        let finished = Arc::clone(&events);
+        Checker::new("not a url")
+            .on_check_started(move |check| started.lock().unwrap().push(("started", check)))
+            .on_check_finished(move |result| {
+                finished.lock().unwrap().push(("finished", result.check));
+            })
+            .run();
+        let events = events.lock().unwrap();
+        assert_eq!(events.len(), 12);
+        for (index, (kind, check)) in events.iter().enumerate() {
+            if *kind == "finished" {
+                assert!(events[..index].contains(&("started", *check)));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test_cancellation {
+    use super::*;
+    use crate::{Error, Outcome};
+
+    #[test]
+    fn cancelled_before_running() {
+        let token = CancellationToken::new();
+        token.cancel();
+        let results = Checker::new("http://localhost")
+            .require_subgraph()
+            .disallow_introspection()
+            .cancellation(&token)
+            .run();
+        assert_eq!(results[0].outcome, Outcome::Failed(Error::Cancelled));
+        assert_eq!(results[2].outcome, Outcome::Failed(Error::Cancelled));
+        assert_eq!(results[4].outcome, Outcome::Failed(Error::Cancelled));
+    }
+}
+
+#[cfg(test)]
+mod test_agent {
+    use std::sync::atomic::AtomicUsize;
+
+    use ureq::{AgentBuilder, Middleware, MiddlewareNext, Request, Response};
+
+    use super::*;
+
+    struct CountRequests(Arc<AtomicUsize>);
+
+    impl Middleware for CountRequests {
+        fn handle(&self, request: Request, next: MiddlewareNext) -> Result<Response, ureq::Error> {
+            self.0.fetch_add(1, Ordering::Relaxed);
+            next.handle(request)
+        }
+    }
+
+    #[test]
+    fn middleware_sees_every_request() {
+        let requests = Arc::new(AtomicUsize::new(0));
+        let agent = AgentBuilder::new()
+            .middleware(CountRequests(Arc::clone(&requests)))
+            .build();
+        Checker::new("http://127.0.0.1:1").agent(agent).run();
+        // The basic query, the subgraph query and the introspection query
+        assert_eq!(requests.load(Ordering::Relaxed), 3);
+    }
+}
+
+#[cfg(test)]
+mod test_wait_until_healthy {
+    use std::sync::atomic::AtomicUsize;
+
+    use serde_json::json;
+
+    use super::*;
+    use crate::testing::MockTransport;
+    use crate::HttpResponse;
+
+    #[test]
+    fn waits_for_server() {
+        let attempts = Arc::new(AtomicUsize::new(0));
+        let counter = Arc::clone(&attempts);
+        let checker =
+            Checker::new("http://localhost:4000").transport(MockTransport::new(move |_request| {
+                if counter.fetch_add(1, Ordering::Relaxed) == 0 {
+                    Ok(HttpResponse::new(503, "Starting up"))
+                } else {
+                    Ok(HttpResponse::json(
+                        200,
+                        &json!({"data": {"__typename": "Query"}}),
+                    ))
+                }
+            }));
+        checker.wait_until_healthy(Duration::from_secs(5)).unwrap();
+        assert_eq!(attempts.load(Ordering::Relaxed), 2);
+    }
+
+    #[test]
+    fn healthy() {
+        let checker = Checker::new("http://localhost:4000").transport(MockTransport::new(|_| {
+            Ok(HttpResponse::json(
+                200,
+                &json!({"data": {"__typename": "Query"}}),
+            ))
+        }));
+        checker.healthy().unwrap();
+    }
+
+    #[test]
+    fn gives_up() {
+        let checker = Checker::new("http://localhost:4000").transport(MockTransport::new(|_| {
+            Ok(HttpResponse::new(503, "Starting up"))
+        }));
+        assert!(matches!(
+            checker.wait_until_healthy(Duration::ZERO),
+            Err(Error::BadStatus { status: 503, .. })
+        ));
+    }
+}
+
+#[cfg(test)]
+mod test_wait_for_port {
+    use std::net::TcpListener;
+
+    use super::*;
+
+    #[test]
+    fn listening() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/graphql", listener.local_addr().unwrap());
+        Checker::new(&url)
+            .wait_for_port(Duration::from_secs(5))
+            .unwrap();
+    }
+
+    #[test]
+    fn ipv6() {
+        // Not every machine has an IPv6 loopback.
+        let Ok(listener) = TcpListener::bind("[::1]:0") else {
+            return;
+        };
+        let url = format!("http://{}/graphql", listener.local_addr().unwrap());
+        Checker::new(&url)
+            .wait_for_port(Duration::from_secs(5))
+            .unwrap();
+    }
+
+    #[test]
+    fn never_started() {
+        let address = TcpListener::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap();
+        let url = format!("http://{address}/graphql");
+        assert!(matches!(
+            Checker::new(&url).wait_for_port(Duration::ZERO),
+            Err(Error::ServerNotStarted { .. })
+        ));
+    }
+}
+
+#[cfg(test)]
+mod test_report {
+    use super::*;
+
+    #[test]
+    fn unreachable() {
+        let report = Checker::new("http://127.0.0.1:1").report();
+        assert_eq!(report.checks.len(), 6);
+        assert!(!report.passed());
+        assert_eq!(report.is_subgraph, None);
+        assert_eq!(report.introspection_enabled, None);
+    }
+
+    #[test]
+    fn timeouts_name_their_check() {
+        let report = Checker::new("https://example.com/graphql")
+            .transport(crate::testing::MockTransport::new(|request| {
+                Err(Error::timed_out(
+                    Duration::from_millis(1200),
+                    request.timeout.unwrap_or_default(),
+                ))
+            }))
+            .timeout(Duration::from_secs(1))
+            .report();
+        assert_eq!(
+            report.checks[0].outcome,
+            Outcome::Failed(Error::Timeout {
+                check: Some(Check::BasicQuery),
+                elapsed: Duration::from_millis(1200),
+                budget: Duration::from_secs(1),
+            })
+        );
+    }
+
+    #[test]
+    fn failures_keep_their_request() {
+        let results = Checker::new("http://127.0.0.1:1").run();
+        assert_eq!(
+            results[0].request.as_deref(),
+            Some("POST http://127.0.0.1:1")
+        );
+        assert!(results
+            .iter()
+            .filter(|result| result.error().is_none())
+            .all(|result| result.request.is_none()));
+    }
+
+    #[test]
+    fn subgraph_schema() {
+        let sdl = "type Query { hello: String }";
+        let report = Checker::new("https://example.com/graphql")
+            .allow_introspection()
+            .transport(crate::testing::MockTransport::new(move |_request| {
+                Ok(crate::HttpResponse::json(
+                    200,
+                    &serde_json::json!({"data": {"__typename": "Query", "_service": {"sdl": sdl}}}),
+                ))
+            }))
+            .report();
+        assert_eq!(report.is_subgraph, Some(true));
+        assert_eq!(report.schema, Some(Schema::Sdl(sdl.to_string())));
+    }
+}
+
+#[cfg(test)]
+mod test_secrets {
+    use super::*;
+    use crate::testing::MockTransport;
+    use crate::HttpResponse;
+
+    /// A server which echoes back whatever credentials it gets, rejecting all of them.
+    fn echo_credentials() -> MockTransport {
+        MockTransport::new(|request| {
+            let header = request.header("Authorization").unwrap_or_default();
+            Ok(HttpResponse::new(
+                401,
+                format!(r#"{{"errors": [{{"message": "Bad credentials: {header}"}}]}}"#),
+            ))
+        })
+    }
+
+    #[test]
+    fn never_leaked() {
+        let capture = Capture::new(Auth::Disabled);
+        let checker = Checker::new("https://example.com/graphql")
+            .auth(Auth::Enabled {
+                header: "Authorization: Bearer hunter2".into(),
+            })
+            .invalid_auth("Authorization: Bearer expired-hunter3")
+            .transport(echo_credentials())
+            .capture(&capture);
+        let debug = format!("{checker:?}");
+        let report = checker.report();
+        let outputs = [
+            debug,
+            format!("{report:?}"),
+            serde_json::to_string(&report).unwrap(),
+            serde_json::to_string(&capture.exchanges()).unwrap(),
+        ];
+        for output in outputs {
+            assert!(!output.contains("hunter"), "{output}");
+        }
+        assert!(!report.passed());
+    }
+}
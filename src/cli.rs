@@ -0,0 +1,992 @@
+use std::fs::read_to_string;
+use std::str::FromStr;
+use std::time::Duration;
+
+use clap::{ArgAction, Args, Parser, Subcommand};
+use clap_complete::Shell;
+use graphql_check_action::capabilities::ContentType;
+use graphql_check_action::config::{parse_duration, CheckConfig};
+use graphql_check_action::fixture::Fixture;
+use graphql_check_action::graphql::{AllowedError, ErrorsPolicy};
+use graphql_check_action::schema::AllowedChanges;
+use graphql_check_action::{Error, Introspection, Profile, Rejection, Subgraph};
+
+use crate::format::Format;
+use crate::log::LogFormat;
+
+/// The crate version and the commit it was built from.
+pub const VERSION: &str = concat!(env!("CARGO_PKG_VERSION"), " (", env!("GIT_SHA"), ")");
+
+/// Checks a GraphQL endpoint for problems.
+///
+/// Without a subcommand, runs the checks the same as `check`.
+#[derive(Debug, Parser)]
+#[command(
+    version = VERSION,
+    subcommand_negates_reqs = true,
+    args_conflicts_with_subcommands = true
+)]
+pub struct Cli {
+    #[command(subcommand)]
+    pub command: Option<Command>,
+    #[command(flatten)]
+    pub check: CheckArgs,
+    /// How to write logs on stderr: `text` or `json`.
+    #[arg(long, global = true, default_value = "text", value_parser = LogFormat::parse)]
+    pub log_format: LogFormat,
+    /// Only log errors.
+    #[arg(long, global = true)]
+    pub quiet: bool,
+}
+
+/// Runs the checks.
+///
+/// Boolean flags can be given on their own (`--subgraph`) or with a value (`--subgraph false`), the
+/// latter being how the action passes its inputs. Values are only parsed by
+/// [`CheckArgs::check_config`], so every bad one is reported at once, like any other bad input.
+#[derive(Debug, Args)]
+pub struct CheckArgs {
+    /// A JSON file configuring the checks, see `CheckConfig`. Flags override what's in it.
+    #[arg(long)]
+    pub config: Option<String>,
+    /// The full URL, including scheme (e.g., `https://`), of the GraphQL endpoint.
+    #[arg(long, required_unless_present_any = ["config", "endpoints", "sdl"])]
+    pub url: Option<String>,
+    /// A JSON object of endpoints to check instead of `--url`, by name, e.g.,
+    /// `{"products": "https://example.com/products"}`. Outputs are set for each name.
+    #[arg(long)]
+    pub endpoints: Option<String>,
+    /// A path to SDL to run the checks which only need a schema on, instead of checking `--url`.
+    #[arg(long)]
+    pub sdl: Option<String>,
+    /// A path to a saved schema (SDL or introspection JSON) which `--sdl` mustn't break.
+    #[arg(long)]
+    pub schema_baseline: Option<String>,
+    /// The full header to send with authenticated requests, in the format `name: value`.
+    #[arg(long)]
+    pub auth: Option<String>,
+    /// A header the server should reject, like an expired token, in the format `name: value`.
+    #[arg(long)]
+    pub invalid_auth: Option<String>,
+    /// How the server rejects requests without valid auth: a status like `401`, or an
+    /// `extensions.code` like `UNAUTHENTICATED`. Defaults to any GraphQL error or 4xx status.
+    #[arg(long)]
+    pub unauthenticated: Option<String>,
+    /// Valid credentials which aren't allowed to run `--privileged-query`, in the format
+    /// `name: value`.
+    #[arg(long)]
+    pub unprivileged_auth: Option<String>,
+    /// A query `--unprivileged-auth` should be forbidden from running.
+    #[arg(long)]
+    pub privileged_query: Option<String>,
+    /// The `Cache-Control` directives responses must have, e.g., `no-store`.
+    #[arg(long)]
+    pub cache_control: Option<String>,
+    /// How many times to send the basic query, one after another, reporting the p50, p95 and max.
+    #[arg(long)]
+    pub latency_samples: Option<String>,
+    /// The longest the p95 of `--latency-samples` may be, in milliseconds.
+    #[arg(long)]
+    pub max_p95_ms: Option<String>,
+    /// How many basic queries to send at once, all of which must succeed.
+    #[arg(long)]
+    pub concurrency: Option<String>,
+    /// The longest the basic queries sent at once by `--concurrency` may take all together, in
+    /// milliseconds.
+    #[arg(long)]
+    pub concurrency_budget_ms: Option<String>,
+    /// How many times to run every check, failing if any check's outcome changes between runs.
+    #[arg(long)]
+    pub stability_runs: Option<String>,
+    /// How long to keep running the checks for, like `10m`, failing if too few runs pass.
+    #[arg(long)]
+    pub monitor_for: Option<String>,
+    /// How often to run the checks during `--monitor-for`, like `30s`.
+    #[arg(long)]
+    pub monitor_interval: Option<String>,
+    /// The lowest percentage of runs during `--monitor-for` which must pass, 100 by default.
+    #[arg(long)]
+    pub min_availability: Option<String>,
+    /// Whether persisted queries sent as `GET`s should be cached by a CDN, unless authenticated.
+    #[arg(long, num_args = 0..=1, default_missing_value = "true", value_name = "BOOL")]
+    pub cdn_cache: Option<String>,
+    /// Whether registering a persisted query under a mismatched hash must be rejected.
+    #[arg(long, num_args = 0..=1, default_missing_value = "true", value_name = "BOOL")]
+    pub persisted_query_hash: Option<String>,
+    /// Comma-separated content types the server may accept, out of `application/json`,
+    /// `application/graphql`, `text/plain` and `none`.
+    #[arg(long)]
+    pub content_types: Option<String>,
+    /// Whether a query for a field which doesn't exist must be rejected with a validation error.
+    #[arg(long, num_args = 0..=1, default_missing_value = "true", value_name = "BOOL")]
+    pub unknown_field: Option<String>,
+    /// Whether to check that HTTP is handled the way the GraphQL over HTTP spec says.
+    #[arg(long, num_args = 0..=1, default_missing_value = "true", value_name = "BOOL")]
+    pub compliance: Option<String>,
+    /// Whether to send deliberately broken requests, which must be rejected without crashing.
+    #[arg(long, num_args = 0..=1, default_missing_value = "true", value_name = "BOOL")]
+    pub robustness: Option<String>,
+    /// A JSON request body, with `query` and `variables` of the wrong type for it, for the
+    /// robustness checks to send instead of their own.
+    #[arg(long)]
+    pub wrong_variables: Option<String>,
+    /// A JSON object of the exit code for each kind of failure, by error code, e.g.,
+    /// `{"introspection_enabled": 0}`. Failures mapped to `0` are reported as warnings.
+    #[arg(long)]
+    pub exit_codes: Option<String>,
+    /// A JSON object of which roles may run which operations, see `AccessMatrix`.
+    #[arg(long)]
+    pub access: Option<String>,
+    /// Whether the endpoint is expected to be a Federation subgraph.
+    #[arg(long, num_args = 0..=1, default_missing_value = "true", value_name = "BOOL")]
+    pub subgraph: Option<String>,
+    /// Whether introspection should be enabled, defaults to the value of `--subgraph`.
+    #[arg(long, num_args = 0..=1, default_missing_value = "true", value_name = "BOOL")]
+    pub allow_introspection: Option<String>,
+    /// Whether a subgraph is allowed to have no `--auth`.
+    #[arg(long, num_args = 0..=1, default_missing_value = "true", value_name = "BOOL")]
+    pub insecure_subgraph: Option<String>,
+    /// A path to write a JSON report of this run to.
+    #[arg(long)]
+    pub report: Option<String>,
+    /// A path to append the action's outputs to, in the format of `GITHUB_OUTPUT` (which is used
+    /// when this isn't given). Without either, outputs are printed after the results.
+    #[arg(long)]
+    pub output: Option<String>,
+    /// A path to the JSON report of a previous run to compare against.
+    #[arg(long)]
+    pub previous_report: Option<String>,
+    /// Whether to only fail when there are failures which weren't in `--previous-report`.
+    #[arg(long, num_args = 0..=1, default_missing_value = "true", value_name = "BOOL")]
+    pub new_failures_only: Option<String>,
+    /// Whether to probe for optional capabilities of the server.
+    #[arg(long, num_args = 0..=1, default_missing_value = "true", value_name = "BOOL")]
+    pub capabilities: Option<String>,
+    /// Whether to post the results as a comment on the pull request.
+    #[arg(long, num_args = 0..=1, default_missing_value = "true", value_name = "BOOL")]
+    pub comment: Option<String>,
+    /// Whether to create a GitHub check run with the results.
+    #[arg(long, num_args = 0..=1, default_missing_value = "true", value_name = "BOOL")]
+    pub check_run: Option<String>,
+    /// A directory to write a debugging bundle to.
+    #[arg(long)]
+    pub artifact_dir: Option<String>,
+    /// A path to write every request and its response to, with secrets redacted, for `--replay`.
+    #[arg(long)]
+    pub record: Option<String>,
+    /// A path to a recording (from `--record`, or `exchanges.json` in a debugging bundle) to answer
+    /// requests from instead of sending them.
+    #[arg(long)]
+    pub replay: Option<String>,
+    /// Whether to ask for as much as possible in a single request.
+    #[arg(long, num_args = 0..=1, default_missing_value = "true", value_name = "BOOL")]
+    pub combine_probes: Option<String>,
+    /// Whether to log the IP addresses the endpoint resolved to.
+    #[arg(long, num_args = 0..=1, default_missing_value = "true", value_name = "BOOL")]
+    pub log_ip: Option<String>,
+    /// Wait up to this many seconds for a server started earlier in the job to accept connections
+    /// and answer queries before checking it.
+    #[arg(long, value_name = "SECONDS")]
+    pub wait_for_port: Option<String>,
+    /// The health check endpoint of an Apollo Router in front of `--url`, e.g.,
+    /// `http://localhost:8088/health`.
+    #[arg(long)]
+    pub router_health: Option<String>,
+    /// Whether to look up an entity of each type with a `@key` in the subgraph's SDL.
+    #[arg(long, num_args = 0..=1, default_missing_value = "true", value_name = "BOOL")]
+    pub check_entities: Option<String>,
+    /// A JSON object of representations to look entities up with, by type name, e.g.,
+    /// `{"Product": {"__typename": "Product", "upc": "1"}}`, instead of synthetic ones.
+    #[arg(long)]
+    pub entity_representations: Option<String>,
+    /// A query spanning several subgraphs to send through the router at `--url`.
+    #[arg(long)]
+    pub smoke_query: Option<String>,
+    /// Comma-separated subgraphs the router's query plan for `--smoke-query` must fetch from.
+    #[arg(long, value_delimiter = ',')]
+    pub smoke_subgraphs: Option<Vec<String>>,
+    /// The server behind `--url`, enabling extra checks for it: `apollo-server`, `yoga`,
+    /// `hotchocolate`, `graphql-java` or `hasura`.
+    #[arg(long)]
+    pub profile: Option<String>,
+    /// Another endpoint, e.g., production, whose schema the one of `--url` should match.
+    #[arg(long)]
+    pub compare_url: Option<String>,
+    /// How the schema of `--url` may differ from the one of `--compare-url`: `none`, `additions`
+    /// or `non_breaking`.
+    #[arg(long)]
+    pub compare_allow: Option<String>,
+    /// What the checks do with `errors` next to `data`: `fail`, `ignore_if_data` or `allowlist`.
+    #[arg(long)]
+    pub errors_policy: Option<String>,
+    /// Comma-separated errors which `--errors-policy allowlist` lets through: `extensions.code`s,
+    /// or regexes between slashes for the messages, like `/is deprecated/`.
+    #[arg(long)]
+    pub allowed_errors: Option<String>,
+    /// The most requests to send a second, counting every check and retry.
+    #[arg(long)]
+    pub rate_limit: Option<String>,
+    /// The CDN endpoint of a GraphQL Hive target to compare the live schema against.
+    #[arg(long)]
+    pub hive_endpoint: Option<String>,
+    /// The CDN access key for `--hive-endpoint`.
+    #[arg(long)]
+    pub hive_token: Option<String>,
+    /// A GraphOS API key for reporting results as an Apollo Studio custom check.
+    #[arg(long)]
+    pub apollo_key: Option<String>,
+    /// The graph the custom check belongs to, as `graph-id@variant`.
+    #[arg(long)]
+    pub apollo_graph_ref: Option<String>,
+    /// The `workflowId` from the custom check webhook.
+    #[arg(long)]
+    pub apollo_workflow_id: Option<String>,
+    /// The `taskId` from the custom check webhook.
+    #[arg(long)]
+    pub apollo_task_id: Option<String>,
+    /// Send a single quick query and exit with 0 if it worked or 1 if not, logging nothing else, for
+    /// use as a Docker `HEALTHCHECK`.
+    #[arg(long)]
+    pub healthcheck: bool,
+    /// How to print results on stdout: `text`, `tap` or `rover` (JSON like `rover --format json`).
+    #[arg(long, default_value = "text", value_parser = Format::parse)]
+    pub format: Format,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum Command {
+    Check(Box<CheckArgs>),
+    /// Fetches the schema of an endpoint: the SDL of a subgraph, or the result of introspection.
+    Introspect {
+        #[command(flatten)]
+        endpoint: EndpointArgs,
+        /// A path to write the schema to instead of printing it.
+        #[arg(long)]
+        output: Option<String>,
+    },
+    /// Works with the schema of an endpoint.
+    #[command(subcommand)]
+    Schema(SchemaCommand),
+    /// Runs a tiny GraphQL server to check, e.g., to test a workflow without a real endpoint.
+    ServeFixture(FixtureArgs),
+    /// Prints a script which completes the flags of this binary in `shell`.
+    #[command(hide = true)]
+    Completions {
+        shell: Shell,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+pub enum SchemaCommand {
+    /// Compares the live schema with a saved one, failing if any type, field or argument was added,
+    /// removed or changed.
+    Diff {
+        #[command(flatten)]
+        endpoint: EndpointArgs,
+        /// A path to the saved schema: SDL, or the JSON of an introspection result.
+        #[arg(long)]
+        baseline: String,
+    },
+}
+
+/// The endpoint to fetch a schema from.
+#[derive(Debug, Args)]
+pub struct EndpointArgs {
+    /// The full URL, including scheme (e.g., `https://`), of the GraphQL endpoint.
+    #[arg(long)]
+    pub url: String,
+    /// The full header to send with requests, in the format `name: value`.
+    #[arg(long)]
+    pub auth: Option<String>,
+}
+
+/// How the fixture server behaves.
+#[derive(Debug, Args)]
+pub struct FixtureArgs {
+    /// The address to listen on.
+    #[arg(long, default_value = "127.0.0.1")]
+    pub host: String,
+    /// The port to listen on, 0 for any free one.
+    #[arg(long, default_value_t = 4000)]
+    pub port: u16,
+    /// A header every request must have, in the format `name: value`.
+    #[arg(long)]
+    pub auth: Option<String>,
+    /// Whether to answer introspection queries.
+    #[arg(long, action = ArgAction::Set, default_value_t = true, num_args = 0..=1, default_missing_value = "true")]
+    pub introspection: bool,
+    /// Whether to answer `_service` like an Apollo Federation subgraph.
+    #[arg(long, action = ArgAction::Set, default_value_t, num_args = 0..=1, default_missing_value = "true")]
+    pub subgraph: bool,
+}
+
+impl FixtureArgs {
+    pub fn fixture(&self) -> Fixture {
+        Fixture {
+            auth: self.auth.clone(),
+            introspection: self.introspection,
+            subgraph: self.subgraph,
+        }
+    }
+}
+
+impl EndpointArgs {
+    pub fn config(&self) -> CheckConfig {
+        CheckConfig {
+            url: self.url.clone(),
+            auth: self.auth.clone(),
+            ..CheckConfig::default()
+        }
+    }
+}
+
+impl Cli {
+    /// The subcommand to run, `check` if none was given.
+    pub fn into_command(self) -> Command {
+        self.command
+            .unwrap_or_else(|| Command::Check(Box::new(self.check)))
+    }
+}
+
+impl CheckArgs {
+    /// The checks to run: those in `--config` if there is one, overridden by any flags given.
+    /// Along with it comes every problem with the inputs, so they can all be reported at once, the
+    /// config having the default for each input with a problem.
+    pub fn check_config(&self) -> (CheckConfig, Vec<Error>) {
+        let mut problems = Vec::new();
+        let mut config = match &self.config {
+            Some(path) => read_to_string(path)
+                .map_err(|err| Error::BadConfig(format!("{path}: {err}")))
+                .and_then(|json| CheckConfig::from_json(&json))
+                .unwrap_or_else(|err| {
+                    problems.push(err);
+                    CheckConfig::default()
+                }),
+            None => CheckConfig::default(),
+        };
+        if let Some(url) = &self.url {
+            config.url.clone_from(url);
+        }
+        if let Some(endpoints) = parse(&mut problems, &self.endpoints, |endpoints| {
+            serde_json::from_str(endpoints)
+                .map_err(|err| Error::BadInput(format!("endpoints is not valid: {err}")))
+        }) {
+            config.endpoints = endpoints;
+        }
+        if let Some(sdl) = &self.sdl {
+            config.sdl = Some(sdl.clone());
+        }
+        if let Some(schema_baseline) = &self.schema_baseline {
+            config.schema_baseline = Some(schema_baseline.clone());
+        }
+        if let Some(auth) = &self.auth {
+            config.auth = Some(auth.clone());
+        }
+        if let Some(invalid_auth) = &self.invalid_auth {
+            config.invalid_auth = Some(invalid_auth.clone());
+        }
+        if let Some(unauthenticated) = parse(&mut problems, &self.unauthenticated, Rejection::parse)
+        {
+            config.unauthenticated = Some(unauthenticated);
+        }
+        if let Some(unprivileged_auth) = &self.unprivileged_auth {
+            config.unprivileged_auth = Some(unprivileged_auth.clone());
+        }
+        if let Some(privileged_query) = &self.privileged_query {
+            config.privileged_query = Some(privileged_query.clone());
+        }
+        if let Some(cache_control) = &self.cache_control {
+            config.cache_control = Some(cache_control.clone());
+        }
+        if let Some(samples) = parse(
+            &mut problems,
+            &self.latency_samples,
+            number("latency_samples"),
+        ) {
+            config.latency_samples = Some(samples);
+        }
+        if let Some(max_p95_ms) = parse(&mut problems, &self.max_p95_ms, number("max_p95_ms")) {
+            config.max_p95_ms = Some(max_p95_ms);
+        }
+        if let Some(concurrency) = parse(&mut problems, &self.concurrency, number("concurrency")) {
+            config.concurrency = Some(concurrency);
+        }
+        let budget = parse(
+            &mut problems,
+            &self.concurrency_budget_ms,
+            number("concurrency_budget_ms"),
+        );
+        if budget.is_some() {
+            config.concurrency_budget_ms = budget;
+        }
+        if let Some(runs) = parse(
+            &mut problems,
+            &self.stability_runs,
+            number("stability_runs"),
+        ) {
+            config.stability_runs = Some(runs);
+        }
+        if let Some(monitor_for) = parse(&mut problems, &self.monitor_for, duration("monitor_for"))
+        {
+            config.monitor_for = Some(monitor_for);
+        }
+        let interval = parse(
+            &mut problems,
+            &self.monitor_interval,
+            duration("monitor_interval"),
+        );
+        if interval.is_some() {
+            config.monitor_interval = interval;
+        }
+        let availability = parse(
+            &mut problems,
+            &self.min_availability,
+            number("min_availability"),
+        );
+        if availability.is_some() {
+            config.min_availability = availability;
+        }
+        if let Some(cdn_cache) = parse(&mut problems, &self.cdn_cache, boolean("cdn_cache")) {
+            config.cdn_cache = cdn_cache;
+        }
+        let persisted_query_hash = parse(
+            &mut problems,
+            &self.persisted_query_hash,
+            boolean("persisted_query_hash"),
+        );
+        if let Some(persisted_query_hash) = persisted_query_hash {
+            config.persisted_query_hash = persisted_query_hash;
+        }
+        if let Some(content_types) = parse(&mut problems, &self.content_types, |content_types| {
+            content_types.split(',').map(ContentType::parse).collect()
+        }) {
+            config.content_types = Some(content_types);
+        }
+        if let Some(unknown_field) =
+            parse(&mut problems, &self.unknown_field, boolean("unknown_field"))
+        {
+            config.unknown_field = unknown_field;
+        }
+        if let Some(compliance) = parse(&mut problems, &self.compliance, boolean("compliance")) {
+            config.compliance = compliance;
+        }
+        if let Some(robustness) = parse(&mut problems, &self.robustness, boolean("robustness")) {
+            config.robustness = robustness;
+        }
+        if let Some(body) = parse(&mut problems, &self.wrong_variables, |body| {
+            serde_json::from_str(body)
+                .map_err(|err| Error::BadInput(format!("wrong_variables is not JSON: {err}")))
+        }) {
+            config.wrong_variables = Some(body);
+        }
+        if let Some(exit_codes) = parse(&mut problems, &self.exit_codes, |exit_codes| {
+            serde_json::from_str(exit_codes)
+                .map_err(|err| Error::BadInput(format!("exit_codes is not valid: {err}")))
+        }) {
+            config.exit_codes = exit_codes;
+        }
+        if let Some(access) = parse(&mut problems, &self.access, |access| {
+            serde_json::from_str(access)
+                .map_err(|err| Error::BadInput(format!("access is not valid: {err}")))
+        }) {
+            config.access = Some(access);
+        }
+        let subgraph = parse(&mut problems, &self.subgraph, boolean("subgraph"));
+        let insecure_subgraph = parse(
+            &mut problems,
+            &self.insecure_subgraph,
+            boolean("insecure_subgraph"),
+        );
+        if subgraph.is_some() || insecure_subgraph.is_some() {
+            let required = subgraph.unwrap_or(config.subgraph != Subgraph::NotASubgraph);
+            let insecure = insecure_subgraph.unwrap_or(config.subgraph == Subgraph::Insecure);
+            config.subgraph = match (required, insecure) {
+                (true, true) => Subgraph::Insecure,
+                (true, false) => Subgraph::Secure,
+                (false, _) => Subgraph::NotASubgraph,
+            };
+        }
+        let allow_introspection = parse(
+            &mut problems,
+            &self.allow_introspection,
+            boolean("allow_introspection"),
+        );
+        if let Some(allow) = allow_introspection {
+            config.introspection = Some(if allow {
+                Introspection::Allow
+            } else {
+                Introspection::Disallow
+            });
+        }
+        if let Some(combine) = parse(
+            &mut problems,
+            &self.combine_probes,
+            boolean("combine_probes"),
+        ) {
+            config.combine_probes = combine;
+        }
+        if let Some(router_health) = &self.router_health {
+            config.router_health = Some(router_health.clone());
+        }
+        if let Some(check_entities) = parse(
+            &mut problems,
+            &self.check_entities,
+            boolean("check_entities"),
+        ) {
+            config.check_entities = check_entities;
+        }
+        if let Some(representations) = parse(&mut problems, &self.entity_representations, |json| {
+            serde_json::from_str(json).map_err(|err| {
+                Error::BadInput(format!(
+                    "entity_representations is not a JSON object: {err}"
+                ))
+            })
+        }) {
+            config.entity_representations = representations;
+        }
+        if let Some(query) = &self.smoke_query {
+            config.smoke_query = Some(query.clone());
+        }
+        if let Some(subgraphs) = &self.smoke_subgraphs {
+            config.smoke_subgraphs.clone_from(subgraphs);
+        }
+        if let Some(profile) = parse(&mut problems, &self.profile, Profile::parse) {
+            config.profile = Some(profile);
+        }
+        if let Some(compare_url) = &self.compare_url {
+            config.compare_url = Some(compare_url.clone());
+        }
+        if let Some(allowed) = parse(&mut problems, &self.compare_allow, AllowedChanges::parse) {
+            config.compare_allow = Some(allowed);
+        }
+        if let Some(policy) = parse(&mut problems, &self.errors_policy, ErrorsPolicy::parse) {
+            config.errors_policy = Some(policy);
+        }
+        if let Some(allowed) = parse(&mut problems, &self.allowed_errors, |allowed| {
+            allowed.split(',').map(AllowedError::parse).collect()
+        }) {
+            config.allowed_errors = allowed;
+        }
+        if let Some(rate_limit) = parse(&mut problems, &self.rate_limit, number("rate_limit")) {
+            config.rate_limit = Some(rate_limit);
+        }
+        // These aren't part of the config, but are checked with the rest.
+        for (name, value) in [
+            ("new_failures_only", &self.new_failures_only),
+            ("capabilities", &self.capabilities),
+            ("comment", &self.comment),
+            ("check_run", &self.check_run),
+            ("log_ip", &self.log_ip),
+        ] {
+            parse(&mut problems, value, boolean(name));
+        }
+        parse(
+            &mut problems,
+            &self.wait_for_port,
+            number::<u64>("wait_for_port"),
+        );
+        (config, problems)
+    }
+
+    /// How many seconds `--wait-for-port` waits, once [`CheckArgs::check_config`] has checked it.
+    pub fn wait_for_port(&self) -> Option<u64> {
+        self.wait_for_port.as_deref()?.parse().ok()
+    }
+}
+
+/// Parses `value` with `parse` if it was given. If it can't be parsed, why is added to `problems`
+/// instead, so the rest of the inputs are still checked.
+fn parse<T>(
+    problems: &mut Vec<Error>,
+    value: &Option<String>,
+    parse: impl FnOnce(&str) -> Result<T, Error>,
+) -> Option<T> {
+    match parse(value.as_deref()?) {
+        Ok(parsed) => Some(parsed),
+        Err(err) => {
+            problems.push(err);
+            None
+        }
+    }
+}
+
+/// Parses the boolean input `name`.
+fn boolean(name: &'static str) -> impl Fn(&str) -> Result<bool, Error> {
+    move |value| match value {
+        "true" => Ok(true),
+        "false" => Ok(false),
+        _ => Err(Error::BadBoolean(name.to_string())),
+    }
+}
+
+/// Parses the input `name`, which is a whole number.
+fn number<T: FromStr>(name: &'static str) -> impl Fn(&str) -> Result<T, Error> {
+    move |value| {
+        value
+            .parse()
+            .map_err(|_| Error::BadInput(format!("`{name}` must be a whole number, not `{value}`")))
+    }
+}
+
+/// Parses the input `name`, which is a duration like `90s`.
+fn duration(name: &'static str) -> impl Fn(&str) -> Result<Duration, Error> {
+    move |value| parse_duration(value).map_err(|err| Error::BadInput(format!("{name}: {err}")))
+}
+
+/// Whether the boolean input `flag` is `true`, once [`CheckArgs::check_config`] has checked it.
+pub fn is_true(flag: &Option<String>) -> bool {
+    flag.as_deref() == Some("true")
+}
+
+/// Each input of the action and its named flag, in the order the action passes them as positional
+/// arguments.
+const INPUTS: [(&str, &str); 59] = [
+    ("endpoint", "--url"),
+    ("auth", "--auth"),
+    ("subgraph", "--subgraph"),
+    ("allow_introspection", "--allow-introspection"),
+    ("insecure_subgraph", "--insecure-subgraph"),
+    ("report", "--report"),
+    ("previous_report", "--previous-report"),
+    ("new_failures_only", "--new-failures-only"),
+    ("capabilities", "--capabilities"),
+    ("comment", "--comment"),
+    ("check_run", "--check-run"),
+    ("artifact_dir", "--artifact-dir"),
+    ("combine_probes", "--combine-probes"),
+    ("log_ip", "--log-ip"),
+    ("wait_for_port", "--wait-for-port"),
+    ("router_health", "--router-health"),
+    ("profile", "--profile"),
+    ("hive_endpoint", "--hive-endpoint"),
+    ("hive_token", "--hive-token"),
+    ("apollo_key", "--apollo-key"),
+    ("apollo_graph_ref", "--apollo-graph-ref"),
+    ("apollo_workflow_id", "--apollo-workflow-id"),
+    ("apollo_task_id", "--apollo-task-id"),
+    ("check_entities", "--check-entities"),
+    ("entity_representations", "--entity-representations"),
+    ("smoke_query", "--smoke-query"),
+    ("smoke_subgraphs", "--smoke-subgraphs"),
+    ("invalid_auth", "--invalid-auth"),
+    ("unauthenticated", "--unauthenticated"),
+    ("unprivileged_auth", "--unprivileged-auth"),
+    ("privileged_query", "--privileged-query"),
+    ("access", "--access"),
+    ("cache_control", "--cache-control"),
+    ("cdn_cache", "--cdn-cache"),
+    ("persisted_query_hash", "--persisted-query-hash"),
+    ("unknown_field", "--unknown-field"),
+    ("robustness", "--robustness"),
+    ("wrong_variables", "--wrong-variables"),
+    ("content_types", "--content-types"),
+    ("compliance", "--compliance"),
+    ("latency_samples", "--latency-samples"),
+    ("max_p95_ms", "--max-p95-ms"),
+    ("stability_runs", "--stability-runs"),
+    ("concurrency", "--concurrency"),
+    ("concurrency_budget_ms", "--concurrency-budget-ms"),
+    ("exit_codes", "--exit-codes"),
+    ("endpoints", "--endpoints"),
+    ("monitor_for", "--monitor-for"),
+    ("monitor_interval", "--monitor-interval"),
+    ("min_availability", "--min-availability"),
+    ("record", "--record"),
+    ("replay", "--replay"),
+    ("sdl", "--sdl"),
+    ("schema_baseline", "--schema-baseline"),
+    ("compare_url", "--compare-url"),
+    ("compare_allow", "--compare-allow"),
+    ("errors_policy", "--errors-policy"),
+    ("allowed_errors", "--allowed-errors"),
+    ("rate_limit", "--rate-limit"),
+];
+
+/// Subcommands, which are never mistaken for a positional `endpoint`.
+const SUBCOMMANDS: [&str; 5] = [
+    "check",
+    "introspect",
+    "schema",
+    "serve-fixture",
+    "completions",
+];
+
+/// Flags which take the next argument as their value.
+const TAKES_VALUE: [&str; 4] = ["--format", "--log-format", "--config", "--output"];
+
+/// Rewrites the inputs of the action into named flags, unless `--url`, `--config`, `--endpoints` or
+/// `--sdl` is given by name or there's a subcommand. Inputs are either the positional arguments the
+/// action has always passed (`url auth subgraph ...`) or, if there are none, `INPUT_*` environment
+/// variables (looked up with `var`) like GitHub sets for Docker actions, which avoid quoting
+/// headers containing spaces.
+///
+/// Empty inputs are dropped, so they get the same default as a flag which wasn't given.
+pub fn compatible_args(
+    args: impl IntoIterator<Item = String>,
+    var: impl Fn(&str) -> Option<String>,
+) -> Vec<String> {
+    let mut args = args.into_iter();
+    let mut compatible: Vec<String> = args.next().into_iter().collect();
+    let args: Vec<String> = args.collect();
+    let named = args.iter().any(|arg| {
+        ["--url", "--config", "--endpoints", "--sdl"]
+            .iter()
+            .any(|flag| arg == flag || arg.starts_with(&format!("{flag}=")))
+    });
+    let subcommand = args
+        .first()
+        .is_some_and(|arg| SUBCOMMANDS.contains(&arg.as_str()));
+    if named || subcommand {
+        compatible.extend(args);
+        return compatible;
+    }
+    let mut positional = INPUTS.iter().map(|(_, flag)| flag);
+    let mut any_positional = false;
+    let mut args = args.into_iter();
+    while let Some(arg) = args.next() {
+        if arg.starts_with("--") {
+            let takes_value = TAKES_VALUE.contains(&arg.as_str());
+            compatible.push(arg);
+            if takes_value {
+                compatible.extend(args.next());
+            }
+        } else if let Some(flag) = positional.next() {
+            any_positional = true;
+            if !arg.is_empty() {
+                compatible.push(format!("{flag}={arg}"));
+            }
+        } else {
+            compatible.push(arg);
+        }
+    }
+    if !any_positional {
+        for (input, flag) in INPUTS {
+            let value = var(&format!("INPUT_{}", input.to_uppercase()));
+            if let Some(value) = value.filter(|value| !value.is_empty()) {
+                compatible.push(format!("{flag}={value}"));
+            }
+        }
+    }
+    compatible
+}
+
+#[cfg(test)]
+mod test_compatible_args {
+    use super::*;
+
+    fn args(args: &[&str]) -> Vec<String> {
+        args.iter().map(ToString::to_string).collect()
+    }
+
+    fn no_inputs(_: &str) -> Option<String> {
+        None
+    }
+
+    #[test]
+    fn positional() {
+        let cli = Cli::parse_from(compatible_args(
+            args(&[
+                "graphql-check-action",
+                "--quiet",
+                "https://example.com/graphql",
+                "",
+                "true",
+                "",
+                "false",
+                "--format",
+                "tap",
+            ]),
+            no_inputs,
+        ));
+        let (config, problems) = cli.check.check_config();
+        assert_eq!(problems, []);
+        assert_eq!(config.url, "https://example.com/graphql");
+        assert_eq!(config.auth, None);
+        assert_eq!(config.subgraph, Subgraph::Secure);
+        assert_eq!(config.introspection, None);
+        assert!(cli.quiet);
+        assert_eq!(cli.check.format, Format::Tap);
+    }
+
+    #[test]
+    fn named() {
+        let cli = Cli::parse_from(args(&[
+            "graphql-check-action",
+            "--url",
+            "https://example.com/graphql",
+            "--subgraph",
+            "--allow-introspection",
+            "false",
+        ]));
+        let (config, problems) = cli.check.check_config();
+        assert_eq!(problems, []);
+        assert_eq!(config.subgraph, Subgraph::Secure);
+        assert_eq!(config.introspection, Some(Introspection::Disallow));
+    }
+
+    #[test]
+    fn config_file() {
+        let path = std::env::temp_dir().join("graphql-check-cli-config.json");
+        std::fs::write(
+            &path,
+            r#"{"url": "https://example.com/graphql", "subgraph": "insecure", "retries": 2}"#,
+        )
+        .unwrap();
+        let cli = Cli::parse_from(compatible_args(
+            args(&[
+                "graphql-check-action",
+                "--config",
+                path.to_str().unwrap(),
+                "--insecure-subgraph",
+                "false",
+            ]),
+            no_inputs,
+        ));
+        let (config, problems) = cli.check.check_config();
+        assert_eq!(problems, []);
+        assert_eq!(config.url, "https://example.com/graphql");
+        assert_eq!(config.subgraph, Subgraph::Secure);
+        assert_eq!(config.retries, 2);
+    }
+
+    #[test]
+    fn missing_url() {
+        assert!(Cli::try_parse_from(["graphql-check-action", "--quiet"]).is_err());
+    }
+
+    #[test]
+    fn env_inputs() {
+        let cli = Cli::parse_from(compatible_args(
+            args(&["graphql-check-action"]),
+            |name| match name {
+                "INPUT_ENDPOINT" => Some("https://example.com/graphql".to_string()),
+                "INPUT_AUTH" => Some("Authorization: Bearer token with spaces".to_string()),
+                "INPUT_ALLOW_INTROSPECTION" => Some(String::new()),
+                "INPUT_SUBGRAPH" => Some("true".to_string()),
+                _ => None,
+            },
+        ));
+        let (config, problems) = cli.check.check_config();
+        assert_eq!(problems, []);
+        assert_eq!(
+            config.auth.as_deref(),
+            Some("Authorization: Bearer token with spaces")
+        );
+        assert_eq!(config.subgraph, Subgraph::Secure);
+        assert_eq!(config.introspection, None);
+    }
+
+    #[test]
+    fn completions() {
+        let cli = Cli::parse_from(compatible_args(
+            args(&["graphql-check-action", "completions", "bash"]),
+            no_inputs,
+        ));
+        assert!(matches!(
+            cli.command,
+            Some(Command::Completions { shell: Shell::Bash })
+        ));
+    }
+
+    #[test]
+    fn subcommands() {
+        let check = Cli::parse_from(compatible_args(
+            args(&[
+                "graphql-check-action",
+                "check",
+                "--url",
+                "https://example.com/graphql",
+                "--quiet",
+            ]),
+            no_inputs,
+        ));
+        assert!(check.quiet);
+        let Command::Check(check) = check.into_command() else {
+            panic!("expected `check`");
+        };
+        assert_eq!(check.url.as_deref(), Some("https://example.com/graphql"));
+        let diff = Cli::parse_from(compatible_args(
+            args(&[
+                "graphql-check-action",
+                "schema",
+                "diff",
+                "--url",
+                "https://example.com/graphql",
+                "--baseline",
+                "schema.graphql",
+            ]),
+            no_inputs,
+        ));
+        assert!(matches!(
+            diff.into_command(),
+            Command::Schema(SchemaCommand::Diff { baseline, .. }) if baseline == "schema.graphql"
+        ));
+        let serve = Cli::parse_from(compatible_args(
+            args(&[
+                "graphql-check-action",
+                "serve-fixture",
+                "--introspection",
+                "false",
+                "--subgraph",
+            ]),
+            no_inputs,
+        ));
+        let Command::ServeFixture(serve) = serve.into_command() else {
+            panic!("expected `serve-fixture`");
+        };
+        assert_eq!(serve.port, 4000);
+        assert!(!serve.fixture().introspection && serve.fixture().subgraph);
+        assert!(Cli::try_parse_from([
+            "graphql-check-action",
+            "--url",
+            "https://example.com/graphql",
+            "introspect",
+        ])
+        .is_err());
+    }
+
+    #[test]
+    fn version() {
+        let err = Cli::try_parse_from(["graphql-check-action", "--version"]).unwrap_err();
+        assert_eq!(err.kind(), clap::error::ErrorKind::DisplayVersion);
+        assert!(err.to_string().contains(env!("CARGO_PKG_VERSION")));
+    }
+
+    #[test]
+    fn bad_boolean() {
+        let cli = Cli::parse_from(compatible_args(
+            args(&[
+                "graphql-check-action",
+                "https://example.com/graphql",
+                "",
+                "yes",
+            ]),
+            no_inputs,
+        ));
+        assert_eq!(
+            cli.check.check_config().1,
+            [Error::BadBoolean("subgraph".to_string())]
+        );
+    }
+
+    #[test]
+    fn every_problem() {
+        let cli = Cli::parse_from(args(&[
+            "graphql-check-action",
+            "--url",
+            "https://example.com/graphql",
+            "--comment",
+            "yes",
+            "--monitor-for",
+            "soon",
+            "--latency-samples=-1",
+            "--profile",
+            "apollo",
+        ]));
+        let (config, problems) = cli.check.check_config();
+        assert_eq!(config.url, "https://example.com/graphql");
+        assert_eq!(config.monitor_for, None);
+        assert_eq!(problems.len(), 4);
+        assert!(problems.contains(&Error::BadBoolean("comment".to_string())));
+    }
+}
@@ -0,0 +1,296 @@
+use std::collections::BTreeMap;
+
+use serde_json::json;
+
+use crate::http::{Context, HttpResponse};
+use crate::{
+    authenticate, make_request, Auth, Check, CheckResult, Error, Event, Fact, HttpRequest, Outcome,
+};
+
+/// The checks run by [`Checker::compliance`](crate::Checker::compliance), in order.
+pub(crate) const COMPLIANCE_CHECKS: &[Check] = &[
+    Check::AcceptNegotiated,
+    Check::OptionsHandled,
+    Check::HeadHandled,
+    Check::NoConnectionClose,
+];
+
+/// The media type every GraphQL server can respond with.
+const JSON: &str = "application/json";
+/// The media type the GraphQL-over-HTTP spec introduces for GraphQL responses.
+const GRAPHQL_RESPONSE: &str = "application/graphql-response+json";
+/// A media type no GraphQL server responds with.
+const UNSUPPORTED: &str = "application/xml";
+
+/// Runs every compliance check, reporting each as it starts and finishes.
+pub(crate) fn run_compliance_checks(
+    context: &Context,
+    url: &str,
+    auth: &Auth,
+    on_event: &mut dyn FnMut(Event),
+) {
+    for check in COMPLIANCE_CHECKS {
+        on_event(Event::Started(*check));
+        let outcome = match check {
+            Check::AcceptNegotiated => {
+                let (outcome, negotiated) = accept_negotiated(context, url, auth);
+                on_event(Event::Observed(Fact::Negotiated(negotiated)));
+                outcome
+            }
+            Check::OptionsHandled => options_handled(context, url),
+            Check::HeadHandled => head_handled(context, url, auth),
+            Check::NoConnectionClose => no_connection_close(context, url, auth),
+            _ => Outcome::Skipped,
+        };
+        on_event(Event::Finished(CheckResult::new(*check, outcome)));
+    }
+}
+
+/// Sends the basic query asking for each of `application/json`,
+/// `application/graphql-response+json` and a type no server supports. Each must be answered with
+/// JSON, either the type asked for or `application/json` as a fallback, or a 406 for the types a
+/// server doesn't support. Also returns what each `Accept` got, as its `Content-Type` or status.
+fn accept_negotiated(
+    context: &Context,
+    url: &str,
+    auth: &Auth,
+) -> (Outcome, BTreeMap<String, String>) {
+    let mut negotiated = BTreeMap::new();
+    let mut outcome = Outcome::Passed;
+    for (accept, acceptable) in [
+        (JSON, &[JSON][..]),
+        (GRAPHQL_RESPONSE, &[GRAPHQL_RESPONSE, JSON][..]),
+        (UNSUPPORTED, &[JSON, GRAPHQL_RESPONSE][..]),
+    ] {
+        let response = make_request(context, url, auth).and_then(|request| {
+            context.send(
+                request.set("Accept", accept),
+                Some(&json!({"query": "query{__typename}"})),
+            )
+        });
+        let response = match response {
+            Ok(response) => response,
+            Err(err) => return (Outcome::Failed(err), negotiated),
+        };
+        let got = describe_response(&response);
+        let fine = if response.status == 406 {
+            accept != JSON
+        } else {
+            response.status < 500 && acceptable.contains(&response.content_type())
+        };
+        if !fine && outcome == Outcome::Passed {
+            outcome = Outcome::Failed(Error::AcceptNotNegotiated {
+                accept: accept.to_string(),
+                got: got.clone(),
+            });
+        }
+        negotiated.insert(accept.to_string(), got);
+    }
+    (outcome, negotiated)
+}
+
+/// Sends the CORS preflight a browser would before a cross-origin `POST`. The server may refuse it,
+/// but mustn't crash, and a successful response must say which methods are allowed.
+fn options_handled(context: &Context, url: &str) -> Outcome {
+    let request = HttpRequest::new("OPTIONS", url)
+        .set("Origin", "https://example.com")
+        .set("Access-Control-Request-Method", "POST")
+        .set("Access-Control-Request-Headers", "content-type");
+    let response = match context.send(request, None) {
+        Ok(response) => response,
+        Err(err) => return Outcome::Failed(err),
+    };
+    let problem = if response.status >= 500 {
+        format!("it responded with status {}", response.status)
+    } else if response.status < 300
+        && response.header("Allow").is_none()
+        && response.header("Access-Control-Allow-Methods").is_none()
+    {
+        "the response had neither `Allow` nor `Access-Control-Allow-Methods`".to_string()
+    } else {
+        return Outcome::Passed;
+    };
+    Outcome::Failed(Error::MethodMishandled {
+        method: "OPTIONS".to_string(),
+        problem,
+    })
+}
+
+/// Sends a `HEAD`, which may be refused but mustn't crash the server or get a body back.
+fn head_handled(context: &Context, url: &str, auth: &Auth) -> Outcome {
+    let response = authenticate(context, HttpRequest::new("HEAD", url), auth)
+        .and_then(|request| context.send(request, None));
+    let response = match response {
+        Ok(response) => response,
+        Err(err) => return Outcome::Failed(err),
+    };
+    let problem = if response.status >= 500 {
+        format!("it responded with status {}", response.status)
+    } else if !response.body.is_empty() {
+        "the response had a body".to_string()
+    } else {
+        return Outcome::Passed;
+    };
+    Outcome::Failed(Error::MethodMishandled {
+        method: "HEAD".to_string(),
+        problem,
+    })
+}
+
+/// Sends the basic query twice in a row, checking that neither response has `Connection: close`,
+/// which makes every real client pay for a new connection (and TLS handshake) per request. This
+/// only checks the header: the transport doesn't say whether a connection was actually reused. The
+/// second request failing is also reported, since that usually means a connection was kept open
+/// but not actually served.
+fn no_connection_close(context: &Context, url: &str, auth: &Auth) -> Outcome {
+    let query = json!({"query": "query{__typename}"});
+    let mut closed = false;
+    for attempt in 0..2 {
+        let response = make_request(context, url, auth)
+            .and_then(|request| context.send(request, Some(&query)));
+        let response = match response {
+            Ok(response) => response,
+            Err(Error::Cancelled) => return Outcome::Failed(Error::Cancelled),
+            Err(err) if attempt > 0 => {
+                return Outcome::Failed(Error::ConnectionClose(format!(
+                    "a request right after a successful one failed ({err})"
+                )))
+            }
+            Err(err) => return Outcome::Failed(err),
+        };
+        closed |= response
+            .header("Connection")
+            .is_some_and(|value| value.eq_ignore_ascii_case("close"));
+    }
+    if closed {
+        Outcome::Failed(Error::ConnectionClose(
+            "the server responded with `Connection: close`, so every request needs a new connection"
+                .to_string(),
+        ))
+    } else {
+        Outcome::Passed
+    }
+}
+
+/// What a response was, for reporting negotiation: its `Content-Type`, or its status if that's an
+/// error.
+fn describe_response(response: &HttpResponse) -> String {
+    if response.status >= 400 {
+        format!("status {}", response.status)
+    } else if response.content_type().is_empty() {
+        "no content type".to_string()
+    } else {
+        response.content_type().to_string()
+    }
+}
+
+#[cfg(test)]
+mod test_run_compliance_checks {
+    use super::*;
+    use crate::testing::context;
+
+    fn run(
+        handler: fn(&crate::HttpRequest) -> HttpResponse,
+    ) -> (Vec<CheckResult>, BTreeMap<String, String>) {
+        let context = context(move |request| Ok(handler(request)));
+        let mut results = Vec::new();
+        let mut negotiated = BTreeMap::new();
+        run_compliance_checks(
+            &context,
+            "https://example.com/graphql",
+            &Auth::Disabled,
+            &mut |event| match event {
+                Event::Finished(result) => results.push(result),
+                Event::Observed(Fact::Negotiated(accepts)) => negotiated = accepts,
+                _ => {}
+            },
+        );
+        (results, negotiated)
+    }
+
+    fn data() -> HttpResponse {
+        HttpResponse::json(200, &json!({"data": {"__typename": "Query"}}))
+    }
+
+    #[test]
+    fn negotiated() {
+        let (results, negotiated) = run(|request| match request.header("Accept") {
+            Some(GRAPHQL_RESPONSE) => {
+                HttpResponse::new(200, r#"{"data": {"__typename": "Query"}}"#)
+                    .with_header("Content-Type", GRAPHQL_RESPONSE)
+            }
+            Some(UNSUPPORTED) => HttpResponse::new(406, "Not Acceptable"),
+            _ => data(),
+        });
+        assert_eq!(results[0].outcome, Outcome::Passed);
+        assert_eq!(
+            negotiated,
+            BTreeMap::from([
+                (JSON.to_string(), JSON.to_string()),
+                (GRAPHQL_RESPONSE.to_string(), GRAPHQL_RESPONSE.to_string()),
+                (UNSUPPORTED.to_string(), "status 406".to_string()),
+            ])
+        );
+    }
+
+    #[test]
+    fn methods() {
+        let (results, _) = run(|request| match request.method.as_str() {
+            "OPTIONS" => HttpResponse::new(204, "").with_header("Allow", "GET, POST, OPTIONS"),
+            "HEAD" => HttpResponse::new(500, "Internal Server Error"),
+            _ => data(),
+        });
+        assert_eq!(results[1].outcome, Outcome::Passed);
+        assert_eq!(
+            results[2].outcome,
+            Outcome::Failed(Error::MethodMishandled {
+                method: "HEAD".to_string(),
+                problem: "it responded with status 500".to_string(),
+            })
+        );
+    }
+
+    #[test]
+    fn options_without_allowed_methods() {
+        let (results, _) = run(|request| match request.method.as_str() {
+            "OPTIONS" => HttpResponse::new(200, ""),
+            _ => HttpResponse::new(405, ""),
+        });
+        assert_eq!(
+            results[1].outcome,
+            Outcome::Failed(Error::MethodMishandled {
+                method: "OPTIONS".to_string(),
+                problem: "the response had neither `Allow` nor `Access-Control-Allow-Methods`"
+                    .to_string(),
+            })
+        );
+        assert_eq!(results[2].outcome, Outcome::Passed);
+    }
+
+    #[test]
+    fn connection_close() {
+        let (results, _) = run(|_| data().with_header("Connection", "close"));
+        assert_eq!(
+            results[3].outcome,
+            Outcome::Failed(Error::ConnectionClose(
+                "the server responded with `Connection: close`, so every request needs a new connection"
+                    .to_string()
+            ))
+        );
+    }
+
+    #[test]
+    fn unsupported_crashes() {
+        let (results, _) = run(|request| match request.header("Accept") {
+            Some(UNSUPPORTED) => HttpResponse::new(500, "Internal Server Error"),
+            _ => data(),
+        });
+        assert_eq!(
+            results[0].outcome,
+            Outcome::Failed(Error::AcceptNotNegotiated {
+                accept: UNSUPPORTED.to_string(),
+                got: "status 500".to_string(),
+            })
+        );
+    }
+}
@@ -0,0 +1,739 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs::read_to_string;
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::capabilities::ContentType;
+use crate::graphql::{AllowedError, ErrorsPolicy};
+use crate::http::{validate_url, RateLimit};
+use crate::schema::{check_sdl, AllowedChanges, Schema};
+use crate::{
+    split_header, AccessMatrix, Auth, Check, CheckResult, Checker, Error, Fleet, Introspection,
+    Monitor, Outcome, Profile, Rejection, Severity, Subgraph,
+};
+
+/// Everything needed to configure a run, in one place so it can be loaded from a file (any serde
+/// format works, e.g., JSON or YAML) or built from the action's inputs.
+///
+/// ```json
+/// {
+///     "url": "https://example.com/graphql",
+///     "auth": "Authorization: Bearer token",
+///     "subgraph": "secure",
+///     "timeout_ms": 5000,
+///     "retries": 2,
+///     "skip": ["introspection_disabled"],
+///     "severities": { "auth_enforced": "warning" }
+/// }
+/// ```
+#[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct CheckConfig {
+    pub url: String,
+    /// Endpoints to check instead of `url`, by a name made of letters, digits, `-` and `_`, each
+    /// with the rest of this config, see [`CheckConfig::fleet`].
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub endpoints: BTreeMap<String, String>,
+    /// A path to SDL to run the schema checks on instead of checking `url`, see
+    /// [`CheckConfig::check_sdl`].
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sdl: Option<String>,
+    /// A path to a saved schema (SDL, or the JSON of an introspection result) which `sdl` mustn't
+    /// break.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub schema_baseline: Option<String>,
+    /// A header in the format `name: value` sent with authenticated requests.
+    #[serde(skip_serializing_if = "Option::is_none", serialize_with = "redact")]
+    pub auth: Option<String>,
+    /// A header which the server should reject, like an expired token, see
+    /// [`Checker::invalid_auth`].
+    #[serde(skip_serializing_if = "Option::is_none", serialize_with = "redact")]
+    pub invalid_auth: Option<String>,
+    /// How the server is expected to reject requests without valid auth, e.g.,
+    /// `{"status": 401}` or `{"error_code": "UNAUTHENTICATED"}`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub unauthenticated: Option<Rejection>,
+    /// Valid credentials which aren't allowed to run `privileged_query`, see
+    /// [`Checker::auth_semantics`].
+    #[serde(skip_serializing_if = "Option::is_none", serialize_with = "redact")]
+    pub unprivileged_auth: Option<String>,
+    /// A query `unprivileged_auth` should be forbidden from running.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub privileged_query: Option<String>,
+    /// How many times to time the basic query, see [`Checker::latency_samples`].
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub latency_samples: Option<u32>,
+    /// The longest the p95 of `latency_samples` may be, in milliseconds.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_p95_ms: Option<u64>,
+    /// How many basic queries to send at once, see [`Checker::concurrency`].
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub concurrency: Option<u32>,
+    /// The longest the basic queries sent at once may take all together, in milliseconds.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub concurrency_budget_ms: Option<u64>,
+    /// How many times to run every check, see [`Checker::stability_runs`].
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub stability_runs: Option<u32>,
+    /// How long to keep running the checks for, like `10m`, see [`Monitor`].
+    #[serde(skip_serializing_if = "Option::is_none", with = "human_duration")]
+    pub monitor_for: Option<Duration>,
+    /// How often to run the checks during `monitor_for`, like `30s`.
+    #[serde(skip_serializing_if = "Option::is_none", with = "human_duration")]
+    pub monitor_interval: Option<Duration>,
+    /// The lowest percentage of runs during `monitor_for` which must pass, 100 by default.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub min_availability: Option<u8>,
+    /// The `Cache-Control` directives responses must have, e.g., `no-store`, see
+    /// [`Checker::cache_control`].
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cache_control: Option<String>,
+    /// Whether persisted `GET` queries should be cached by a CDN, see [`Checker::cdn_cache`].
+    pub cdn_cache: bool,
+    /// Whether persisted queries must match their hash, see [`Checker::persisted_query_hash`].
+    pub persisted_query_hash: bool,
+    /// The only content types the server may accept, see [`Checker::content_types`].
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub content_types: Option<Vec<ContentType>>,
+    /// Whether unknown fields must be rejected as invalid, see [`Checker::unknown_field`].
+    pub unknown_field: bool,
+    /// Whether to check HTTP handling against the spec, see [`Checker::compliance`].
+    pub compliance: bool,
+    /// Whether to send deliberately broken requests, see [`Checker::robustness`].
+    pub robustness: bool,
+    /// The operation to send with variables of the wrong type, see [`Checker::wrong_variables`].
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub wrong_variables: Option<Value>,
+    /// Which roles may run which operations, see [`Checker::access`].
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub access: Option<AccessMatrix>,
+    pub subgraph: Subgraph,
+    /// Defaults to [`Introspection::Allow`] for subgraphs and [`Introspection::Disallow`] otherwise.
+    pub introspection: Option<Introspection>,
+    /// The longest any single request may take, in milliseconds, 60 seconds if not set.
+    pub timeout_ms: Option<u64>,
+    /// How many more times to try a request which got no response at all.
+    pub retries: u32,
+    /// The most requests to send a second, see [`Checker::rate_limit`].
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rate_limit: Option<u32>,
+    /// What the checks do with `errors` next to `data`, see [`Checker::errors_policy`]. Defaults
+    /// to [`ErrorsPolicy::Allowlist`] with `allowed_errors` and [`ErrorsPolicy::Fail`] otherwise.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub errors_policy: Option<ErrorsPolicy>,
+    /// The errors which `errors_policy: allowlist` lets through, see [`AllowedError`].
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub allowed_errors: Vec<AllowedError>,
+    /// Whether to ask as much as possible in one request, see [`Checker::combine_probes`].
+    pub combine_probes: bool,
+    /// Whether to look up an entity of each type with a `@key`, see [`Checker::check_entities`].
+    pub check_entities: bool,
+    /// Representations to look entities up with, by type name, instead of synthetic ones.
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub entity_representations: BTreeMap<String, Value>,
+    /// A query spanning several subgraphs to send through the router at `url`, see
+    /// [`Checker::smoke_query`].
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub smoke_query: Option<String>,
+    /// Subgraphs the router's query plan for `smoke_query` must fetch from.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub smoke_subgraphs: Vec<String>,
+    /// The health check endpoint of an Apollo Router in front of `url`, see
+    /// [`Checker::router_health`].
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub router_health: Option<String>,
+    /// Another endpoint whose schema `url` should match, see [`Checker::compare_schema`].
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub compare_url: Option<String>,
+    /// How the schema of `url` may differ from the one of `compare_url`, not at all by default.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub compare_allow: Option<AllowedChanges>,
+    /// The server behind `url`, enabling the extra checks for it.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub profile: Option<Profile>,
+    /// Checks which are reported as skipped, see [`Checker::skip`].
+    pub skip: BTreeSet<Check>,
+    /// Overrides how much a failure of each check matters.
+    pub severities: BTreeMap<Check, Severity>,
+    /// The exit code for each kind of failure, by [`Error::code`]. Failures mapped to `0` don't
+    /// fail the run and are reported as warnings instead.
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub exit_codes: BTreeMap<String, u8>,
+}
+
+impl CheckConfig {
+    pub fn from_json(json: &str) -> Result<Self, Error> {
+        serde_json::from_str(json).map_err(|err| Error::BadConfig(err.to_string()))
+    }
+
+    pub fn errors_policy(&self) -> ErrorsPolicy {
+        self.errors_policy
+            .unwrap_or(if self.allowed_errors.is_empty() {
+                ErrorsPolicy::Fail
+            } else {
+                ErrorsPolicy::Allowlist
+            })
+    }
+
+    pub fn auth(&self) -> Auth<'_> {
+        self.auth
+            .as_deref()
+            .filter(|header| !header.is_empty())
+            .map_or(Auth::Disabled, |header| Auth::Enabled {
+                header: header.into(),
+            })
+    }
+
+    /// Finds every problem with this config which would otherwise only show up part way through a
+    /// run, so they can all be reported before making any requests.
+    pub fn validate(&self) -> Vec<Error> {
+        let mut problems = Vec::new();
+        if self.sdl.is_some() {
+            if !self.url.is_empty() || !self.endpoints.is_empty() {
+                problems.push(Error::BadInput(
+                    "`sdl` can't be given together with `endpoint` or `endpoints`".to_string(),
+                ));
+            }
+        } else if self.endpoints.is_empty() {
+            if let Err(problem) = validate_url(&self.url) {
+                problems.push(Error::BadUri(problem));
+            }
+        } else if !self.url.is_empty() {
+            problems.push(Error::BadInput(
+                "`endpoint` and `endpoints` can't be given together".to_string(),
+            ));
+        }
+        for (name, url) in &self.endpoints {
+            if name.is_empty()
+                || !name
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+            {
+                problems.push(Error::BadInput(format!(
+                    "endpoint name `{name}` must only have letters, digits, `-` and `_`"
+                )));
+            }
+            if let Err(problem) = validate_url(url) {
+                problems.push(Error::BadUri(problem));
+            }
+        }
+        if let Auth::Enabled { header } = self.auth() {
+            if let Err(err) = split_header(&header) {
+                problems.push(err);
+            }
+        }
+        if let Some(header) = self
+            .invalid_auth
+            .as_deref()
+            .filter(|header| !header.is_empty())
+        {
+            if let Err(err) = split_header(header) {
+                problems.push(err);
+            }
+        }
+        if let Some(header) = &self.unprivileged_auth {
+            if let Err(err) = split_header(header) {
+                problems.push(err);
+            }
+        }
+        if let Some(body) = &self.wrong_variables {
+            if !body["query"].is_string() || !body["variables"].is_object() {
+                problems.push(Error::BadInput(
+                    "`wrong_variables` must have a `query` string and a `variables` object"
+                        .to_string(),
+                ));
+            }
+        }
+        if self.unprivileged_auth.is_some() != self.privileged_query.is_some() {
+            problems.push(Error::BadInput(
+                "`unprivileged_auth` and `privileged_query` must be given together".to_string(),
+            ));
+        }
+        if let Some(matrix) = &self.access {
+            problems.extend(matrix.validate());
+        }
+        if let Some(Err(problem)) = self.router_health.as_deref().map(validate_url) {
+            problems.push(Error::BadUri(problem));
+        }
+        if let Some(Err(problem)) = self.compare_url.as_deref().map(validate_url) {
+            problems.push(Error::BadUri(problem));
+        }
+        if self.compare_allow.is_some() && self.compare_url.is_none() {
+            problems.push(Error::BadInput(
+                "`compare_allow` needs `compare_url`".to_string(),
+            ));
+        }
+        if self.compare_url.is_some() && self.sdl.is_some() {
+            problems.push(Error::BadInput(
+                "`compare_url` can't be given together with `sdl`".to_string(),
+            ));
+        }
+        match (self.errors_policy, self.allowed_errors.is_empty()) {
+            (Some(ErrorsPolicy::Allowlist), true) => problems.push(Error::BadInput(
+                "`errors_policy: allowlist` needs `allowed_errors`".to_string(),
+            )),
+            (Some(ErrorsPolicy::Fail | ErrorsPolicy::IgnoreIfData), false) => {
+                problems.push(Error::BadInput(
+                    "`allowed_errors` needs `errors_policy: allowlist`".to_string(),
+                ));
+            }
+            _ => {}
+        }
+        if self.rate_limit == Some(0) {
+            problems.push(Error::BadInput(
+                "`rate_limit` must be more than 0".to_string(),
+            ));
+        }
+        if self.latency_samples == Some(0) {
+            problems.push(Error::BadInput(
+                "`latency_samples` must be more than 0".to_string(),
+            ));
+        }
+        if self.max_p95_ms.is_some() && self.latency_samples.is_none() {
+            problems.push(Error::BadInput(
+                "`max_p95_ms` needs `latency_samples`".to_string(),
+            ));
+        }
+        if self.concurrency == Some(0) {
+            problems.push(Error::BadInput(
+                "`concurrency` must be more than 0".to_string(),
+            ));
+        }
+        if self.concurrency_budget_ms.is_some() && self.concurrency.is_none() {
+            problems.push(Error::BadInput(
+                "`concurrency_budget_ms` needs `concurrency`".to_string(),
+            ));
+        }
+        if self.schema_baseline.is_some() && self.sdl.is_none() {
+            problems.push(Error::BadInput("`schema_baseline` needs `sdl`".to_string()));
+        }
+        if self.monitor_for.is_none()
+            && (self.monitor_interval.is_some() || self.min_availability.is_some())
+        {
+            problems.push(Error::BadInput(
+                "`monitor_interval` and `min_availability` need `monitor_for`".to_string(),
+            ));
+        }
+        if self.monitor_interval == Some(Duration::ZERO) {
+            problems.push(Error::BadInput(
+                "`monitor_interval` must be more than 0".to_string(),
+            ));
+        }
+        if self.min_availability.is_some_and(|percent| percent > 100) {
+            problems.push(Error::BadInput(
+                "`min_availability` is a percentage, so it can't be more than 100".to_string(),
+            ));
+        }
+        if self.stability_runs == Some(0) {
+            problems.push(Error::BadInput(
+                "`stability_runs` must be more than 0".to_string(),
+            ));
+        }
+        if self.timeout_ms == Some(0) {
+            problems.push(Error::BadInput(
+                "`timeout_ms` must be more than 0".to_string(),
+            ));
+        }
+        for check in self.severities.keys() {
+            if self.skip.contains(check) {
+                problems.push(Error::BadInput(format!(
+                    "`{}` is skipped, so it can't have a severity",
+                    check.name()
+                )));
+            }
+        }
+        problems
+    }
+
+    /// A [`Checker`] configured from this, which can be customized further before running.
+    pub fn checker(&self) -> Checker<'_> {
+        self.checker_for(&self.url)
+    }
+
+    /// Checks every one of `endpoints` with the rest of this config, in the order of their names.
+    pub fn fleet(&self) -> Fleet<'_> {
+        Fleet::new(
+            self.endpoints
+                .values()
+                .map(|url| self.checker_for(url))
+                .collect(),
+        )
+    }
+
+    /// Runs the checks over and over for `monitor_for`, if it's set.
+    pub fn monitor(&self) -> Option<Monitor<'_>> {
+        let monitor = Monitor::new(self.checker(), self.monitor_for?);
+        Some(match self.monitor_interval {
+            Some(interval) => monitor.interval(interval),
+            None => monitor,
+        })
+    }
+
+    /// Reads `sdl` (and `schema_baseline`, if given) and runs [`check_sdl`] on it, skipping the
+    /// checks in `skip` and applying `severities`.
+    pub fn check_sdl(&self) -> Result<Vec<CheckResult>, Error> {
+        let read = |path: &str| {
+            read_to_string(path).map_err(|err| Error::BadSchema(format!("{path}: {err}")))
+        };
+        let sdl = read(self.sdl.as_deref().unwrap_or_default())?;
+        let baseline = match &self.schema_baseline {
+            Some(path) => Some(Schema::parse(&read(path)?)?),
+            None => None,
+        };
+        let mut results = check_sdl(&sdl, baseline.as_ref());
+        for result in &mut results {
+            if self.skip.contains(&result.check) {
+                result.outcome = Outcome::Skipped;
+            }
+            if let Some(severity) = self.severities.get(&result.check) {
+                result.severity = *severity;
+            }
+        }
+        Ok(results)
+    }
+
+    fn checker_for<'a>(&'a self, url: &'a str) -> Checker<'a> {
+        let mut checker = Checker::new(url)
+            .auth(self.auth())
+            .subgraph(self.subgraph)
+            .retries(self.retries)
+            .errors_policy(self.errors_policy())
+            .allowed_errors(self.allowed_errors.clone());
+        if let Some(introspection) = self.introspection {
+            checker = checker.introspection(introspection);
+        }
+        if let Some(per_second) = self.rate_limit {
+            checker = checker.rate_limit(&RateLimit::per_second(per_second));
+        }
+        if self.combine_probes {
+            checker = checker.combine_probes();
+        }
+        if let Some(header) = self
+            .invalid_auth
+            .as_deref()
+            .filter(|header| !header.is_empty())
+        {
+            checker = checker.invalid_auth(header);
+        }
+        if let Some(rejection) = &self.unauthenticated {
+            checker = checker.unauthenticated(rejection.clone());
+        }
+        if let (Some(header), Some(query)) = (&self.unprivileged_auth, &self.privileged_query) {
+            checker = checker.auth_semantics(header, query);
+        }
+        if let Some(samples) = self.latency_samples {
+            checker = checker.latency_samples(samples, self.max_p95_ms.map(Duration::from_millis));
+        }
+        if let Some(concurrency) = self.concurrency {
+            checker = checker.concurrency(
+                concurrency,
+                self.concurrency_budget_ms.map(Duration::from_millis),
+            );
+        }
+        if let Some(runs) = self.stability_runs {
+            checker = checker.stability_runs(runs);
+        }
+        if let Some(policy) = &self.cache_control {
+            checker = checker.cache_control(policy);
+        }
+        if self.cdn_cache {
+            checker = checker.cdn_cache();
+        }
+        if self.persisted_query_hash {
+            checker = checker.persisted_query_hash();
+        }
+        if let Some(allowed) = &self.content_types {
+            checker = checker.content_types(allowed.clone());
+        }
+        if self.unknown_field {
+            checker = checker.unknown_field();
+        }
+        if self.compliance {
+            checker = checker.compliance();
+        }
+        if self.robustness {
+            checker = checker.robustness();
+        }
+        if let Some(body) = &self.wrong_variables {
+            checker = checker.wrong_variables(body.clone());
+        }
+        if let Some(matrix) = &self.access {
+            checker = checker.access(matrix.clone());
+        }
+        if self.check_entities {
+            checker = checker.check_entities(self.entity_representations.clone());
+        }
+        if let Some(query) = &self.smoke_query {
+            checker = checker.smoke_query(query, self.smoke_subgraphs.clone());
+        }
+        if let Some(router_health) = &self.router_health {
+            checker = checker.router_health(router_health);
+        }
+        if let Some(compare_url) = &self.compare_url {
+            checker = checker.compare_schema(compare_url, self.compare_allow.unwrap_or_default());
+        }
+        if let Some(profile) = self.profile {
+            checker = checker.profile(profile);
+        }
+        if let Some(timeout_ms) = self.timeout_ms {
+            checker = checker.timeout(Duration::from_millis(timeout_ms));
+        }
+        for check in &self.skip {
+            checker = checker.skip(*check);
+        }
+        for (check, severity) in &self.severities {
+            checker = checker.severity(*check, *severity);
+        }
+        checker
+    }
+}
+
+/// Parses a duration like `90s`, `10m` or `1h`, where a plain number is seconds.
+pub fn parse_duration(duration: &str) -> Result<Duration, String> {
+    let invalid = || format!("`{duration}` isn't a duration like `90s`, `10m` or `1h`");
+    let trimmed = duration.trim();
+    let (number, unit) = trimmed.split_at(
+        trimmed
+            .find(|c: char| !c.is_ascii_digit())
+            .unwrap_or(trimmed.len()),
+    );
+    let number: u64 = number.parse().map_err(|_| invalid())?;
+    let seconds = match unit.trim() {
+        "ms" => return Ok(Duration::from_millis(number)),
+        "" | "s" => number,
+        "m" => number.saturating_mul(60),
+        "h" => number.saturating_mul(60 * 60),
+        _ => return Err(invalid()),
+    };
+    Ok(Duration::from_secs(seconds))
+}
+
+/// Durations written the way people write them, see [`parse_duration`].
+mod human_duration {
+    use std::time::Duration;
+
+    use serde::{de, Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(
+        duration: &Option<Duration>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        match duration {
+            Some(duration) => serializer.serialize_str(&format!("{}ms", duration.as_millis())),
+            None => serializer.serialize_none(),
+        }
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Option<Duration>, D::Error> {
+        Option::<String>::deserialize(deserializer)?
+            .map(|duration| super::parse_duration(&duration).map_err(de::Error::custom))
+            .transpose()
+    }
+}
+
+fn redact<S: serde::Serializer>(header: &Option<String>, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_str(&crate::redacted_header(
+        header.as_deref().unwrap_or_default(),
+    ))
+}
+
+#[cfg(test)]
+mod test_check_config {
+    use super::*;
+
+    #[test]
+    fn from_json() {
+        let config = CheckConfig::from_json(
+            r#"{
+                "url": "https://example.com/graphql",
+                "auth": "Authorization: Bearer hunter2",
+                "subgraph": "secure",
+                "retries": 2,
+                "skip": ["introspection_disabled"],
+                "severities": {"auth_enforced": "warning"},
+                "exit_codes": {"introspection_enabled": 0}
+            }"#,
+        )
+        .unwrap();
+        assert_eq!(config.subgraph, Subgraph::Secure);
+        assert_eq!(config.retries, 2);
+        assert_eq!(
+            config.auth(),
+            Auth::Enabled {
+                header: "Authorization: Bearer hunter2".into()
+            }
+        );
+        assert_eq!(
+            config.severities.get(&Check::AuthEnforced),
+            Some(&Severity::Warning)
+        );
+        assert_eq!(config.exit_codes.get("introspection_enabled"), Some(&0));
+    }
+
+    #[test]
+    fn defaults() {
+        let config = CheckConfig::from_json(r#"{"url": "https://example.com/graphql"}"#).unwrap();
+        assert_eq!(config.auth(), Auth::Disabled);
+        assert_eq!(config.subgraph, Subgraph::NotASubgraph);
+        assert!(config.skip.is_empty());
+    }
+
+    #[test]
+    fn validate() {
+        let config = CheckConfig {
+            url: "example.com/graphql".to_string(),
+            auth: Some("Bearer hunter2".to_string()),
+            timeout_ms: Some(0),
+            skip: BTreeSet::from([Check::AuthEnforced]),
+            severities: BTreeMap::from([(Check::AuthEnforced, Severity::Warning)]),
+            ..CheckConfig::default()
+        };
+        let codes: Vec<_> = config.validate().iter().map(Error::code).collect();
+        assert_eq!(codes, ["bad_uri", "bad_header", "bad_input", "bad_input"]);
+    }
+
+    #[test]
+    fn validate_sdl() {
+        let config = CheckConfig {
+            url: "https://example.com/graphql".to_string(),
+            sdl: Some("schema.graphql".to_string()),
+            ..CheckConfig::default()
+        };
+        assert_eq!(
+            config.validate(),
+            [Error::BadInput(
+                "`sdl` can't be given together with `endpoint` or `endpoints`".to_string()
+            )]
+        );
+        let config = CheckConfig {
+            sdl: Some("schema.graphql".to_string()),
+            ..CheckConfig::default()
+        };
+        assert_eq!(config.validate(), []);
+    }
+
+    #[test]
+    fn validate_endpoints() {
+        let config = CheckConfig {
+            url: "https://example.com/graphql".to_string(),
+            endpoints: BTreeMap::from([
+                (
+                    "products".to_string(),
+                    "https://example.com/products".to_string(),
+                ),
+                ("re views".to_string(), "example.com/reviews".to_string()),
+            ]),
+            ..CheckConfig::default()
+        };
+        let codes: Vec<_> = config.validate().iter().map(Error::code).collect();
+        assert_eq!(codes, ["bad_input", "bad_input", "bad_uri"]);
+    }
+
+    #[test]
+    fn validate_errors_policy() {
+        let config = CheckConfig {
+            url: "https://example.com/graphql".to_string(),
+            errors_policy: Some(ErrorsPolicy::Allowlist),
+            ..CheckConfig::default()
+        };
+        assert_eq!(
+            config.validate(),
+            [Error::BadInput(
+                "`errors_policy: allowlist` needs `allowed_errors`".to_string()
+            )]
+        );
+        let config = CheckConfig::from_json(
+            r#"{"url": "https://example.com/graphql", "allowed_errors": ["DOWNSTREAM_SERVICE_ERROR", "/is deprecated$/"]}"#,
+        )
+        .unwrap();
+        assert_eq!(config.validate(), []);
+        assert_eq!(config.errors_policy(), ErrorsPolicy::Allowlist);
+        assert!(CheckConfig::from_json(r#"{"allowed_errors": ["/(/"]}"#).is_err());
+    }
+
+    #[test]
+    fn validate_rate_limit() {
+        let config = CheckConfig {
+            url: "https://example.com/graphql".to_string(),
+            rate_limit: Some(0),
+            ..CheckConfig::default()
+        };
+        assert_eq!(
+            config.validate(),
+            [Error::BadInput(
+                "`rate_limit` must be more than 0".to_string()
+            )]
+        );
+    }
+
+    #[test]
+    fn fleet() {
+        let config = CheckConfig {
+            endpoints: BTreeMap::from([
+                (
+                    "reviews".to_string(),
+                    "https://example.com/reviews".to_string(),
+                ),
+                (
+                    "products".to_string(),
+                    "https://example.com/products".to_string(),
+                ),
+            ]),
+            ..CheckConfig::default()
+        };
+        assert!(config.validate().is_empty());
+        let urls: Vec<_> = config.fleet().checkers().iter().map(Checker::url).collect();
+        assert_eq!(
+            urls,
+            [
+                "https://example.com/products",
+                "https://example.com/reviews"
+            ]
+        );
+    }
+
+    #[test]
+    fn monitor() {
+        let config = CheckConfig::from_json(
+            r#"{"url": "https://example.com/graphql", "monitor_for": "10m", "monitor_interval": "15s"}"#,
+        )
+        .unwrap();
+        assert_eq!(config.monitor_for, Some(Duration::from_secs(600)));
+        assert_eq!(config.monitor_interval, Some(Duration::from_secs(15)));
+        assert!(config.monitor().is_some());
+        let json = serde_json::to_value(&config).unwrap();
+        assert_eq!(json["monitor_for"], "600000ms");
+        assert_eq!(serde_json::from_value::<CheckConfig>(json).unwrap(), config);
+    }
+
+    #[test]
+    fn parse_durations() {
+        assert_eq!(parse_duration("90"), Ok(Duration::from_secs(90)));
+        assert_eq!(parse_duration("1h"), Ok(Duration::from_secs(3600)));
+        assert_eq!(parse_duration("250ms"), Ok(Duration::from_millis(250)));
+        assert!(parse_duration("10 minutes").is_err());
+        assert!(parse_duration("m").is_err());
+    }
+
+    #[test]
+    fn unknown_field() {
+        assert!(matches!(
+            CheckConfig::from_json(r#"{"url": "", "retry": 1}"#),
+            Err(Error::BadConfig(_))
+        ));
+    }
+
+    #[test]
+    fn auth_is_redacted() {
+        let config = CheckConfig {
+            auth: Some("Authorization: Bearer hunter2".to_string()),
+            ..CheckConfig::default()
+        };
+        let json = serde_json::to_string(&config).unwrap();
+        assert!(json.contains(r#""auth":"Authorization: [REDACTED]""#));
+    }
+}
@@ -0,0 +1,823 @@
+use std::collections::BTreeMap;
+use std::iter::Peekable;
+
+use graphql_parser::schema::{
+    parse_schema, Definition, Directive, Document, EnumValue, Field, InputValue, Type,
+    TypeDefinition, TypeExtension, Value as Gql,
+};
+use itertools::Itertools;
+use serde_json::{json, Value};
+
+use crate::http::Context;
+use crate::{get_json, make_request, post_json, Auth, Error, Outcome};
+
+/// Sent in place of any `ID` or `String` in a synthetic representation. No real entity should
+/// have it, so a working resolver returns `null` for it rather than an error.
+const SYNTHETIC_ID: &str = "graphql-check-action";
+
+/// What `extend schema` without operation types is parsed as, see [`parse_subgraph_schema`].
+const SCHEMA_EXTENSION: &str = "__SchemaExtension";
+
+const ENTITIES_QUERY: &str =
+    "query($representations:[_Any!]!){_entities(representations:$representations){__typename}}";
+
+/// Looks up one entity of each type with a `@key` in the subgraph's SDL, using the representation
+/// in `representations` for its type if there is one and a synthetic one built from the key
+/// otherwise. Fails with every entity the subgraph couldn't look up.
+pub(crate) fn check_entities(
+    context: &Context,
+    url: &str,
+    auth: &Auth,
+    representations: &BTreeMap<String, Value>,
+) -> Outcome {
+    let sdl = match post_json(context, url, auth, json!({"query": "query{_service{sdl}}"})) {
+        Ok(body) => body
+            .pointer("/data/_service/sdl")
+            .and_then(Value::as_str)
+            .map(String::from),
+        Err(Error::Cancelled) => return Outcome::Failed(Error::Cancelled),
+        Err(_) => None,
+    };
+    let Some(sdl) = sdl else {
+        return Outcome::Failed(Error::NotASubgraph);
+    };
+    let entities = match entity_representations(&sdl) {
+        Ok(entities) => entities,
+        Err(err) => return Outcome::Failed(err),
+    };
+    let mut unresolvable = Vec::new();
+    for (type_name, synthetic) in entities {
+        let representation = representations
+            .get(&type_name)
+            .cloned()
+            .unwrap_or(synthetic);
+        let body = json!({
+            "query": ENTITIES_QUERY,
+            "variables": {"representations": [representation]},
+        });
+        match post_json(context, url, auth, body) {
+            Ok(_) => {}
+            Err(Error::Cancelled) => return Outcome::Failed(Error::Cancelled),
+            Err(Error::GraphQLError(errors)) => {
+                unresolvable.push(format!("{type_name} ({})", errors.iter().join("; ")));
+            }
+            Err(err) => unresolvable.push(format!("{type_name} ({err})")),
+        }
+    }
+    if unresolvable.is_empty() {
+        Outcome::Passed
+    } else {
+        Outcome::Failed(Error::EntitiesUnresolvable(unresolvable))
+    }
+}
+
+/// Sends `query`, which should span several subgraphs, through a router. If `subgraphs` isn't
+/// empty, also asks the router to expose its query plan and fails unless every one of them is
+/// fetched from. Subgraphs' `ftv1` traces never reach the client, so the plan is the only way to
+/// see which subgraphs took part.
+pub(crate) fn check_smoke_query(
+    context: &Context,
+    url: &str,
+    auth: &Auth,
+    query: &str,
+    subgraphs: &[String],
+) -> Outcome {
+    let mut request = match make_request(context, url, auth) {
+        Ok(request) => request,
+        Err(err) => return Outcome::Failed(err),
+    };
+    if !subgraphs.is_empty() {
+        request = request.set("Apollo-Expose-Query-Plan", "true");
+    }
+    let body = match get_json(
+        context,
+        context.send(request, Some(&json!({ "query": query }))),
+    ) {
+        Ok(body) => body,
+        Err(err) => return Outcome::Failed(err),
+    };
+    if subgraphs.is_empty() {
+        return Outcome::Passed;
+    }
+    let Some(plan) = body.pointer("/extensions/apolloQueryPlan/object") else {
+        return Outcome::Failed(Error::QueryPlanUnavailable);
+    };
+    let mut fetched = Vec::new();
+    service_names(plan, &mut fetched);
+    let missing: Vec<String> = subgraphs
+        .iter()
+        .filter(|subgraph| !fetched.contains(&subgraph.as_str()))
+        .cloned()
+        .collect();
+    if missing.is_empty() {
+        Outcome::Passed
+    } else {
+        Outcome::Failed(Error::SubgraphsNotQueried(missing))
+    }
+}
+
+/// The `serviceName` of every fetch in a query plan.
+fn service_names<'a>(node: &'a Value, names: &mut Vec<&'a str>) {
+    match node {
+        Value::Object(object) => {
+            if let Some(Value::String(name)) = object.get("serviceName") {
+                names.push(name);
+            }
+            for value in object.values() {
+                service_names(value, names);
+            }
+        }
+        Value::Array(array) => {
+            for value in array {
+                service_names(value, names);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Each federation directive and the version of Federation 2 which added it.
+const FEDERATION_DIRECTIVES: [(&str, (u32, u32)); 18] = [
+    ("key", (2, 0)),
+    ("requires", (2, 0)),
+    ("provides", (2, 0)),
+    ("external", (2, 0)),
+    ("tag", (2, 0)),
+    ("extends", (2, 0)),
+    ("shareable", (2, 0)),
+    ("inaccessible", (2, 0)),
+    ("override", (2, 0)),
+    ("composeDirective", (2, 1)),
+    ("interfaceObject", (2, 3)),
+    ("authenticated", (2, 5)),
+    ("requiresScopes", (2, 5)),
+    ("policy", (2, 6)),
+    ("context", (2, 8)),
+    ("fromContext", (2, 8)),
+    ("cost", (2, 9)),
+    ("listSize", (2, 9)),
+];
+
+/// Checks that the federation `@link` in `sdl` imports every federation directive used without
+/// its namespace (e.g., `@shareable` rather than `@federation__shareable`), and that the linked
+/// version has every federation directive which is used. Passes a Federation 1 subgraph, which
+/// doesn't link anything.
+pub(crate) fn check_imports(sdl: &str) -> Result<(), Error> {
+    if sdl.trim().is_empty() {
+        return Ok(());
+    }
+    let document = parse_subgraph_schema(sdl)?;
+    let Some(link) = FederationLink::find(&document) else {
+        return Ok(());
+    };
+    let defined: Vec<&str> = document
+        .definitions
+        .iter()
+        .filter_map(|definition| match definition {
+            Definition::DirectiveDefinition(directive) => Some(directive.name.as_str()),
+            _ => None,
+        })
+        .collect();
+    let mut problems = Vec::new();
+    for (local, imported) in &link.imports {
+        if added_in(imported).is_none() {
+            problems.push(format!(
+                "`@{local}` is imported but isn't a federation directive"
+            ));
+        }
+    }
+    let mut unimported: BTreeMap<&str, Vec<String>> = BTreeMap::new();
+    let mut too_new: BTreeMap<&str, ((u32, u32), Vec<String>)> = BTreeMap::new();
+    for (name, location) in directive_usages(&document) {
+        let federation_name = link.imports.get(name).map(String::as_str).or_else(|| {
+            name.strip_prefix(&link.namespace)
+                .and_then(|rest| rest.strip_prefix("__"))
+        });
+        match federation_name.map(added_in) {
+            Some(Some(added)) if added > link.version => {
+                too_new
+                    .entry(name)
+                    .or_insert((added, Vec::new()))
+                    .1
+                    .push(location);
+            }
+            Some(_) => {}
+            None if added_in(name).is_some() && !defined.contains(&name) => {
+                unimported.entry(name).or_default().push(location);
+            }
+            None => {}
+        }
+    }
+    for (name, locations) in unimported {
+        problems.push(format!(
+            "`@{name}` is used on {} but isn't imported",
+            locations.iter().unique().join(", ")
+        ));
+    }
+    for (name, ((major, minor), locations)) in too_new {
+        problems.push(format!(
+            "`@{name}` is used on {} but needs federation v{major}.{minor}, and v{}.{} is linked",
+            locations.iter().unique().join(", "),
+            link.version.0,
+            link.version.1,
+        ));
+    }
+    if problems.is_empty() {
+        Ok(())
+    } else {
+        Err(Error::BadFederationImports(problems))
+    }
+}
+
+fn added_in(federation_name: &str) -> Option<(u32, u32)> {
+    FEDERATION_DIRECTIVES
+        .iter()
+        .find(|(name, _)| *name == federation_name)
+        .map(|(_, version)| *version)
+}
+
+/// The `@link(url: "https://specs.apollo.dev/federation/v2.x", ...)` of a subgraph.
+struct FederationLink {
+    version: (u32, u32),
+    /// What un-imported directives are prefixed with, e.g., `federation` for
+    /// `@federation__shareable`.
+    namespace: String,
+    /// The local name of each imported directive, without `@`, and its name in federation.
+    imports: BTreeMap<String, String>,
+}
+
+impl FederationLink {
+    fn find(document: &Document<'static, String>) -> Option<Self> {
+        document
+            .definitions
+            .iter()
+            .flat_map(|definition| match definition {
+                Definition::SchemaDefinition(schema) => &schema.directives[..],
+                Definition::TypeExtension(TypeExtension::Scalar(scalar))
+                    if scalar.name == SCHEMA_EXTENSION =>
+                {
+                    &scalar.directives[..]
+                }
+                _ => &[],
+            })
+            .filter(|directive| directive.name == "link")
+            .find_map(Self::parse)
+    }
+
+    fn parse(directive: &Directive<'static, String>) -> Option<Self> {
+        let argument = |name: &str| {
+            directive
+                .arguments
+                .iter()
+                .find(|(argument, _)| argument == name)
+                .map(|(_, value)| value)
+        };
+        let Some(Gql::String(url)) = argument("url") else {
+            return None;
+        };
+        let (_, version) = url.split_once("specs.apollo.dev/federation/v")?;
+        let (major, minor) = version.trim_end_matches('/').split_once('.')?;
+        let version = (major.parse().ok()?, minor.parse().ok()?);
+        let namespace = match argument("as") {
+            Some(Gql::String(namespace)) => namespace.clone(),
+            _ => "federation".to_string(),
+        };
+        let mut imports = BTreeMap::new();
+        if let Some(Gql::List(list)) = argument("import") {
+            for import in list {
+                let (name, alias) = match import {
+                    Gql::String(name) => (name, name),
+                    Gql::Object(object) => match (object.get("name"), object.get("as")) {
+                        (Some(Gql::String(name)), Some(Gql::String(alias))) => (name, alias),
+                        (Some(Gql::String(name)), _) => (name, name),
+                        _ => continue,
+                    },
+                    _ => continue,
+                };
+                // Anything else, e.g., `FieldSet`, is a type rather than a directive.
+                if let (Some(name), Some(alias)) = (name.strip_prefix('@'), alias.strip_prefix('@'))
+                {
+                    imports.insert(alias.to_string(), name.to_string());
+                }
+            }
+        }
+        Some(Self {
+            version,
+            namespace,
+            imports,
+        })
+    }
+}
+
+/// The name of every directive used in `document`, with where it's used, e.g., `Product.upc`.
+fn directive_usages<'a>(document: &'a Document<'static, String>) -> Vec<(&'a str, String)> {
+    let mut usages = Usages::default();
+    for definition in &document.definitions {
+        match definition {
+            Definition::SchemaDefinition(schema) => usages.add(&schema.directives, "schema"),
+            Definition::TypeDefinition(TypeDefinition::Scalar(ty)) => {
+                usages.add(&ty.directives, &ty.name);
+            }
+            Definition::TypeDefinition(TypeDefinition::Object(ty)) => {
+                usages.add(&ty.directives, &ty.name);
+                usages.fields(&ty.name, &ty.fields);
+            }
+            Definition::TypeDefinition(TypeDefinition::Interface(ty)) => {
+                usages.add(&ty.directives, &ty.name);
+                usages.fields(&ty.name, &ty.fields);
+            }
+            Definition::TypeDefinition(TypeDefinition::Union(ty)) => {
+                usages.add(&ty.directives, &ty.name);
+            }
+            Definition::TypeDefinition(TypeDefinition::Enum(ty)) => {
+                usages.add(&ty.directives, &ty.name);
+                usages.values(&ty.name, &ty.values);
+            }
+            Definition::TypeDefinition(TypeDefinition::InputObject(ty)) => {
+                usages.add(&ty.directives, &ty.name);
+                usages.inputs(&ty.name, &ty.fields);
+            }
+            Definition::TypeExtension(TypeExtension::Scalar(ty)) if ty.name == SCHEMA_EXTENSION => {
+                usages.add(&ty.directives, "schema");
+            }
+            Definition::TypeExtension(TypeExtension::Scalar(ty)) => {
+                usages.add(&ty.directives, &ty.name);
+            }
+            Definition::TypeExtension(TypeExtension::Object(ty)) => {
+                usages.add(&ty.directives, &ty.name);
+                usages.fields(&ty.name, &ty.fields);
+            }
+            Definition::TypeExtension(TypeExtension::Interface(ty)) => {
+                usages.add(&ty.directives, &ty.name);
+                usages.fields(&ty.name, &ty.fields);
+            }
+            Definition::TypeExtension(TypeExtension::Union(ty)) => {
+                usages.add(&ty.directives, &ty.name);
+            }
+            Definition::TypeExtension(TypeExtension::Enum(ty)) => {
+                usages.add(&ty.directives, &ty.name);
+                usages.values(&ty.name, &ty.values);
+            }
+            Definition::TypeExtension(TypeExtension::InputObject(ty)) => {
+                usages.add(&ty.directives, &ty.name);
+                usages.inputs(&ty.name, &ty.fields);
+            }
+            Definition::DirectiveDefinition(_) => {}
+        }
+    }
+    usages.0
+}
+
+#[derive(Default)]
+struct Usages<'a>(Vec<(&'a str, String)>);
+
+impl<'a> Usages<'a> {
+    fn add(&mut self, directives: &'a [Directive<'static, String>], location: &str) {
+        for directive in directives {
+            self.0.push((&directive.name, location.to_string()));
+        }
+    }
+
+    fn fields(&mut self, type_name: &str, fields: &'a [Field<'static, String>]) {
+        for field in fields {
+            self.add(&field.directives, &format!("{type_name}.{}", field.name));
+            for argument in &field.arguments {
+                let location = format!("{type_name}.{}({}:)", field.name, argument.name);
+                self.add(&argument.directives, &location);
+            }
+        }
+    }
+
+    fn values(&mut self, type_name: &str, values: &'a [EnumValue<'static, String>]) {
+        for value in values {
+            self.add(&value.directives, &format!("{type_name}.{}", value.name));
+        }
+    }
+
+    fn inputs(&mut self, type_name: &str, inputs: &'a [InputValue<'static, String>]) {
+        for input in inputs {
+            self.add(&input.directives, &format!("{type_name}.{}", input.name));
+        }
+    }
+}
+
+/// A synthetic representation for every resolvable entity type in `sdl`, built from its first
+/// `@key`, e.g., `{"__typename": "Product", "upc": "graphql-check-action"}`.
+fn entity_representations(sdl: &str) -> Result<Vec<(String, Value)>, Error> {
+    let document = parse_subgraph_schema(sdl)?;
+    let mut types: BTreeMap<&str, TypeInfo> = BTreeMap::new();
+    for definition in &document.definitions {
+        let (name, fields, directives, values) = match definition {
+            Definition::TypeDefinition(TypeDefinition::Object(ty)) => {
+                (&ty.name, &ty.fields[..], &ty.directives[..], Vec::new())
+            }
+            Definition::TypeDefinition(TypeDefinition::Interface(ty)) => {
+                (&ty.name, &ty.fields[..], &ty.directives[..], Vec::new())
+            }
+            Definition::TypeExtension(TypeExtension::Object(ty)) => {
+                (&ty.name, &ty.fields[..], &ty.directives[..], Vec::new())
+            }
+            Definition::TypeExtension(TypeExtension::Interface(ty)) => {
+                (&ty.name, &ty.fields[..], &ty.directives[..], Vec::new())
+            }
+            Definition::TypeDefinition(TypeDefinition::Enum(ty)) => (
+                &ty.name,
+                &[][..],
+                &[][..],
+                ty.values.iter().map(|value| value.name.as_str()).collect(),
+            ),
+            _ => continue,
+        };
+        let info = types.entry(name.as_str()).or_default();
+        info.fields.extend(fields);
+        info.enum_values.extend(values);
+        if info.key.is_none() {
+            info.key = directives.iter().find_map(resolvable_key);
+        }
+    }
+    Ok(types
+        .iter()
+        .filter_map(|(name, info)| {
+            let key = info.key.as_deref()?;
+            let mut representation = synthetic_value(&types, name, &mut tokens(key));
+            representation["__typename"] = json!(name);
+            Some(((*name).to_string(), representation))
+        })
+        .collect())
+}
+
+/// Parses a subgraph's SDL. Federation 2 subgraphs start with `extend schema @link(...)`, which
+/// `graphql_parser` doesn't support, so each `extend schema` with only directives is parsed as
+/// `extend scalar __SchemaExtension` instead.
+pub(crate) fn parse_subgraph_schema(sdl: &str) -> Result<Document<'static, String>, Error> {
+    let mut parseable = String::with_capacity(sdl.len());
+    let mut rest = sdl;
+    while let Some(start) = rest.find("extend schema") {
+        let (before, definition) = rest.split_at(start);
+        let after = &definition["extend schema".len()..];
+        parseable.push_str(before);
+        if starts_definition(before) && !has_operation_types(after) {
+            parseable.push_str("extend scalar ");
+            parseable.push_str(SCHEMA_EXTENSION);
+        } else {
+            parseable.push_str("extend schema");
+        }
+        rest = after;
+    }
+    parseable.push_str(rest);
+    parse_schema::<String>(&parseable)
+        .map(Document::into_static)
+        .map_err(|err| Error::BadSchema(err.to_string()))
+}
+
+/// Whether text following `before` is at the start of a line, rather than in a description.
+fn starts_definition(before: &str) -> bool {
+    before
+        .rsplit('\n')
+        .next()
+        .is_some_and(|line| line.trim().is_empty())
+}
+
+/// Whether the directives at the start of `definition` are followed by `{`.
+fn has_operation_types(definition: &str) -> bool {
+    let mut depth = 0_usize;
+    let mut in_string = false;
+    let mut previous = ' ';
+    for c in definition.chars() {
+        match c {
+            '"' => in_string = !in_string,
+            _ if in_string => {}
+            '(' => depth += 1,
+            ')' => depth = depth.saturating_sub(1),
+            '{' if depth == 0 => return true,
+            // A name which isn't a directive starts the next definition.
+            c if depth == 0
+                && (c.is_alphabetic() || c == '_')
+                && previous.is_whitespace()
+                && previous != '@' =>
+            {
+                return false;
+            }
+            _ => {}
+        }
+        previous = c;
+    }
+    false
+}
+
+#[derive(Default)]
+struct TypeInfo<'a> {
+    fields: Vec<&'a Field<'static, String>>,
+    enum_values: Vec<&'a str>,
+    /// The `fields` of the first `@key` which isn't `resolvable: false`.
+    key: Option<String>,
+}
+
+fn resolvable_key(directive: &Directive<String>) -> Option<String> {
+    if directive.name != "key" {
+        return None;
+    }
+    let argument = |name: &str| {
+        directive
+            .arguments
+            .iter()
+            .find(|(argument, _)| argument == name)
+            .map(|(_, value)| value)
+    };
+    if let Some(Gql::Boolean(false)) = argument("resolvable") {
+        return None;
+    }
+    match argument("fields") {
+        Some(Gql::String(fields)) => Some(fields.clone()),
+        _ => None,
+    }
+}
+
+type Tokens = Peekable<std::vec::IntoIter<String>>;
+
+/// The names and braces of a key's field set, e.g., `id organization { id }`.
+fn tokens(fields: &str) -> Tokens {
+    fields
+        .replace('{', " { ")
+        .replace('}', " } ")
+        .split_whitespace()
+        .map(String::from)
+        .collect_vec()
+        .into_iter()
+        .peekable()
+}
+
+/// An object with a placeholder for each field selected by `tokens`, up to the `}` closing this
+/// selection.
+fn synthetic_value(
+    types: &BTreeMap<&str, TypeInfo>,
+    type_name: &str,
+    tokens: &mut Tokens,
+) -> Value {
+    let mut object = json!({});
+    while let Some(token) = tokens.next() {
+        if token == "}" {
+            break;
+        }
+        let field_type = types.get(type_name).and_then(|info| {
+            info.fields
+                .iter()
+                .find(|field| field.name == token)
+                .map(|field| &field.field_type)
+        });
+        let value = if tokens.next_if(|next| next == "{").is_some() {
+            synthetic_value(types, field_type.map_or("", named_type), tokens)
+        } else {
+            field_type.map_or(json!(SYNTHETIC_ID), |field_type| {
+                synthetic_scalar(types, field_type)
+            })
+        };
+        object[token] = value;
+    }
+    object
+}
+
+fn synthetic_scalar(types: &BTreeMap<&str, TypeInfo>, field_type: &Type<String>) -> Value {
+    match field_type {
+        Type::NonNullType(inner) => synthetic_scalar(types, inner),
+        Type::ListType(inner) => json!([synthetic_scalar(types, inner)]),
+        Type::NamedType(name) => match name.as_str() {
+            "Int" => json!(0),
+            "Float" => json!(0.0),
+            "Boolean" => json!(false),
+            other => types
+                .get(other)
+                .and_then(|info| info.enum_values.first())
+                .map_or(json!(SYNTHETIC_ID), |value| json!(value)),
+        },
+    }
+}
+
+fn named_type<'a>(field_type: &'a Type<String>) -> &'a str {
+    match field_type {
+        Type::NonNullType(inner) | Type::ListType(inner) => named_type(inner),
+        Type::NamedType(name) => name,
+    }
+}
+
+#[cfg(test)]
+mod test_check_entities {
+    use super::*;
+    use crate::testing::{context, query};
+    use crate::HttpResponse;
+
+    const SDL: &str = r#"
+        extend schema @link(url: "https://specs.apollo.dev/federation/v2.3", import: ["@key"])
+
+        type Product @key(fields: "upc") @key(fields: "sku") {
+            upc: String!
+            sku: Int!
+        }
+
+        type Review @key(fields: "id author { id }") {
+            id: ID!
+            author: User!
+            rating: Rating
+        }
+
+        type User @key(fields: "id", resolvable: false) {
+            id: ID!
+        }
+
+        type Stock @key(fields: "warehouse status") {
+            warehouse: Int
+            status: Status!
+        }
+
+        enum Status { IN_STOCK OUT_OF_STOCK }
+    "#;
+
+    #[test]
+    fn synthetic_representations() {
+        assert_eq!(
+            entity_representations(SDL).unwrap(),
+            [
+                (
+                    "Product".to_string(),
+                    json!({"__typename": "Product", "upc": SYNTHETIC_ID})
+                ),
+                (
+                    "Review".to_string(),
+                    json!({"__typename": "Review", "id": SYNTHETIC_ID, "author": {"id": SYNTHETIC_ID}})
+                ),
+                (
+                    "Stock".to_string(),
+                    json!({"__typename": "Stock", "warehouse": 0, "status": "IN_STOCK"})
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn reports_unresolvable() {
+        let context = context(|request| {
+            if query(request) == Some("query{_service{sdl}}") {
+                return Ok(HttpResponse::json(
+                    200,
+                    &json!({"data": {"_service": {"sdl": SDL}}}),
+                ));
+            }
+            let representation = &request.body.as_ref().unwrap()["variables"]["representations"][0];
+            Ok(match representation["__typename"].as_str() {
+                Some("Review") => HttpResponse::json(
+                    200,
+                    &json!({"errors": [{"message": "Cannot read properties of undefined"}]}),
+                ),
+                _ => HttpResponse::json(200, &json!({"data": {"_entities": [null]}})),
+            })
+        });
+        let representations = BTreeMap::from([(
+            "Product".to_string(),
+            json!({"__typename": "Product", "upc": "1"}),
+        )]);
+        assert_eq!(
+            check_entities(
+                &context,
+                "https://example.com/graphql",
+                &Auth::Disabled,
+                &representations
+            ),
+            Outcome::Failed(Error::EntitiesUnresolvable(vec![
+                "Review (Cannot read properties of undefined)".to_string()
+            ]))
+        );
+    }
+}
+
+#[cfg(test)]
+mod test_check_smoke_query {
+    use super::*;
+    use crate::testing::context;
+    use crate::HttpResponse;
+
+    const QUERY: &str = "query{topProducts{name reviews{body}}}";
+
+    fn router(plan: Option<Value>) -> Context {
+        context(move |request| {
+            let exposed = request
+                .headers
+                .iter()
+                .any(|(name, value)| name == "Apollo-Expose-Query-Plan" && value == "true");
+            let mut body = json!({"data": {"topProducts": []}});
+            if let (true, Some(plan)) = (exposed, &plan) {
+                body["extensions"] = json!({"apolloQueryPlan": {"object": plan}});
+            }
+            Ok(HttpResponse::json(200, &body))
+        })
+    }
+
+    fn plan() -> Value {
+        json!({
+            "kind": "QueryPlan",
+            "node": {
+                "kind": "Sequence",
+                "nodes": [
+                    {"kind": "Fetch", "serviceName": "products"},
+                    {"kind": "Flatten", "node": {"kind": "Fetch", "serviceName": "reviews"}}
+                ]
+            }
+        })
+    }
+
+    fn check(context: &Context, subgraphs: &[&str]) -> Outcome {
+        let subgraphs: Vec<String> = subgraphs.iter().map(ToString::to_string).collect();
+        check_smoke_query(
+            context,
+            "https://example.com/graphql",
+            &Auth::Disabled,
+            QUERY,
+            &subgraphs,
+        )
+    }
+
+    #[test]
+    fn without_subgraphs() {
+        assert_eq!(check(&router(None), &[]), Outcome::Passed);
+    }
+
+    #[test]
+    fn every_subgraph_fetched() {
+        assert_eq!(
+            check(&router(Some(plan())), &["products", "reviews"]),
+            Outcome::Passed
+        );
+    }
+
+    #[test]
+    fn subgraph_missing() {
+        assert_eq!(
+            check(&router(Some(plan())), &["products", "inventory"]),
+            Outcome::Failed(Error::SubgraphsNotQueried(vec!["inventory".to_string()]))
+        );
+    }
+
+    #[test]
+    fn plan_not_exposed() {
+        assert_eq!(
+            check(&router(None), &["products"]),
+            Outcome::Failed(Error::QueryPlanUnavailable)
+        );
+    }
+}
+
+#[cfg(test)]
+mod test_check_imports {
+    use super::*;
+
+    #[test]
+    fn federation_1() {
+        check_imports(r#"type Product @key(fields: "upc") { upc: String! @external }"#).unwrap();
+    }
+
+    #[test]
+    fn imported_or_namespaced() {
+        check_imports(
+            r#"
+            extend schema
+                @link(url: "https://specs.apollo.dev/link/v1.0")
+                @link(
+                    url: "https://specs.apollo.dev/federation/v2.3"
+                    import: ["@key", {name: "@shareable", as: "@shared"}, "FieldSet"]
+                )
+
+            type Product @key(fields: "upc") @federation__interfaceObject {
+                upc: String! @shared
+                name: String @federation__inaccessible
+            }
+            "#,
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn problems() {
+        let sdl = r#"
+            extend schema @link(
+                url: "https://specs.apollo.dev/federation/v2.0"
+                import: ["@key", "@interfaceObject", "@sharable"]
+            )
+
+            directive @external on FIELD_DEFINITION
+
+            type Product @key(fields: "upc") @interfaceObject {
+                upc: String! @shareable @external
+                name(locale: String @inaccessible): String @shareable
+            }
+        "#;
+        assert_eq!(
+            check_imports(sdl),
+            Err(Error::BadFederationImports(vec![
+                "`@sharable` is imported but isn't a federation directive".to_string(),
+                "`@inaccessible` is used on Product.name(locale:) but isn't imported".to_string(),
+                "`@shareable` is used on Product.upc, Product.name but isn't imported".to_string(),
+                "`@interfaceObject` is used on Product but needs federation v2.3, and v2.0 is linked"
+                    .to_string(),
+            ]))
+        );
+    }
+}
@@ -0,0 +1,516 @@
+//! A tiny GraphQL server to point checks at, for testing workflows and the checks themselves
+//! without a live endpoint.
+//!
+//! It only knows `type Query { hello: String }`, plus introspection and (as a subgraph) `_service`,
+//! and rejects any other field like a real server would.
+
+use std::collections::HashMap;
+use std::io::{self, BufRead, BufReader, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream, ToSocketAddrs};
+use std::thread::{self, JoinHandle};
+
+use graphql_parser::query::{
+    parse_query, Definition, Document, OperationDefinition, Selection, SelectionSet,
+};
+use serde_json::{json, Map, Value};
+use url::Url;
+
+use crate::{split_header, HttpRequest, HttpResponse};
+
+/// The largest request body the server reads, anything longer is refused.
+const MAX_BODY_LEN: usize = 1024 * 1024;
+
+/// The SDL a subgraph fixture answers `_service` with.
+pub const SUBGRAPH_SDL: &str = r#"extend schema @link(url: "https://specs.apollo.dev/federation/v2.3", import: ["@key"])
+
+type Query {
+  hello: String
+}
+"#;
+
+/// How a fixture server behaves.
+///
+/// ```
+/// use graphql_check_action::fixture::Fixture;
+/// use graphql_check_action::Checker;
+///
+/// let server = Fixture {
+///     introspection: false,
+///     ..Fixture::default()
+/// }
+/// .start()
+/// .unwrap();
+/// assert!(Checker::new(&server.url()).report().passed());
+/// ```
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Fixture {
+    /// A header every request must have, in the format `name: value`. Requests without it get a
+    /// 401, as do those with a different value.
+    pub auth: Option<String>,
+    /// Whether `__schema` and `__type` are answered, rather than resolving to `null` with an
+    /// error. On by default.
+    pub introspection: bool,
+    /// Whether to answer `_service` like an Apollo Federation subgraph.
+    pub subgraph: bool,
+}
+
+impl Default for Fixture {
+    fn default() -> Self {
+        Self {
+            auth: None,
+            introspection: true,
+            subgraph: false,
+        }
+    }
+}
+
+impl Fixture {
+    /// Serves on a free port of `127.0.0.1` in the background, until the process exits.
+    pub fn start(self) -> io::Result<FixtureServer> {
+        self.serve("127.0.0.1:0")
+    }
+
+    /// Serves on `address` in the background, until the process exits. Each connection is handled
+    /// on its own thread.
+    pub fn serve(self, address: impl ToSocketAddrs) -> io::Result<FixtureServer> {
+        if let Some(header) = &self.auth {
+            split_header(header)
+                .map_err(|err| io::Error::new(io::ErrorKind::InvalidInput, err.to_string()))?;
+        }
+        let listener = TcpListener::bind(address)?;
+        let address = listener.local_addr()?;
+        let thread = thread::spawn(move || {
+            for stream in listener.incoming().flatten() {
+                let fixture = self.clone();
+                thread::spawn(move || {
+                    // A client which hangs up or sends garbage only affects its own connection.
+                    let _ = fixture.handle(stream);
+                });
+            }
+        });
+        Ok(FixtureServer { address, thread })
+    }
+
+    /// Answers a single request, like the server would.
+    pub fn respond(&self, request: &HttpRequest) -> HttpResponse {
+        if request.method == "OPTIONS" {
+            return HttpResponse::new(204, "")
+                .with_header("Allow", "GET, POST, OPTIONS")
+                .with_header("Access-Control-Allow-Origin", "*")
+                .with_header("Access-Control-Allow-Methods", "GET, POST, OPTIONS")
+                .with_header("Access-Control-Allow-Headers", "*");
+        }
+        if !matches!(request.method.as_str(), "GET" | "POST") {
+            return HttpResponse::new(405, "").with_header("Allow", "GET, POST, OPTIONS");
+        }
+        if let Some(expected) = &self.auth {
+            let authenticated = split_header(expected)
+                .is_ok_and(|(name, value)| request.header(name).map(str::trim) == Some(value));
+            if !authenticated {
+                return error_response(401, "Not authenticated", "UNAUTHENTICATED");
+            }
+        }
+        let params = if request.method == "GET" {
+            get_params(&request.url)
+        } else {
+            request.body.clone()
+        };
+        let Some(params) = params else {
+            return error_response(400, "The body must be a JSON object", "BAD_REQUEST");
+        };
+        let Some(query) = params.get("query").and_then(Value::as_str) else {
+            return error_response(400, "`query` is missing", "BAD_REQUEST");
+        };
+        let operation_name = params.get("operationName").and_then(Value::as_str);
+        self.execute(query, operation_name)
+    }
+
+    fn handle(&self, stream: TcpStream) -> io::Result<()> {
+        let host = stream.local_addr()?;
+        stream.set_nodelay(true)?;
+        let mut reader = BufReader::new(stream.try_clone()?);
+        let mut writer = stream;
+        while let Some((request, close)) = read_request(&mut reader, host)? {
+            let response = self.respond(&request);
+            write_response(&mut writer, &response, request.method == "HEAD", close)?;
+            if close {
+                break;
+            }
+        }
+        Ok(())
+    }
+
+    fn execute(&self, query: &str, operation_name: Option<&str>) -> HttpResponse {
+        let document = match parse_query::<&str>(query) {
+            Ok(document) => document,
+            Err(err) => {
+                return error_response(400, &err.to_string(), "GRAPHQL_PARSE_FAILED");
+            }
+        };
+        let operations: Vec<_> = document
+            .definitions
+            .iter()
+            .filter_map(|definition| match definition {
+                Definition::Operation(operation) => Some(operation),
+                Definition::Fragment(_) => None,
+            })
+            .collect();
+        let operation = match operation_name {
+            Some(name) => operations
+                .into_iter()
+                .find(|operation| operation_name_of(operation) == Some(name)),
+            None if operations.len() == 1 => operations.into_iter().next(),
+            None => None,
+        };
+        let selection_set = match operation {
+            Some(OperationDefinition::Query(query)) => &query.selection_set,
+            Some(OperationDefinition::SelectionSet(selection_set)) => selection_set,
+            Some(_) => {
+                return error_response(
+                    400,
+                    "Only queries are supported",
+                    "GRAPHQL_VALIDATION_FAILED",
+                );
+            }
+            None => {
+                return error_response(
+                    400,
+                    "Couldn't tell which operation to run",
+                    "GRAPHQL_VALIDATION_FAILED",
+                );
+            }
+        };
+        let mut fields = Vec::new();
+        root_fields(&document, selection_set, &mut fields);
+        let mut data = Map::new();
+        let mut errors = Vec::new();
+        for (key, name) in fields {
+            let value = match name {
+                "__typename" => json!("Query"),
+                "hello" => json!("world"),
+                "_service" if self.subgraph => json!({"sdl": SUBGRAPH_SDL}),
+                "__schema" | "__type" if !self.introspection => {
+                    errors.push(json!({
+                        "message": "Introspection is disabled",
+                        "path": [key],
+                        "extensions": {"code": "INTROSPECTION_DISABLED"}
+                    }));
+                    Value::Null
+                }
+                "__schema" => introspection(),
+                "__type" => Value::Null,
+                _ => {
+                    return error_response(
+                        400,
+                        &format!("Cannot query field \"{name}\" on type \"Query\"."),
+                        "GRAPHQL_VALIDATION_FAILED",
+                    );
+                }
+            };
+            data.insert(key.to_string(), value);
+        }
+        let mut body = json!({ "data": data });
+        if !errors.is_empty() {
+            body["errors"] = Value::Array(errors);
+        }
+        HttpResponse::json(200, &body)
+    }
+}
+
+/// A running fixture server.
+#[derive(Debug)]
+pub struct FixtureServer {
+    address: SocketAddr,
+    thread: JoinHandle<()>,
+}
+
+impl FixtureServer {
+    /// The address the server is listening on.
+    pub const fn address(&self) -> SocketAddr {
+        self.address
+    }
+
+    /// The URL of the GraphQL endpoint. Every path is the same endpoint.
+    pub fn url(&self) -> String {
+        format!("http://{}/graphql", self.address)
+    }
+
+    /// Blocks for as long as the server runs, which is until the process exits.
+    pub fn wait(self) {
+        let _ = self.thread.join();
+    }
+}
+
+fn operation_name_of<'a>(operation: &OperationDefinition<'a, &'a str>) -> Option<&'a str> {
+    match operation {
+        OperationDefinition::Query(query) => query.name,
+        OperationDefinition::Mutation(mutation) => mutation.name,
+        OperationDefinition::Subscription(subscription) => subscription.name,
+        OperationDefinition::SelectionSet(_) => None,
+    }
+}
+
+/// Every field selected on `Query`, as the key in the response and the name of the field, going
+/// into fragments.
+fn root_fields<'a>(
+    document: &Document<'a, &'a str>,
+    selection_set: &SelectionSet<'a, &'a str>,
+    fields: &mut Vec<(&'a str, &'a str)>,
+) {
+    for selection in &selection_set.items {
+        match selection {
+            Selection::Field(field) => {
+                fields.push((field.alias.unwrap_or(field.name), field.name));
+            }
+            Selection::InlineFragment(fragment) => {
+                root_fields(document, &fragment.selection_set, fields);
+            }
+            Selection::FragmentSpread(spread) => {
+                let fragment =
+                    document
+                        .definitions
+                        .iter()
+                        .find_map(|definition| match definition {
+                            Definition::Fragment(fragment)
+                                if fragment.name == spread.fragment_name =>
+                            {
+                                Some(fragment)
+                            }
+                            _ => None,
+                        });
+                if let Some(fragment) = fragment {
+                    root_fields(document, &fragment.selection_set, fields);
+                }
+            }
+        }
+    }
+}
+
+/// The parameters of a `GET` request, from its query string.
+fn get_params(url: &str) -> Option<Value> {
+    let url = Url::parse(url).ok()?;
+    let params: Map<String, Value> = url
+        .query_pairs()
+        .map(|(name, value)| (name.into_owned(), Value::String(value.into_owned())))
+        .collect();
+    Some(Value::Object(params))
+}
+
+fn error_response(status: u16, message: &str, code: &str) -> HttpResponse {
+    HttpResponse::json(
+        status,
+        &json!({"errors": [{"message": message, "extensions": {"code": code}}]}),
+    )
+}
+
+/// The introspection result of the schema, with every field of the usual introspection query
+/// whatever was actually asked for.
+fn introspection() -> Value {
+    let scalar = |name: &str| {
+        json!({
+            "kind": "SCALAR",
+            "name": name,
+            "description": null,
+            "fields": null,
+            "inputFields": null,
+            "interfaces": null,
+            "enumValues": null,
+            "possibleTypes": null
+        })
+    };
+    json!({
+        "queryType": {"name": "Query"},
+        "mutationType": null,
+        "subscriptionType": null,
+        "types": [
+            {
+                "kind": "OBJECT",
+                "name": "Query",
+                "description": null,
+                "fields": [{
+                    "name": "hello",
+                    "description": null,
+                    "args": [],
+                    "type": {"kind": "SCALAR", "name": "String", "ofType": null},
+                    "isDeprecated": false,
+                    "deprecationReason": null
+                }],
+                "inputFields": null,
+                "interfaces": [],
+                "enumValues": null,
+                "possibleTypes": null
+            },
+            scalar("String"),
+            scalar("Boolean")
+        ],
+        "directives": []
+    })
+}
+
+/// Reads the next request on a connection, and whether the client asked to close it afterwards.
+/// Returns `None` once the client hangs up.
+fn read_request(
+    reader: &mut impl BufRead,
+    host: SocketAddr,
+) -> io::Result<Option<(HttpRequest, bool)>> {
+    let mut line = String::new();
+    if reader.read_line(&mut line)? == 0 {
+        return Ok(None);
+    }
+    let mut parts = line.split_whitespace();
+    let (Some(method), Some(target)) = (parts.next(), parts.next()) else {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "bad request line",
+        ));
+    };
+    let http_1_0 = parts.next() == Some("HTTP/1.0");
+    let mut request = HttpRequest::new(method, &format!("http://{host}{target}"));
+    let mut headers = HashMap::new();
+    loop {
+        let mut line = String::new();
+        if reader.read_line(&mut line)? == 0 {
+            return Ok(None);
+        }
+        let line = line.trim_end();
+        if line.is_empty() {
+            break;
+        }
+        if let Some((name, value)) = line.split_once(':') {
+            headers.insert(name.to_ascii_lowercase(), value.trim().to_string());
+            request = request.set(name, value.trim());
+        }
+    }
+    let length: usize = headers
+        .get("content-length")
+        .and_then(|length| length.parse().ok())
+        .unwrap_or_default();
+    if length > MAX_BODY_LEN {
+        return Err(io::Error::new(io::ErrorKind::InvalidData, "body too long"));
+    }
+    let mut body = vec![0; length];
+    reader.read_exact(&mut body)?;
+    if !body.is_empty() {
+        let body = String::from_utf8_lossy(&body).into_owned();
+        match serde_json::from_str::<Value>(&body) {
+            Ok(value) if value.is_object() => request.body = Some(value),
+            _ => request.raw_body = Some(body),
+        }
+    }
+    let close = match headers.get("connection") {
+        Some(connection) => connection.eq_ignore_ascii_case("close"),
+        None => http_1_0,
+    };
+    Ok(Some((request, close)))
+}
+
+fn write_response(
+    writer: &mut impl Write,
+    response: &HttpResponse,
+    head: bool,
+    close: bool,
+) -> io::Result<()> {
+    let mut message = format!(
+        "HTTP/1.1 {} {}\r\n",
+        response.status,
+        reason(response.status)
+    );
+    for (name, value) in &response.headers {
+        message.push_str(&format!("{name}: {value}\r\n"));
+    }
+    message.push_str(&format!("Content-Length: {}\r\n", response.body.len()));
+    if close {
+        message.push_str("Connection: close\r\n");
+    }
+    message.push_str("\r\n");
+    if !head {
+        message.push_str(&response.body);
+    }
+    // In one write, so the client isn't left waiting for the rest while its ACK is delayed.
+    writer.write_all(message.as_bytes())?;
+    writer.flush()
+}
+
+const fn reason(status: u16) -> &'static str {
+    match status {
+        200 => "OK",
+        204 => "No Content",
+        400 => "Bad Request",
+        401 => "Unauthorized",
+        405 => "Method Not Allowed",
+        _ => "",
+    }
+}
+
+#[cfg(test)]
+mod test_fixture {
+    use super::*;
+    use crate::{Auth, Checker, Error, Outcome};
+
+    fn post(fixture: &Fixture, query: &str) -> HttpResponse {
+        let mut request = HttpRequest::new("POST", "http://localhost/graphql");
+        request.body = Some(json!({ "query": query }));
+        fixture.respond(&request)
+    }
+
+    #[test]
+    fn queries() {
+        let response = post(
+            &Fixture::default(),
+            "{ greeting: hello ...on Query { __typename } }",
+        );
+        assert_eq!(response.status, 200);
+        assert_eq!(
+            serde_json::from_str::<Value>(&response.body).unwrap(),
+            json!({"data": {"greeting": "world", "__typename": "Query"}})
+        );
+        assert_eq!(post(&Fixture::default(), "{ goodbye }").status, 400);
+        assert_eq!(
+            post(&Fixture::default(), "{ _service { sdl } }").status,
+            400
+        );
+        assert_eq!(post(&Fixture::default(), "{ hello").status, 400);
+    }
+
+    #[test]
+    fn introspection_disabled() {
+        let fixture = Fixture {
+            introspection: false,
+            ..Fixture::default()
+        };
+        let body: Value = serde_json::from_str(
+            &post(&fixture, "{ __typename __schema { types { name } } }").body,
+        )
+        .unwrap();
+        assert_eq!(
+            body["data"],
+            json!({"__typename": "Query", "__schema": null})
+        );
+        assert_eq!(body["errors"][0]["path"], json!(["__schema"]));
+    }
+
+    #[test]
+    fn served() {
+        let server = Fixture {
+            auth: Some("Authorization: Bearer token".to_string()),
+            subgraph: true,
+            ..Fixture::default()
+        }
+        .start()
+        .unwrap();
+        let report = Checker::new(&server.url())
+            .auth(Auth::Enabled {
+                header: "Authorization: Bearer token".into(),
+            })
+            .require_subgraph()
+            .allow_introspection()
+            .report();
+        assert!(report.passed(), "{report:?}");
+
+        let results = Checker::new(&server.url()).allow_introspection().run();
+        assert!(matches!(
+            results[0].outcome,
+            Outcome::Failed(Error::BadStatus { status: 401, .. })
+        ));
+    }
+}
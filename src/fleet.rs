@@ -0,0 +1,214 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use serde::Serialize;
+
+use crate::{serialize_millis, CheckReport, Checker};
+
+/// How many endpoints a [`Fleet`] checks at once unless told otherwise.
+pub const DEFAULT_MAX_CONCURRENCY: usize = 4;
+
+/// Runs the checks against many endpoints in parallel, a few at a time, so a shared gateway in front
+/// of them doesn't see hundreds of connections at once.
+///
+/// ```no_run
+/// use graphql_check_action::{Checker, Fleet};
+///
+/// let report = Fleet::new(vec![
+///     Checker::new("https://example.com/products"),
+///     Checker::new("https://example.com/reviews"),
+/// ])
+/// .max_concurrency(2)
+/// .report();
+/// if let Some(slowest) = report.slowest() {
+///     println!("{} took {:?}", slowest.endpoint, slowest.report.duration);
+/// }
+/// ```
+#[derive(Clone, Debug)]
+pub struct Fleet<'a> {
+    checkers: Vec<Checker<'a>>,
+    max_concurrency: usize,
+}
+
+impl<'a> Fleet<'a> {
+    pub fn new(checkers: Vec<Checker<'a>>) -> Self {
+        Self {
+            checkers,
+            max_concurrency: DEFAULT_MAX_CONCURRENCY,
+        }
+    }
+
+    /// The most endpoints to check at the same time, at least 1. Use `usize::MAX` to check every
+    /// endpoint at once, so the whole fleet takes about as long as its slowest endpoint.
+    #[must_use]
+    pub fn max_concurrency(mut self, max_concurrency: usize) -> Self {
+        self.max_concurrency = max_concurrency.max(1);
+        self
+    }
+
+    /// The checkers, in the order their reports will be in.
+    pub fn checkers(&self) -> &[Checker<'a>] {
+        &self.checkers
+    }
+
+    /// Runs every checker, returning their reports in the same order as the checkers.
+    pub fn report(&self) -> FleetReport {
+        let start = Instant::now();
+        let next = AtomicUsize::new(0);
+        let reports = Mutex::new(vec![CheckReport::default(); self.checkers.len()]);
+        let workers = self.max_concurrency.min(self.checkers.len());
+        thread::scope(|scope| {
+            for _ in 0..workers {
+                scope.spawn(|| loop {
+                    let index = next.fetch_add(1, Ordering::Relaxed);
+                    let Some(checker) = self.checkers.get(index) else {
+                        break;
+                    };
+                    let report = checker.report();
+                    reports.lock().expect("fleet lock poisoned")[index] = report;
+                });
+            }
+        });
+        let reports = reports.into_inner().expect("fleet lock poisoned");
+        FleetReport {
+            endpoints: self
+                .checkers
+                .iter()
+                .zip(reports)
+                .map(|(checker, report)| EndpointReport {
+                    endpoint: checker.url().to_string(),
+                    report,
+                })
+                .collect(),
+            duration: start.elapsed(),
+        }
+    }
+}
+
+/// The results of a [`Fleet`], one report for each endpoint.
+#[derive(Clone, Debug, Default, Eq, PartialEq, Serialize)]
+pub struct FleetReport {
+    pub endpoints: Vec<EndpointReport>,
+    /// How long checking every endpoint took, wall-clock.
+    #[serde(rename = "duration_ms", serialize_with = "serialize_millis")]
+    pub duration: Duration,
+}
+
+impl FleetReport {
+    /// Whether every endpoint passed.
+    pub fn passed(&self) -> bool {
+        self.endpoints
+            .iter()
+            .all(|endpoint| endpoint.report.passed())
+    }
+
+    /// The endpoint which took the longest to check, which is what bounds the whole fleet.
+    pub fn slowest(&self) -> Option<&EndpointReport> {
+        self.endpoints
+            .iter()
+            .max_by_key(|endpoint| endpoint.report.duration)
+    }
+}
+
+#[derive(Clone, Debug, Default, Eq, PartialEq, Serialize)]
+pub struct EndpointReport {
+    pub endpoint: String,
+    #[serde(flatten)]
+    pub report: CheckReport,
+}
+
+#[cfg(test)]
+mod test_fleet {
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    use serde_json::json;
+
+    use super::*;
+    use crate::testing::MockTransport;
+    use crate::{HttpResponse, Outcome};
+
+    #[test]
+    fn limits_concurrency() {
+        let in_flight = Arc::new(AtomicUsize::new(0));
+        let most_in_flight = Arc::new(AtomicUsize::new(0));
+        let checkers = (0..6)
+            .map(|_| {
+                let in_flight = in_flight.clone();
+                let most_in_flight = most_in_flight.clone();
+                Checker::new("https://example.com/graphql")
+                    .allow_introspection()
+                    .transport(MockTransport::new(move |_request| {
+                        let now = in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+                        most_in_flight.fetch_max(now, Ordering::SeqCst);
+                        thread::sleep(Duration::from_millis(10));
+                        in_flight.fetch_sub(1, Ordering::SeqCst);
+                        Ok(HttpResponse::json(
+                            200,
+                            &json!({"data": {"__typename": "Query"}}),
+                        ))
+                    }))
+            })
+            .collect();
+        let report = Fleet::new(checkers).max_concurrency(2).report();
+        assert_eq!(report.endpoints.len(), 6);
+        assert!(report
+            .endpoints
+            .iter()
+            .all(|endpoint| endpoint.report.checks[0].outcome == Outcome::Passed));
+        assert!(most_in_flight.load(Ordering::SeqCst) <= 2);
+    }
+
+    #[test]
+    fn keeps_order() {
+        let checkers = ["not a url", "https://example.com/graphql"]
+            .into_iter()
+            .map(|url| {
+                Checker::new(url)
+                    .allow_introspection()
+                    .transport(MockTransport::new(|_request| {
+                        Ok(HttpResponse::json(
+                            200,
+                            &json!({"data": {"__typename": "Query"}}),
+                        ))
+                    }))
+            })
+            .collect();
+        let report = Fleet::new(checkers).max_concurrency(0).report();
+        assert_eq!(report.endpoints[0].endpoint, "not a url");
+        assert!(matches!(
+            report.endpoints[0].report.checks[0].outcome,
+            Outcome::Failed(crate::Error::BadUri(_))
+        ));
+        assert_eq!(
+            report.endpoints[1].report.checks[0].outcome,
+            Outcome::Passed
+        );
+    }
+
+    #[test]
+    fn slowest() {
+        let report = FleetReport {
+            endpoints: vec![
+                EndpointReport {
+                    endpoint: "fast".to_string(),
+                    report: CheckReport {
+                        duration: Duration::from_millis(10),
+                        ..CheckReport::default()
+                    },
+                },
+                EndpointReport {
+                    endpoint: "slow".to_string(),
+                    report: CheckReport {
+                        duration: Duration::from_millis(900),
+                        ..CheckReport::default()
+                    },
+                },
+            ],
+            duration: Duration::from_millis(900),
+        };
+        assert_eq!(report.slowest().unwrap().endpoint, "slow");
+    }
+}
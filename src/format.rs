@@ -0,0 +1,431 @@
+use std::fmt::Write;
+
+use graphql_check_action::report::Failure;
+use graphql_check_action::{CheckResult, Error, Outcome, Severity};
+use itertools::Itertools;
+use serde_json::{json, Value};
+
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum Format {
+    /// A human-readable line for each check on stdout.
+    #[default]
+    Text,
+    /// [Test Anything Protocol](https://testanything.org) on stdout, one test per check.
+    Tap,
+    /// A JSON object shaped like the output of `rover --format json`, with `json_version`, `data`
+    /// and `error`.
+    Rover,
+}
+
+impl Format {
+    pub fn parse(value: &str) -> Result<Self, Error> {
+        match value {
+            "text" => Ok(Format::Text),
+            "tap" => Ok(Format::Tap),
+            "rover" => Ok(Format::Rover),
+            other => Err(Error::BadFormat(other.to_string())),
+        }
+    }
+}
+
+/// Prefix of every line matched by the bundled `problem-matcher.json`.
+const OWNER: &str = "graphql-check";
+
+/// Formats a failure as a single `graphql-check: level: code: check: message` line (without the
+/// check if it didn't come from one), which the bundled problem matcher highlights in the workflow
+/// log.
+pub fn problem_line(failure: &Failure) -> String {
+    let level = match failure.severity {
+        Severity::Error => "error",
+        Severity::Warning => "warning",
+    };
+    let message = failure.to_string().replace(['\r', '\n'], " ");
+    format!("{OWNER}: {level}: {}: {message}", failure.code)
+}
+
+/// Formats every check as a line like `PASS basic_query`, followed by errors which didn't come from
+/// a check.
+pub fn text(results: &[CheckResult], other_errors: &[Error]) -> String {
+    let mut text = String::new();
+    for result in results {
+        let name = result.check.name();
+        match &result.outcome {
+            Outcome::Passed => writeln!(text, "PASS {name}"),
+            Outcome::Skipped => writeln!(text, "SKIP {name}"),
+            Outcome::Failed(err) if result.severity == Severity::Warning => {
+                writeln!(text, "WARN {name}: {err}")
+            }
+            Outcome::Failed(err) => writeln!(text, "FAIL {name}: {err}"),
+        }
+        .expect("writing to a String can't fail");
+    }
+    for err in other_errors {
+        writeln!(text, "FAIL {}: {err}", err.code()).expect("writing to a String can't fail");
+    }
+    text
+}
+
+/// Formats every check as a TAP test. Errors which didn't come from a check (like bad inputs) are
+/// reported as extra failed tests named by their code.
+pub fn tap(results: &[CheckResult], other_errors: &[Error]) -> String {
+    let mut tap = format!(
+        "TAP version 13\n1..{}\n",
+        results.len() + other_errors.len()
+    );
+    let tests = results
+        .iter()
+        .map(|result| (result.check.name(), result.outcome.clone()))
+        .chain(
+            other_errors
+                .iter()
+                .map(|err| (err.code(), Outcome::Failed(err.clone()))),
+        );
+    for (number, (name, outcome)) in (1..).zip(tests) {
+        match outcome {
+            Outcome::Passed => writeln!(tap, "ok {number} - {name}"),
+            Outcome::Skipped => writeln!(tap, "ok {number} - {name} # SKIP"),
+            Outcome::Failed(err) => {
+                let message = err.to_string().replace(['\r', '\n'], " ");
+                writeln!(tap, "not ok {number} - {name}\n  # {message}")
+            }
+        }
+        .expect("writing to a String can't fail");
+    }
+    tap
+}
+
+/// Formats the run like `rover --format json`: `data.success` is whether everything passed, with
+/// every check in `data.checks`, and `error` describes the first failure (or is `null`). Warnings
+/// don't make the run unsuccessful, same as the exit code.
+pub fn rover(results: &[CheckResult], other_errors: &[Error]) -> String {
+    let checks: Vec<Value> = results
+        .iter()
+        .map(|result| {
+            let (outcome, error) = match &result.outcome {
+                Outcome::Passed => ("passed", Value::Null),
+                Outcome::Skipped => ("skipped", Value::Null),
+                Outcome::Failed(err) => ("failed", rover_error(err)),
+            };
+            json!({
+                "name": result.check.name(),
+                "outcome": outcome,
+                "severity": result.severity,
+                "error": error,
+            })
+        })
+        .collect();
+    let error = other_errors
+        .iter()
+        .filter(|err| err.severity() == Severity::Error)
+        .chain(
+            results
+                .iter()
+                .filter(|result| result.severity == Severity::Error)
+                .filter_map(CheckResult::error),
+        )
+        .next()
+        .map_or(Value::Null, rover_error);
+    let output = json!({
+        "json_version": "1",
+        "data": {
+            "success": error.is_null(),
+            "checks": checks,
+        },
+        "error": error,
+    });
+    format!("{output}\n")
+}
+
+fn rover_error(err: &Error) -> Value {
+    json!({"message": err.to_string(), "code": err.code()})
+}
+
+/// Whether anything which should fail the run went wrong.
+pub fn failed(results: &[CheckResult], other_errors: &[Error]) -> bool {
+    results
+        .iter()
+        .any(|result| result.error().is_some() && result.severity == Severity::Error)
+        || other_errors
+            .iter()
+            .any(|err| err.severity() == Severity::Error)
+}
+
+/// Formats a summary of the run as a Markdown table, for comments and summaries. Failures which
+/// only warn are marked as such, and don't make the run failed.
+pub fn markdown(endpoint: &str, results: &[CheckResult], other_errors: &[Error]) -> String {
+    let mut markdown = format!(
+        "## GraphQL Check {}\n\nEndpoint: `{endpoint}`\n\n| Check | Result |\n|---|---|\n",
+        if failed(results, other_errors) {
+            "failed ❌"
+        } else {
+            "passed ✅"
+        }
+    );
+    for result in results {
+        let outcome = match &result.outcome {
+            Outcome::Passed => "✅ Passed".to_string(),
+            Outcome::Skipped => "⏭️ Skipped".to_string(),
+            Outcome::Failed(err) => {
+                let icon = match result.severity {
+                    Severity::Error => "❌",
+                    Severity::Warning => "⚠️",
+                };
+                format!("{icon} {}", escape_cell(&err.to_string()))
+            }
+        };
+        writeln!(markdown, "| `{}` | {outcome} |", result.check.name())
+            .expect("writing to a String can't fail");
+    }
+    let failed_checks: Vec<_> = results
+        .iter()
+        .filter(|result| result.error().is_some())
+        .map(|result| result.check)
+        .unique()
+        .collect();
+    if !failed_checks.is_empty() {
+        markdown.push_str("\nHow to fix:\n\n");
+        for check in failed_checks {
+            let info = check.info();
+            writeln!(
+                markdown,
+                "- `{}`: {} ([docs]({}))",
+                check.name(),
+                info.remediation,
+                info.docs_url
+            )
+            .expect("writing to a String can't fail");
+        }
+    }
+    if !other_errors.is_empty() {
+        markdown.push_str("\nOther problems:\n\n");
+        for err in other_errors {
+            writeln!(markdown, "- {err}").expect("writing to a String can't fail");
+        }
+    }
+    markdown
+}
+
+fn escape_cell(value: &str) -> String {
+    value.replace('|', "\\|").replace(['\r', '\n'], " ")
+}
+
+#[cfg(test)]
+mod test_problem_line {
+    use graphql_check_action::graphql::GraphQLError;
+    use graphql_check_action::Check;
+
+    use super::*;
+
+    #[test]
+    fn error() {
+        assert_eq!(
+            problem_line(&Failure::new(
+                &Error::BadStatus {
+                    status: 500,
+                    excerpt: String::new()
+                },
+                Severity::Error
+            )),
+            "graphql-check: error: bad_status: Got status code: 500, the server failed to handle the request"
+        );
+    }
+
+    #[test]
+    fn from_a_check() {
+        let result = CheckResult {
+            severity: Severity::Warning,
+            ..CheckResult::new(
+                Check::IntrospectionDisabled,
+                Outcome::Failed(Error::IntrospectionEnabled),
+            )
+        };
+        assert_eq!(
+            problem_line(&Failure::of_check(&result).unwrap()),
+            format!(
+                "graphql-check: warning: introspection_enabled: introspection_disabled: {}",
+                Error::IntrospectionEnabled
+            )
+        );
+    }
+
+    #[test]
+    fn single_line() {
+        let line = problem_line(&Failure::new(
+            &Error::GraphQLError(vec![GraphQLError::new("first\nsecond")]),
+            Severity::Error,
+        ));
+        assert_eq!(line.lines().count(), 1);
+    }
+}
+
+#[cfg(test)]
+mod test_text {
+    use graphql_check_action::Check;
+
+    use super::*;
+
+    #[test]
+    fn each_outcome() {
+        let results = [
+            CheckResult::new(Check::BasicQuery, Outcome::Passed),
+            CheckResult::new(Check::Subgraph, Outcome::Skipped),
+            CheckResult {
+                severity: Severity::Warning,
+                ..CheckResult::new(
+                    Check::IntrospectionDisabled,
+                    Outcome::Failed(Error::IntrospectionEnabled),
+                )
+            },
+        ];
+        assert_eq!(
+            text(&results, &[Error::BadHeader]),
+            "PASS basic_query
+SKIP subgraph
+WARN introspection_disabled: Introspection is enabled for the GraphQL server but not allowed
+FAIL bad_header: Provided `auth` input was not a valid header in the format of `name: value`
+"
+        );
+    }
+}
+
+#[cfg(test)]
+mod test_tap {
+    use graphql_check_action::Check;
+
+    use super::*;
+
+    #[test]
+    fn each_outcome() {
+        let results = [
+            CheckResult::new(Check::BasicQuery, Outcome::Passed),
+            CheckResult::new(
+                Check::IntrospectionDisabled,
+                Outcome::Failed(Error::IntrospectionEnabled),
+            ),
+            CheckResult::new(Check::Subgraph, Outcome::Skipped),
+        ];
+        assert_eq!(
+            tap(&results, &[Error::BadBoolean("subgraph".to_string())]),
+            "TAP version 13
+1..4
+ok 1 - basic_query
+not ok 2 - introspection_disabled
+  # Introspection is enabled for the GraphQL server but not allowed
+ok 3 - subgraph # SKIP
+not ok 4 - bad_boolean
+  # Input `subgraph` can only be `true` or `false`
+"
+        );
+    }
+}
+
+#[cfg(test)]
+mod test_rover {
+    use graphql_check_action::Check;
+
+    use super::*;
+
+    #[test]
+    fn failed() {
+        let results = [
+            CheckResult::new(Check::BasicQuery, Outcome::Passed),
+            CheckResult {
+                severity: Severity::Warning,
+                ..CheckResult::new(Check::Subgraph, Outcome::Failed(Error::NotASubgraph))
+            },
+            CheckResult::new(
+                Check::IntrospectionDisabled,
+                Outcome::Failed(Error::IntrospectionEnabled),
+            ),
+        ];
+        let output: Value = serde_json::from_str(&rover(&results, &[])).unwrap();
+        assert_eq!(output["json_version"], "1");
+        assert_eq!(output["data"]["success"], false);
+        assert_eq!(
+            output["data"]["checks"][1],
+            json!({
+                "name": "subgraph",
+                "outcome": "failed",
+                "severity": "warning",
+                "error": {
+                    "message": Error::NotASubgraph.to_string(),
+                    "code": "not_a_subgraph"
+                },
+            })
+        );
+        assert_eq!(output["error"]["code"], "introspection_enabled");
+    }
+
+    #[test]
+    fn warnings_succeed() {
+        let results = [CheckResult {
+            severity: Severity::Warning,
+            ..CheckResult::new(Check::Subgraph, Outcome::Failed(Error::NotASubgraph))
+        }];
+        let other_errors = [Error::GitHubApi("rate limited".to_string())];
+        let output: Value = serde_json::from_str(&rover(&results, &other_errors)).unwrap();
+        assert_eq!(output["data"]["success"], true);
+        assert_eq!(output["error"], Value::Null);
+    }
+}
+
+#[cfg(test)]
+mod test_markdown {
+    use graphql_check_action::graphql::GraphQLError;
+    use graphql_check_action::Check;
+
+    use super::*;
+
+    #[test]
+    fn failed() {
+        let results = [
+            CheckResult::new(Check::BasicQuery, Outcome::Passed),
+            CheckResult::new(
+                Check::IntrospectionDisabled,
+                Outcome::Failed(Error::GraphQLError(vec![GraphQLError::new("a|b")])),
+            ),
+        ];
+        assert_eq!(
+            markdown("https://example.com", &results, &[Error::BadHeader]),
+            "## GraphQL Check failed ❌
+
+Endpoint: `https://example.com`
+
+| Check | Result |
+|---|---|
+| `basic_query` | ✅ Passed |
+| `introspection_disabled` | ❌ Received error from GraphQL server: a\\|b |
+
+How to fix:
+
+- `introspection_disabled`: Turn introspection off in production, e.g., with `introspection: false` in the `ApolloServer` options or the `useDisableIntrospection` plugin in Yoga. Set `allow_introspection: true` if it's meant to be public. ([docs](https://github.com/dbanty/graphql-check-action#introspection-disabled))
+
+Other problems:
+
+- Provided `auth` input was not a valid header in the format of `name: value`
+"
+        );
+    }
+
+    #[test]
+    fn warnings_pass() {
+        let results = [CheckResult {
+            severity: Severity::Warning,
+            ..CheckResult::new(
+                Check::IntrospectionDisabled,
+                Outcome::Failed(Error::IntrospectionEnabled),
+            )
+        }];
+        let other_errors = [Error::GitHubApi("rate limited".to_string())];
+        let markdown = markdown("https://example.com", &results, &other_errors);
+        assert!(!super::failed(&results, &other_errors));
+        assert!(
+            markdown.starts_with("## GraphQL Check passed ✅"),
+            "{markdown}"
+        );
+        assert!(markdown.contains(&format!(
+            "| `introspection_disabled` | ⚠️ {} |",
+            Error::IntrospectionEnabled
+        )));
+    }
+}
@@ -0,0 +1,348 @@
+use std::env;
+use std::fs::read_to_string;
+
+use graphql_check_action::{new_agent, CheckResult, Error, Severity};
+use serde::Serialize;
+use serde_json::{json, Value};
+use ureq::{Agent, Request};
+
+/// Hidden marker included in every comment this action posts, so re-runs update the same comment.
+pub const COMMENT_MARKER: &str = "<!-- graphql-check-action -->";
+
+const PER_PAGE: usize = 100;
+/// The most annotations the Checks API accepts in a single request.
+const ANNOTATIONS_PER_REQUEST: usize = 50;
+/// The longest summary the Checks API accepts.
+const MAX_SUMMARY_LEN: usize = 65535;
+const CHECK_RUN_NAME: &str = "GraphQL Check";
+
+/// A minimal client for the parts of the GitHub REST API this action uses.
+pub struct GitHub {
+    agent: Agent,
+    api_url: String,
+    repository: String,
+    token: String,
+}
+
+impl GitHub {
+    /// Configures a client from the environment variables set by GitHub Actions.
+    pub fn from_env() -> Result<Self, Error> {
+        let token = env::var("GITHUB_TOKEN")
+            .ok()
+            .filter(|token| !token.is_empty())
+            .ok_or_else(|| Error::GitHubApi("`GITHUB_TOKEN` is not set".to_string()))?;
+        let repository = env::var("GITHUB_REPOSITORY")
+            .map_err(|_| Error::GitHubApi("`GITHUB_REPOSITORY` is not set".to_string()))?;
+        let api_url =
+            env::var("GITHUB_API_URL").unwrap_or_else(|_| "https://api.github.com".to_string());
+        Ok(Self {
+            agent: new_agent(),
+            api_url,
+            repository,
+            token,
+        })
+    }
+
+    fn request(&self, method: &str, path: &str) -> Request {
+        self.agent
+            .request(
+                method,
+                &format!("{}/repos/{}/{path}", self.api_url, self.repository),
+            )
+            .set("Authorization", &format!("Bearer {}", self.token))
+            .set("Accept", "application/vnd.github+json")
+            .set("X-GitHub-Api-Version", "2022-11-28")
+            .set("User-Agent", "graphql-check-action")
+    }
+
+    /// Creates a comment on the pull request, or replaces the one from a previous run.
+    pub fn upsert_comment(&self, pull_request: u64, body: &str) -> Result<(), Error> {
+        let body = format!("{COMMENT_MARKER}\n{body}");
+        match self.find_comment(pull_request)? {
+            Some(id) => self
+                .request("PATCH", &format!("issues/comments/{id}"))
+                .send_json(json!({ "body": body })),
+            None => self
+                .request("POST", &format!("issues/{pull_request}/comments"))
+                .send_json(json!({ "body": body })),
+        }
+        .map_err(api_error)?;
+        Ok(())
+    }
+
+    fn find_comment(&self, pull_request: u64) -> Result<Option<u64>, Error> {
+        for page in 1.. {
+            let comments: Vec<Value> = self
+                .request("GET", &format!("issues/{pull_request}/comments"))
+                .query("per_page", &PER_PAGE.to_string())
+                .query("page", &page.to_string())
+                .call()
+                .map_err(api_error)?
+                .into_json()
+                .map_err(|err| Error::GitHubApi(err.to_string()))?;
+            if let Some(id) = marked_comment(&comments) {
+                return Ok(Some(id));
+            }
+            if comments.len() < PER_PAGE {
+                break;
+            }
+        }
+        Ok(None)
+    }
+
+    /// Creates a completed check run for the commit, sending annotations in batches so no request
+    /// goes over the API's limits.
+    pub fn create_check_run(
+        &self,
+        head_sha: &str,
+        failed: bool,
+        summary: &str,
+        annotations: &[Annotation],
+    ) -> Result<(), Error> {
+        let title = if failed {
+            "GraphQL checks failed"
+        } else {
+            "GraphQL checks passed"
+        };
+        let summary = truncate(summary, MAX_SUMMARY_LEN);
+        let mut batches = annotations.chunks(ANNOTATIONS_PER_REQUEST);
+        let check_run: Value = self
+            .request("POST", "check-runs")
+            .send_json(json!({
+                "name": CHECK_RUN_NAME,
+                "head_sha": head_sha,
+                "status": "completed",
+                "conclusion": if failed { "failure" } else { "success" },
+                "output": {
+                    "title": title,
+                    "summary": summary,
+                    "annotations": batches.next().unwrap_or_default(),
+                },
+            }))
+            .map_err(api_error)?
+            .into_json()
+            .map_err(|err| Error::GitHubApi(err.to_string()))?;
+        let id = check_run["id"]
+            .as_u64()
+            .ok_or_else(|| Error::GitHubApi("check run response had no `id`".to_string()))?;
+        for batch in batches {
+            self.request("PATCH", &format!("check-runs/{id}"))
+                .send_json(json!({
+                    "output": {
+                        "title": title,
+                        "summary": summary,
+                        "annotations": batch,
+                    },
+                }))
+                .map_err(api_error)?;
+        }
+        Ok(())
+    }
+}
+
+/// A problem attached to a check run. The Checks API requires every annotation to point at a file,
+/// so these all point at the start of the workflow file that ran the action.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize)]
+pub struct Annotation {
+    path: String,
+    start_line: u32,
+    end_line: u32,
+    annotation_level: &'static str,
+    title: String,
+    message: String,
+    /// How to fix a failed check, and where it's explained.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    raw_details: Option<String>,
+}
+
+pub fn annotations(results: &[CheckResult], other_errors: &[Error], path: &str) -> Vec<Annotation> {
+    results
+        .iter()
+        .filter_map(|result| {
+            let info = Some(result.check.info());
+            Some((result.check.name(), result.error()?, result.severity, info))
+        })
+        .chain(
+            other_errors
+                .iter()
+                .map(|err| (err.code(), err, err.severity(), None)),
+        )
+        .map(|(title, err, severity, info)| Annotation {
+            path: path.to_string(),
+            start_line: 1,
+            end_line: 1,
+            annotation_level: match severity {
+                Severity::Error => "failure",
+                Severity::Warning => "warning",
+            },
+            title: title.to_string(),
+            message: err.to_string(),
+            raw_details: info.map(|info| format!("{}\n\nSee {}", info.remediation, info.docs_url)),
+        })
+        .collect()
+}
+
+fn truncate(value: &str, max_len: usize) -> &str {
+    let end = (0..=max_len.min(value.len()))
+        .rev()
+        .find(|index| value.is_char_boundary(*index))
+        .unwrap_or_default();
+    &value[..end]
+}
+
+fn api_error(err: ureq::Error) -> Error {
+    match err {
+        ureq::Error::Status(status, _) => Error::GitHubApi(format!("got status code {status}")),
+        ureq::Error::Transport(transport) => Error::GitHubApi(transport.to_string()),
+    }
+}
+
+fn marked_comment(comments: &[Value]) -> Option<u64> {
+    comments
+        .iter()
+        .find(|comment| {
+            comment["body"]
+                .as_str()
+                .is_some_and(|body| body.contains(COMMENT_MARKER))
+        })
+        .and_then(|comment| comment["id"].as_u64())
+}
+
+/// The number of the pull request which triggered this workflow run, if any.
+pub fn pull_request_number() -> Option<u64> {
+    let event = read_to_string(env::var("GITHUB_EVENT_PATH").ok()?).ok()?;
+    pull_request_from_event(&serde_json::from_str(&event).ok()?)
+}
+
+fn pull_request_from_event(event: &Value) -> Option<u64> {
+    event
+        .pointer("/pull_request/number")
+        .or_else(|| {
+            event
+                .pointer("/issue/pull_request")
+                .and(event.pointer("/issue/number"))
+        })
+        .and_then(Value::as_u64)
+}
+
+/// The commit to attach a check run to, which is the head of the pull request if there is one.
+pub fn head_sha() -> Option<String> {
+    let from_event = env::var("GITHUB_EVENT_PATH")
+        .ok()
+        .and_then(|path| read_to_string(path).ok())
+        .and_then(|event| serde_json::from_str::<Value>(&event).ok())
+        .and_then(|event| {
+            event
+                .pointer("/pull_request/head/sha")
+                .and_then(Value::as_str)
+                .map(String::from)
+        });
+    from_event.or_else(|| env::var("GITHUB_SHA").ok())
+}
+
+/// The path of the workflow file within the repository, e.g. `.github/workflows/deploy.yml`.
+pub fn workflow_path() -> Option<String> {
+    workflow_path_from_ref(&env::var("GITHUB_WORKFLOW_REF").ok()?)
+}
+
+fn workflow_path_from_ref(workflow_ref: &str) -> Option<String> {
+    let (path, _git_ref) = workflow_ref.split_once('@')?;
+    path.splitn(3, '/').nth(2).map(String::from)
+}
+
+#[cfg(test)]
+mod test_marked_comment {
+    use super::*;
+
+    #[test]
+    fn found() {
+        let comments = [
+            json!({"id": 1, "body": "LGTM"}),
+            json!({"id": 2, "body": format!("{COMMENT_MARKER}\nold report")}),
+        ];
+        assert_eq!(marked_comment(&comments), Some(2));
+    }
+
+    #[test]
+    fn not_found() {
+        assert_eq!(marked_comment(&[json!({"id": 1, "body": "LGTM"})]), None);
+    }
+}
+
+#[cfg(test)]
+mod test_pull_request_from_event {
+    use super::*;
+
+    #[test]
+    fn pull_request() {
+        let event = json!({"number": 5, "pull_request": {"number": 5}});
+        assert_eq!(pull_request_from_event(&event), Some(5));
+    }
+
+    #[test]
+    fn pull_request_comment() {
+        let event = json!({"issue": {"number": 7, "pull_request": {}}});
+        assert_eq!(pull_request_from_event(&event), Some(7));
+    }
+
+    #[test]
+    fn push() {
+        let event = json!({"issue": {"number": 7}, "ref": "refs/heads/main"});
+        assert_eq!(pull_request_from_event(&event), None);
+    }
+}
+
+#[cfg(test)]
+mod test_annotations {
+    use graphql_check_action::{Check, Outcome};
+
+    use super::*;
+
+    #[test]
+    fn only_failures() {
+        let results = [
+            CheckResult::new(Check::BasicQuery, Outcome::Passed),
+            CheckResult::new(
+                Check::IntrospectionDisabled,
+                Outcome::Failed(Error::IntrospectionEnabled),
+            ),
+        ];
+        let annotations = annotations(
+            &results,
+            &[Error::GitHubApi("nope".to_string())],
+            "deploy.yml",
+        );
+        assert_eq!(annotations.len(), 2);
+        assert_eq!(annotations[0].title, "introspection_disabled");
+        assert_eq!(annotations[0].annotation_level, "failure");
+        assert_eq!(annotations[1].title, "github_api");
+        assert_eq!(annotations[1].annotation_level, "warning");
+        let details = annotations[0].raw_details.as_deref().unwrap();
+        assert!(details.ends_with("#introspection-disabled"));
+        assert_eq!(annotations[1].raw_details, None);
+    }
+}
+
+#[cfg(test)]
+mod test_truncate {
+    use super::*;
+
+    #[test]
+    fn char_boundary() {
+        assert_eq!(truncate("ab✅", 3), "ab");
+        assert_eq!(truncate("ab✅", 5), "ab✅");
+    }
+}
+
+#[cfg(test)]
+mod test_workflow_path_from_ref {
+    use super::*;
+
+    #[test]
+    fn happy() {
+        assert_eq!(
+            workflow_path_from_ref("octo/repo/.github/workflows/deploy.yml@refs/heads/main"),
+            Some(".github/workflows/deploy.yml".to_string())
+        );
+    }
+}
@@ -0,0 +1,368 @@
+use std::fmt::{self, Display, Formatter};
+use std::hash::{Hash, Hasher};
+
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::Error;
+
+/// One entry of the `errors` array in a GraphQL response, as described by the
+/// [spec](https://spec.graphql.org/October2021/#sec-Errors).
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct GraphQLError {
+    pub message: String,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub locations: Vec<Location>,
+    /// Field names and list indices leading to the field which failed.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub path: Vec<Value>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub extensions: Option<Value>,
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, Eq, Hash, PartialEq, Serialize)]
+pub struct Location {
+    pub line: u32,
+    pub column: u32,
+}
+
+impl GraphQLError {
+    pub fn new(message: impl Into<String>) -> Self {
+        Self {
+            message: message.into(),
+            locations: Vec::new(),
+            path: Vec::new(),
+            extensions: None,
+        }
+    }
+
+    /// Parses the `errors` of a response. Anything which doesn't follow the spec is kept as the
+    /// message of a single error, so nothing the server said is lost.
+    pub fn parse_all(errors: &Value) -> Vec<Self> {
+        serde_json::from_value(errors.clone())
+            .ok()
+            .filter(|errors: &Vec<Self>| !errors.is_empty())
+            .unwrap_or_else(|| vec![Self::new(errors.to_string())])
+    }
+
+    /// The `extensions.code` most servers use to say what kind of error this is, e.g.,
+    /// `UNAUTHENTICATED` or `PERSISTED_QUERY_NOT_FOUND`.
+    pub fn code(&self) -> Option<&str> {
+        self.extensions.as_ref()?.get("code")?.as_str()
+    }
+
+    /// The [`code`](GraphQLError::code), for telling the kinds of errors the checks care about
+    /// apart without comparing strings.
+    pub fn kind(&self) -> Option<ErrorCode> {
+        self.code().map(ErrorCode::parse)
+    }
+}
+
+/// The codes [Apollo Server](https://www.apollographql.com/docs/apollo-server/data/errors#built-in-error-codes)
+/// puts in `extensions.code`, which most other servers use too.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub enum ErrorCode {
+    Unauthenticated,
+    Forbidden,
+    PersistedQueryNotFound,
+    PersistedQueryNotSupported,
+    GraphQLParseFailed,
+    GraphQLValidationFailed,
+    BadUserInput,
+    InternalServerError,
+    /// Any other code, as the server sent it.
+    Other(String),
+}
+
+impl ErrorCode {
+    const KNOWN: [ErrorCode; 8] = [
+        ErrorCode::Unauthenticated,
+        ErrorCode::Forbidden,
+        ErrorCode::PersistedQueryNotFound,
+        ErrorCode::PersistedQueryNotSupported,
+        ErrorCode::GraphQLParseFailed,
+        ErrorCode::GraphQLValidationFailed,
+        ErrorCode::BadUserInput,
+        ErrorCode::InternalServerError,
+    ];
+
+    pub fn parse(code: &str) -> Self {
+        Self::KNOWN
+            .into_iter()
+            .find(|known| known.as_str() == code)
+            .unwrap_or_else(|| ErrorCode::Other(code.to_string()))
+    }
+
+    pub fn as_str(&self) -> &str {
+        match self {
+            ErrorCode::Unauthenticated => "UNAUTHENTICATED",
+            ErrorCode::Forbidden => "FORBIDDEN",
+            ErrorCode::PersistedQueryNotFound => "PERSISTED_QUERY_NOT_FOUND",
+            ErrorCode::PersistedQueryNotSupported => "PERSISTED_QUERY_NOT_SUPPORTED",
+            ErrorCode::GraphQLParseFailed => "GRAPHQL_PARSE_FAILED",
+            ErrorCode::GraphQLValidationFailed => "GRAPHQL_VALIDATION_FAILED",
+            ErrorCode::BadUserInput => "BAD_USER_INPUT",
+            ErrorCode::InternalServerError => "INTERNAL_SERVER_ERROR",
+            ErrorCode::Other(code) => code,
+        }
+    }
+}
+
+impl Display for ErrorCode {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// What the checks do with a response which has `errors` next to `data`, see
+/// [`Checker::errors_policy`](crate::Checker::errors_policy).
+#[derive(Clone, Copy, Debug, Default, Deserialize, Eq, Hash, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorsPolicy {
+    /// Any `errors` fail the check, even with `data`.
+    #[default]
+    Fail,
+    /// `errors` are ignored as long as there's some `data`, e.g., for a schema with fields known
+    /// to be flaky.
+    IgnoreIfData,
+    /// `errors` are ignored as long as there's some `data` and every one of them is an
+    /// [`AllowedError`].
+    Allowlist,
+}
+
+impl ErrorsPolicy {
+    pub const ALL: [ErrorsPolicy; 3] = [
+        ErrorsPolicy::Fail,
+        ErrorsPolicy::IgnoreIfData,
+        ErrorsPolicy::Allowlist,
+    ];
+
+    pub const fn name(self) -> &'static str {
+        match self {
+            ErrorsPolicy::Fail => "fail",
+            ErrorsPolicy::IgnoreIfData => "ignore_if_data",
+            ErrorsPolicy::Allowlist => "allowlist",
+        }
+    }
+
+    pub fn parse(value: &str) -> Result<Self, Error> {
+        Self::ALL
+            .into_iter()
+            .find(|policy| policy.name() == value)
+            .ok_or_else(|| {
+                let names = Self::ALL.map(|policy| format!("`{}`", policy.name()));
+                Error::BadInput(format!(
+                    "unknown errors policy `{value}`, expected one of {}",
+                    names.join(", ")
+                ))
+            })
+    }
+
+    /// Whether a response with `data` (`null` if it had none) and `errors` can be used anyway,
+    /// with `allowed` being what [`ErrorsPolicy::Allowlist`] lets through.
+    pub fn tolerates(
+        self,
+        data: &Value,
+        errors: &[GraphQLError],
+        allowed: &[AllowedError],
+    ) -> bool {
+        let has_data = data.as_object().is_some_and(|data| !data.is_empty());
+        match self {
+            ErrorsPolicy::Fail => false,
+            ErrorsPolicy::IgnoreIfData => has_data,
+            ErrorsPolicy::Allowlist => {
+                has_data
+                    && errors
+                        .iter()
+                        .all(|error| allowed.iter().any(|allowed| allowed.allows(error)))
+            }
+        }
+    }
+}
+
+/// An error which [`ErrorsPolicy::Allowlist`] lets through: one with an `extensions.code`, like
+/// `DEPRECATED_FIELD`, or a message matching a regex written between slashes, like
+/// `/is deprecated/`.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(try_from = "String", into = "String")]
+pub enum AllowedError {
+    Code(String),
+    Message(Regex),
+}
+
+impl AllowedError {
+    pub fn parse(value: &str) -> Result<Self, Error> {
+        let Some(pattern) = value
+            .strip_prefix('/')
+            .and_then(|value| value.strip_suffix('/'))
+        else {
+            return Ok(AllowedError::Code(value.to_string()));
+        };
+        Regex::new(pattern)
+            .map(AllowedError::Message)
+            .map_err(|err| Error::BadInput(format!("`{value}` is not a valid regex: {err}")))
+    }
+
+    pub fn allows(&self, error: &GraphQLError) -> bool {
+        match self {
+            AllowedError::Code(code) => error.code() == Some(code.as_str()),
+            AllowedError::Message(regex) => regex.is_match(&error.message),
+        }
+    }
+}
+
+impl Display for AllowedError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            AllowedError::Code(code) => f.write_str(code),
+            AllowedError::Message(regex) => write!(f, "/{regex}/"),
+        }
+    }
+}
+
+impl PartialEq for AllowedError {
+    fn eq(&self, other: &Self) -> bool {
+        self.to_string() == other.to_string()
+    }
+}
+
+impl Eq for AllowedError {}
+
+impl TryFrom<String> for AllowedError {
+    type Error = Error;
+
+    fn try_from(value: String) -> Result<Self, Error> {
+        Self::parse(&value)
+    }
+}
+
+impl From<AllowedError> for String {
+    fn from(allowed: AllowedError) -> Self {
+        allowed.to_string()
+    }
+}
+
+impl Hash for GraphQLError {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.message.hash(state);
+        self.locations.hash(state);
+        for segment in &self.path {
+            segment.to_string().hash(state);
+        }
+        self.extensions.as_ref().map(Value::to_string).hash(state);
+    }
+}
+
+impl Display for GraphQLError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self.code() {
+            Some(code) => write!(f, "{} ({code})", self.message),
+            None => write!(f, "{}", self.message),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test_parse_all {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn spec() {
+        let errors = GraphQLError::parse_all(&json!([{
+            "message": "Not logged in",
+            "locations": [{"line": 1, "column": 2}],
+            "path": ["me", 0],
+            "extensions": {"code": "UNAUTHENTICATED"},
+        }]));
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].message, "Not logged in");
+        assert_eq!(errors[0].locations, [Location { line: 1, column: 2 }]);
+        assert_eq!(errors[0].path, [json!("me"), json!(0)]);
+        assert_eq!(errors[0].code(), Some("UNAUTHENTICATED"));
+        assert_eq!(errors[0].kind(), Some(ErrorCode::Unauthenticated));
+        assert_eq!(errors[0].to_string(), "Not logged in (UNAUTHENTICATED)");
+    }
+
+    #[test]
+    fn other_code() {
+        let errors = GraphQLError::parse_all(&json!([{
+            "message": "Too complex",
+            "extensions": {"code": "COST_LIMIT_EXCEEDED"},
+        }]));
+        assert_eq!(
+            errors[0].kind(),
+            Some(ErrorCode::Other("COST_LIMIT_EXCEEDED".to_string()))
+        );
+        assert_eq!(GraphQLError::new("No code").kind(), None);
+    }
+
+    #[test]
+    fn not_spec() {
+        let errors = GraphQLError::parse_all(&json!("something broke"));
+        assert_eq!(errors, [GraphQLError::new(r#""something broke""#)]);
+    }
+}
+
+#[cfg(test)]
+mod test_errors_policy {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn tolerates() {
+        let errors = GraphQLError::parse_all(&json!([{
+            "message": "Reviews are unavailable",
+            "extensions": {"code": "DOWNSTREAM_SERVICE_ERROR"},
+        }]));
+        let data = json!({"product": {"name": "Table", "reviews": null}});
+        let allowed = [AllowedError::Code("DOWNSTREAM_SERVICE_ERROR".to_string())];
+        assert!(!ErrorsPolicy::Fail.tolerates(&data, &errors, &allowed));
+        assert!(ErrorsPolicy::IgnoreIfData.tolerates(&data, &errors, &[]));
+        assert!(!ErrorsPolicy::IgnoreIfData.tolerates(&Value::Null, &errors, &[]));
+        assert!(ErrorsPolicy::Allowlist.tolerates(&data, &errors, &allowed));
+        assert!(!ErrorsPolicy::Allowlist.tolerates(&data, &errors, &[]));
+        let unknown = [GraphQLError::new("No code")];
+        assert!(!ErrorsPolicy::Allowlist.tolerates(&data, &unknown, &allowed));
+    }
+
+    #[test]
+    fn parse() {
+        assert_eq!(
+            ErrorsPolicy::parse("ignore_if_data"),
+            Ok(ErrorsPolicy::IgnoreIfData)
+        );
+        assert!(ErrorsPolicy::parse("ignore").is_err());
+    }
+}
+
+#[cfg(test)]
+mod test_allowed_error {
+    use super::*;
+
+    #[test]
+    fn code_or_message() {
+        let error: GraphQLError = serde_json::from_value(serde_json::json!({
+            "message": "`legacyId` is deprecated",
+            "extensions": {"code": "DEPRECATED_FIELD"},
+        }))
+        .unwrap();
+        let code = AllowedError::parse("DEPRECATED_FIELD").unwrap();
+        let message = AllowedError::parse("/`\\w+` is deprecated/").unwrap();
+        assert!(code.allows(&error));
+        assert!(message.allows(&error));
+        assert!(!AllowedError::parse("/removed/").unwrap().allows(&error));
+        assert_eq!(message.to_string(), "/`\\w+` is deprecated/");
+    }
+
+    #[test]
+    fn bad_regex() {
+        assert!(matches!(
+            AllowedError::parse("/(/"),
+            Err(Error::BadInput(_))
+        ));
+    }
+}
@@ -0,0 +1,88 @@
+use graphql_check_action::schema::{diff, Schema};
+use graphql_check_action::Error;
+use ureq::Agent;
+
+/// A minimal client for the [GraphQL Hive](https://the-guild.dev/graphql/hive) schema registry,
+/// reading from its CDN so it works with a read-only access key.
+pub struct Hive {
+    agent: Agent,
+    /// The CDN endpoint of a target, e.g., `https://cdn.graphql-hive.com/artifacts/v1/<target id>`.
+    endpoint: String,
+    token: String,
+}
+
+impl Hive {
+    pub fn new(agent: Agent, endpoint: &str, token: &str) -> Self {
+        Self {
+            agent,
+            endpoint: endpoint.trim_end_matches('/').to_string(),
+            token: token.to_string(),
+        }
+    }
+
+    /// The SDL of the latest valid schema version registered for the target. For a federated
+    /// target, that's the composed schema clients of the gateway see.
+    pub fn latest_sdl(&self) -> Result<String, Error> {
+        self.agent
+            .get(&format!("{}/sdl", self.endpoint))
+            .set("X-Hive-CDN-Key", &self.token)
+            .set("User-Agent", "graphql-check-action")
+            .call()
+            .map_err(|err| match err {
+                ureq::Error::Status(status, _) => {
+                    Error::Registry(format!("got status code {status}"))
+                }
+                ureq::Error::Transport(transport) => Error::Registry(transport.to_string()),
+            })?
+            .into_string()
+            .map_err(|err| Error::Registry(err.to_string()))
+    }
+}
+
+/// Fails with every change if the live schema isn't the one registered in Hive.
+pub fn compare_with_registry(registered_sdl: String, live: &Schema) -> Result<(), Error> {
+    let diff = diff(&Schema::Sdl(registered_sdl), live)?;
+    if diff.is_empty() {
+        return Ok(());
+    }
+    Err(Error::SchemaDrift {
+        expected: "the latest version registered in Hive".to_string(),
+        changes: diff.to_string().lines().map(String::from).collect(),
+    })
+}
+
+#[cfg(test)]
+mod test_compare_with_registry {
+    use super::*;
+
+    #[test]
+    fn registered() {
+        assert_eq!(
+            compare_with_registry(
+                "type Query { hello: String }".to_string(),
+                &Schema::Sdl("type Query {\n  hello: String\n}\n".to_string())
+            ),
+            Ok(())
+        );
+    }
+
+    #[test]
+    fn unregistered_drift() {
+        let err = compare_with_registry(
+            "type Query { hello: String }".to_string(),
+            &Schema::Sdl("type Query { hello: String! goodbye: String }".to_string()),
+        )
+        .unwrap_err();
+        assert_eq!(
+            err,
+            Error::SchemaDrift {
+                expected: "the latest version registered in Hive".to_string(),
+                changes: vec![
+                    "- Query.hello: String".to_string(),
+                    "+ Query.goodbye: String".to_string(),
+                    "+ Query.hello: String!".to_string(),
+                ],
+            }
+        );
+    }
+}
@@ -0,0 +1,299 @@
+use std::sync::Mutex;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize, Serialize};
+
+use crate::http::{Context, Exchange};
+use crate::{basic_query, deserialize_millis, serialize_millis, Auth, Error, Outcome};
+
+/// How long the basic query took over several samples, see
+/// [`Checker::latency_samples`](crate::Checker::latency_samples).
+#[derive(Clone, Copy, Debug, Default, Deserialize, Eq, Hash, PartialEq, Serialize)]
+pub struct LatencyStats {
+    pub samples: u32,
+    #[serde(
+        rename = "p50_ms",
+        serialize_with = "serialize_millis",
+        deserialize_with = "deserialize_millis"
+    )]
+    pub p50: Duration,
+    #[serde(
+        rename = "p95_ms",
+        serialize_with = "serialize_millis",
+        deserialize_with = "deserialize_millis"
+    )]
+    pub p95: Duration,
+    #[serde(
+        rename = "max_ms",
+        serialize_with = "serialize_millis",
+        deserialize_with = "deserialize_millis"
+    )]
+    pub max: Duration,
+}
+
+impl LatencyStats {
+    /// Summarizes `durations`, `None` if there aren't any.
+    pub fn new(mut durations: Vec<Duration>) -> Option<Self> {
+        durations.sort_unstable();
+        let max = *durations.last()?;
+        Some(Self {
+            samples: u32::try_from(durations.len()).unwrap_or(u32::MAX),
+            p50: percentile(&durations, 50),
+            p95: percentile(&durations, 95),
+            max,
+        })
+    }
+}
+
+/// How long the first request of a run took, which had to open a new connection (and, for
+/// serverless deployments, often wake an instance up), next to the ones after it.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq, Serialize)]
+pub struct ColdStart {
+    #[serde(rename = "first_ms", serialize_with = "serialize_millis")]
+    pub first: Duration,
+    /// Every later request, `None` if there weren't any.
+    pub warm: Option<LatencyStats>,
+}
+
+impl ColdStart {
+    /// Measures the cold start from every request of a run, in order, `None` if none got a
+    /// response. See [`Capture::exchanges`](crate::Capture::exchanges).
+    ///
+    /// The first request to get a response is the cold one. Only later requests which are the same
+    /// as it count as warm, so a big query doesn't skew them.
+    pub fn new(exchanges: &[Exchange]) -> Option<Self> {
+        let millis = |exchange: &Exchange| {
+            Duration::from_millis(u64::try_from(exchange.duration_ms).unwrap_or(u64::MAX))
+        };
+        let mut answered = exchanges
+            .iter()
+            .filter(|exchange| exchange.status.is_some());
+        let first = answered.next()?;
+        let warm = answered
+            .filter(|exchange| {
+                exchange.method == first.method
+                    && exchange.url == first.url
+                    && exchange.request_body == first.request_body
+            })
+            .map(millis)
+            .collect();
+        Some(Self {
+            first: millis(first),
+            warm: LatencyStats::new(warm),
+        })
+    }
+}
+
+/// The nearest-rank percentile of `sorted`, which mustn't be empty.
+fn percentile(sorted: &[Duration], percent: usize) -> Duration {
+    let rank = (sorted.len() * percent).div_ceil(100).max(1);
+    sorted[rank - 1]
+}
+
+/// Sends the basic query `samples` times, one after another, failing with the first error or if
+/// the p95 is over `max_p95`.
+pub(crate) fn check_latency(
+    context: &Context,
+    url: &str,
+    auth: &Auth,
+    samples: u32,
+    max_p95: Option<Duration>,
+) -> (Outcome, Option<LatencyStats>) {
+    let mut durations = Vec::new();
+    for _ in 0..samples {
+        let start = Instant::now();
+        if let Err(err) = basic_query(context, url, auth) {
+            return (Outcome::Failed(err), None);
+        }
+        durations.push(start.elapsed());
+    }
+    let stats = LatencyStats::new(durations);
+    let outcome = match (stats, max_p95) {
+        (Some(stats), Some(limit)) if stats.p95 > limit => Outcome::Failed(Error::SlowP95 {
+            p95: stats.p95,
+            limit,
+        }),
+        _ => Outcome::Passed,
+    };
+    (outcome, stats)
+}
+
+/// Sends the basic query `concurrency` times at once, failing if any of them fails or if they
+/// don't all finish within `budget`.
+pub(crate) fn check_concurrency(
+    context: &Context,
+    url: &str,
+    auth: &Auth,
+    concurrency: u32,
+    budget: Option<Duration>,
+) -> (Outcome, Option<LatencyStats>) {
+    let durations = Mutex::new(Vec::new());
+    let errors = Mutex::new(Vec::new());
+    let start = Instant::now();
+    thread::scope(|scope| {
+        for _ in 0..concurrency {
+            scope.spawn(|| {
+                let start = Instant::now();
+                match basic_query(context, url, auth) {
+                    Ok(()) => durations
+                        .lock()
+                        .expect("concurrency lock poisoned")
+                        .push(start.elapsed()),
+                    Err(err) => errors.lock().expect("concurrency lock poisoned").push(err),
+                }
+            });
+        }
+    });
+    let took = start.elapsed();
+    let stats = LatencyStats::new(durations.into_inner().expect("concurrency lock poisoned"));
+    let errors = errors.into_inner().expect("concurrency lock poisoned");
+    let outcome = if let Some(first) = errors.first() {
+        if *first == Error::Cancelled {
+            Outcome::Failed(Error::Cancelled)
+        } else {
+            Outcome::Failed(Error::ConcurrentFailures {
+                failed: u32::try_from(errors.len()).unwrap_or(u32::MAX),
+                concurrency,
+                first: first.to_string(),
+            })
+        }
+    } else {
+        match budget {
+            Some(budget) if took > budget => {
+                Outcome::Failed(Error::ConcurrencyTooSlow { took, budget })
+            }
+            _ => Outcome::Passed,
+        }
+    };
+    (outcome, stats)
+}
+
+#[cfg(test)]
+mod test_latency_stats {
+    use super::*;
+
+    #[test]
+    fn percentiles() {
+        let durations = (1..=100).rev().map(Duration::from_millis).collect();
+        let stats = LatencyStats::new(durations).unwrap();
+        assert_eq!(stats.samples, 100);
+        assert_eq!(stats.p50, Duration::from_millis(50));
+        assert_eq!(stats.p95, Duration::from_millis(95));
+        assert_eq!(stats.max, Duration::from_millis(100));
+    }
+
+    #[test]
+    fn few_samples() {
+        let stats = LatencyStats::new(vec![Duration::from_millis(7)]).unwrap();
+        assert_eq!(stats.p50, Duration::from_millis(7));
+        assert_eq!(stats.p95, Duration::from_millis(7));
+        assert_eq!(LatencyStats::new(Vec::new()), None);
+    }
+}
+
+#[cfg(test)]
+mod test_cold_start {
+    use serde_json::json;
+
+    use super::*;
+
+    fn exchange(duration_ms: u128) -> Exchange {
+        Exchange {
+            method: "POST".to_string(),
+            url: "https://example.com/graphql".to_string(),
+            request_headers: Vec::new(),
+            request_body: None,
+            status: Some(200),
+            response_headers: Vec::new(),
+            response_body: None,
+            error: None,
+            error_kind: None,
+            duration_ms,
+        }
+    }
+
+    #[test]
+    fn first_request() {
+        let cold_start = ColdStart::new(&[exchange(900), exchange(40), exchange(60)]).unwrap();
+        assert_eq!(cold_start.first, Duration::from_millis(900));
+        assert_eq!(cold_start.warm.unwrap().p50, Duration::from_millis(40));
+        assert_eq!(ColdStart::new(&[exchange(900)]).unwrap().warm, None);
+        assert_eq!(ColdStart::new(&[]), None);
+    }
+
+    #[test]
+    fn same_request() {
+        let refused = Exchange {
+            status: None,
+            error: Some("Connection refused".to_string()),
+            ..exchange(1)
+        };
+        let introspection = Exchange {
+            request_body: Some(json!({"query": "{ __schema { types { name } } }"})),
+            ..exchange(2000)
+        };
+        let cold_start =
+            ColdStart::new(&[refused.clone(), exchange(900), introspection, exchange(40)]).unwrap();
+        assert_eq!(cold_start.first, Duration::from_millis(900));
+        assert_eq!(cold_start.warm.unwrap().samples, 1);
+        assert_eq!(ColdStart::new(&[refused]), None);
+    }
+}
+
+#[cfg(test)]
+mod test_check_concurrency {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    use serde_json::json;
+
+    use super::*;
+    use crate::testing::context;
+    use crate::HttpResponse;
+
+    /// A server with `workers` workers, which fails any request beyond those.
+    fn server(workers: usize) -> Context {
+        let handled = Arc::new(AtomicUsize::new(0));
+        context(move |_| {
+            Ok(if handled.fetch_add(1, Ordering::SeqCst) < workers {
+                HttpResponse::json(200, &json!({"data": {"__typename": "Query"}}))
+            } else {
+                HttpResponse::new(503, "Service Unavailable")
+            })
+        })
+    }
+
+    #[test]
+    fn enough_workers() {
+        let (outcome, stats) = check_concurrency(
+            &server(8),
+            "https://example.com/graphql",
+            &Auth::Disabled,
+            8,
+            Some(Duration::from_secs(10)),
+        );
+        assert_eq!(outcome, Outcome::Passed);
+        assert_eq!(stats.unwrap().samples, 8);
+    }
+
+    #[test]
+    fn too_few_workers() {
+        let (outcome, stats) = check_concurrency(
+            &server(6),
+            "https://example.com/graphql",
+            &Auth::Disabled,
+            8,
+            None,
+        );
+        assert_eq!(
+            outcome,
+            Outcome::Failed(Error::ConcurrentFailures {
+                failed: 2,
+                concurrency: 8,
+                first: "Got status code: 503, a proxy or load balancer could not reach the server (is it running?), with response: Service Unavailable".to_string(),
+            })
+        );
+        assert_eq!(stats.unwrap().samples, 6);
+    }
+}
@@ -1,291 +1,2537 @@
+//! Checks that a GraphQL endpoint is reachable and configured safely.
+//!
+//! Every check is blocking, so there's no async runtime to set up, a [`Checker`] can be run
+//! directly from build scripts or other synchronous tools. From async code, run it on a blocking
+//! thread (e.g., `tokio::task::spawn_blocking`) or use [`Checker::stream`].
+
+use std::borrow::Cow;
+use std::collections::BTreeMap;
 use std::fmt::Display;
+use std::sync::Arc;
+use std::time::Duration;
 
+use itertools::Itertools;
+use serde::ser::SerializeStructVariant;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
 use serde_json::Value::Object;
 use serde_json::{json, Value};
-use ureq::{Request, Response};
 
+pub use crate::access::{AccessMatrix, Role};
+pub use crate::checker::{CancellationToken, CheckReport, Checker};
+use crate::federation::check_imports;
+pub use crate::fleet::{EndpointReport, Fleet, FleetReport};
+use crate::graphql::{ErrorCode, GraphQLError};
+pub use crate::http::{
+    new_agent, Capture, DnsCache, ErrorKind, Exchange, HttpRequest, HttpResponse, RateLimit,
+    Retried, StatusCategory, Throttled, Transport, TransportError,
+};
+use crate::http::{Context, REDACTED};
+pub use crate::latency::{ColdStart, LatencyStats};
+pub use crate::metadata::CheckInfo;
+pub use crate::monitor::{Monitor, MonitorReport};
+pub use crate::profile::Profile;
+pub use crate::replay::Replay;
+use crate::schema::Schema;
+/// Re-exported with the `schema-ast` feature, for the types of [`schema::Schema::document`].
+#[cfg(feature = "schema-ast")]
+pub use graphql_parser;
+/// Re-exported so a custom [`ureq::Agent`] for [`Checker::agent`] is built with the same version.
+pub use ureq;
+
+mod access;
+#[cfg(feature = "schema-ast")]
+mod ast;
+pub mod baseline;
+mod caching;
+pub mod capabilities;
+mod checker;
+mod compliance;
+pub mod config;
+mod federation;
+pub mod fixture;
+mod fleet;
+pub mod graphql;
+mod http;
+mod latency;
+mod lint;
+mod metadata;
+mod monitor;
+mod profile;
+mod replay;
+pub mod report;
+mod robustness;
+pub mod schema;
+mod stability;
+#[cfg(any(test, feature = "testing"))]
+pub mod testing;
+mod validation;
+
+/// Runs every check against `url`.
+///
+/// Kept for compatibility, [`Checker`] is more flexible and reports the outcome of every check.
 pub fn run_checks(
     url: &str,
     auth: Auth,
     subgraph: Subgraph,
     introspection: Introspection,
 ) -> Result<(), Vec<Error>> {
-    let mut errors = Vec::new();
+    let errors: Vec<Error> = Checker::new(url)
+        .auth(auth)
+        .subgraph(subgraph)
+        .introspection(introspection)
+        .run()
+        .into_iter()
+        .filter_map(|result| match result.outcome {
+            Outcome::Failed(err) => Some(err),
+            Outcome::Passed | Outcome::Skipped => None,
+        })
+        .collect();
 
-    let basic_err = basic_query(url, Auth::Disabled).err();
-    let subgraph_err = check_subgraph(url, auth).err();
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(errors)
+    }
+}
 
-    let unauthed_err = if auth.is_enabled() {
-        if let Some(authed_err) = basic_query(url, auth).err() {
-            errors.push(authed_err);
-        }
-        match basic_err {
-            Some(Error::GraphQLError(_) | Error::BadStatus(_)) => None,
-            None => Some(Error::AuthNotEnforced),
-            other_err => other_err,
+/// Progress through a run, in the order it happens.
+enum Event {
+    Started(Check),
+    Finished(CheckResult),
+    Observed(Fact),
+}
+
+/// Something learned about the endpoint along the way, regardless of whether it was required.
+enum Fact {
+    IsSubgraph(bool),
+    IntrospectionEnabled(bool),
+    /// What each `Accept` header got, see [`CheckReport::negotiated`].
+    Negotiated(BTreeMap<String, String>),
+    /// How long the basic query took, see [`CheckReport::latency`].
+    Latency(LatencyStats),
+    /// How long the basic query took when sent concurrently, see
+    /// [`CheckReport::concurrent_latency`].
+    ConcurrentLatency(LatencyStats),
+    /// Every request which was retried, see [`CheckReport::retried`].
+    Retried(Vec<Retried>),
+    /// Every response which asked to slow down, see [`CheckReport::throttled`].
+    Throttled(Vec<Throttled>),
+    /// The subgraph's SDL, see [`CheckReport::schema`].
+    Schema(Schema),
+}
+
+/// What the endpoint is expected to be, for [`run_each_check_with`].
+struct Expectations<'a> {
+    subgraph: Subgraph,
+    introspection: Introspection,
+    unauthenticated: Option<&'a Rejection>,
+}
+
+/// Runs every check, reporting each as it starts and handing over its result as soon as it's known.
+fn run_each_check_with(
+    context: &Context,
+    url: &str,
+    auth: &Auth,
+    expectations: &Expectations,
+    combine: bool,
+    on_event: &mut dyn FnMut(Event),
+) {
+    let Expectations {
+        subgraph,
+        introspection,
+        unauthenticated,
+    } = *expectations;
+    let finished = |check, outcome| Event::Finished(CheckResult::new(check, outcome));
+
+    on_event(Event::Started(Check::BasicQuery));
+    on_event(Event::Started(Check::AuthEnforced));
+    // Answer as many checks as possible with one request: always for a required subgraph, which
+    // should answer `_service` alongside the basic query, and for every check when combining
+    // probes. Anything the combined request couldn't tell is asked separately.
+    let disallow_introspection = introspection == Introspection::Disallow;
+    let probed = if combine {
+        combined_probe(context, url, auth, disallow_introspection)
+    } else if subgraph.required() {
+        combined_probe(context, url, auth, false)
+    } else {
+        None
+    };
+    let authenticated_basic_query = || {
+        if probed.is_some() {
+            Ok(())
+        } else {
+            basic_query(context, url, auth)
         }
+    };
+    if auth.is_enabled() {
+        let auth_outcome = Rejection::check(
+            unauthenticated,
+            basic_query(context, url, &Auth::Disabled),
+            Error::AuthNotEnforced,
+        );
+        on_event(finished(
+            Check::BasicQuery,
+            authenticated_basic_query().into(),
+        ));
+        on_event(finished(Check::AuthEnforced, auth_outcome));
     } else {
-        basic_err
+        let basic_err = authenticated_basic_query().err();
+        on_event(finished(Check::BasicQuery, Outcome::from_error(basic_err)));
+        on_event(finished(Check::AuthEnforced, Outcome::Skipped));
+    }
+
+    on_event(Event::Started(Check::Subgraph));
+    on_event(Event::Started(Check::SubgraphSecured));
+    let subgraph_result = match probed.as_ref().and_then(|probed| probed.is_subgraph) {
+        Some(true) => Ok(probed.as_ref().and_then(|probed| probed.sdl.clone())),
+        Some(false) => Err(Error::NotASubgraph),
+        None => check_subgraph(context, url, auth).map(Some),
+    };
+    let (sdl, subgraph_err) = match subgraph_result {
+        Ok(sdl) => (sdl, None),
+        Err(err) => (None, Some(err)),
     };
-    if let Some(err) = unauthed_err {
-        errors.push(err);
+    if let Some(sdl) = &sdl {
+        on_event(Event::Observed(Fact::Schema(Schema::Sdl(sdl.clone()))));
+    }
+    let is_subgraph = subgraph_err.is_none();
+    // Only an answer says whether it's a subgraph, not a failure to get one.
+    if matches!(subgraph_err, None | Some(Error::NotASubgraph)) {
+        on_event(Event::Observed(Fact::IsSubgraph(is_subgraph)));
+    }
+    let subgraph_outcome = if subgraph.required() {
+        Outcome::from_error(subgraph_err)
+    } else {
+        Outcome::Skipped
+    };
+    on_event(finished(Check::Subgraph, subgraph_outcome));
+
+    let subgraph_secured_outcome = if !is_subgraph {
+        Outcome::Skipped
+    } else if !auth.is_enabled() && subgraph.security_required() {
+        Outcome::Failed(Error::InsecureSubgraph)
+    } else {
+        Outcome::Passed
+    };
+    on_event(finished(Check::SubgraphSecured, subgraph_secured_outcome));
+
+    on_event(Event::Started(Check::IntrospectionDisabled));
+    let introspection_outcome = match introspection {
+        Introspection::Disallow => {
+            let result = match probed
+                .as_ref()
+                .and_then(|probed| probed.introspection_enabled)
+            {
+                Some(true) => Err(Error::IntrospectionEnabled),
+                Some(false) => Ok(()),
+                None => require_introspection_disabled(context, url, auth),
+            };
+            match result {
+                Ok(()) => on_event(Event::Observed(Fact::IntrospectionEnabled(false))),
+                Err(Error::IntrospectionEnabled) => {
+                    on_event(Event::Observed(Fact::IntrospectionEnabled(true)));
+                }
+                Err(_) => {}
+            }
+            result.into()
+        }
+        Introspection::Allow => Outcome::Skipped,
+    };
+    on_event(finished(
+        Check::IntrospectionDisabled,
+        introspection_outcome,
+    ));
+
+    on_event(Event::Started(Check::FederationImports));
+    let imports_outcome = match sdl {
+        Some(sdl) => check_imports(&sdl).into(),
+        None => Outcome::Skipped,
+    };
+    on_event(finished(Check::FederationImports, imports_outcome));
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Check {
+    BasicQuery,
+    AuthEnforced,
+    Subgraph,
+    SubgraphSecured,
+    IntrospectionDisabled,
+    /// Only run when given a number of samples, see [`Checker::latency_samples`].
+    Latency,
+    /// Only run when given a concurrency, see [`Checker::concurrency`].
+    Concurrency,
+    /// Only run when given a policy, see [`Checker::cache_control`].
+    CacheControl,
+    /// Only run when asked to, see [`Checker::cdn_cache`].
+    CdnCache,
+    /// Only run when asked to, see [`Checker::persisted_query_hash`].
+    PersistedQueryHash,
+    /// Only run when given the content types to accept, see [`Checker::content_types`].
+    ContentTypes,
+    /// Only run when asked to, see [`Checker::unknown_field`].
+    UnknownFieldRejected,
+    /// The checks of [`Checker::compliance`].
+    AcceptNegotiated,
+    OptionsHandled,
+    HeadHandled,
+    NoConnectionClose,
+    /// The checks of [`Checker::robustness`].
+    MalformedJsonRejected,
+    MissingQueryRejected,
+    EmptyQueryRejected,
+    UnicodeHandled,
+    WrongVariablesRejected,
+    /// Only run when given an invalid header, see [`Checker::invalid_auth`].
+    InvalidAuthRejected,
+    /// Only run when given unprivileged credentials, see [`Checker::auth_semantics`].
+    AuthSemantics,
+    /// Only run when given an access matrix, see [`Checker::access`].
+    RoleAccess,
+    /// Only run for subgraphs, whose SDL links Federation 2.
+    FederationImports,
+    /// Only run when asked to, see [`Checker::check_entities`].
+    EntitiesResolvable,
+    /// Only run when given a query, see [`Checker::smoke_query`].
+    SmokeQuery,
+    /// Only run when given the health check endpoint of an Apollo Router.
+    RouterHealth,
+    /// Only run when given another endpoint to compare with, see [`Checker::compare_schema`].
+    SchemaMatches,
+    /// Only run when asked to run every check several times, see [`Checker::stability_runs`].
+    Stable,
+    /// The checks of [`Profile::ApolloServer`].
+    ApolloServerStackTracesHidden,
+    ApolloServerCsrfPrevention,
+    /// The checks of [`Profile::Yoga`].
+    YogaErrorsMasked,
+    YogaGraphiqlDisabled,
+    /// The checks of [`Profile::HotChocolate`].
+    HotChocolateCostAnalysis,
+    HotChocolateBananaCakePopDisabled,
+    /// The checks of [`Profile::GraphQLJava`].
+    GraphQLJavaDepthLimited,
+    /// The checks of [`Profile::Hasura`].
+    HasuraConsoleDisabled,
+    HasuraAdminSecretRequired,
+    HasuraDevModeDisabled,
+    HasuraMetadataApiDisabled,
+    /// The checks of [`schema::check_sdl`], which only need a schema.
+    SchemaValid,
+    SchemaLint,
+    BreakingChanges,
+}
+
+impl Check {
+    /// Every check, e.g., to list their [`Check::info`].
+    pub const ALL: [Check; 44] = [
+        Check::BasicQuery,
+        Check::AuthEnforced,
+        Check::Subgraph,
+        Check::SubgraphSecured,
+        Check::IntrospectionDisabled,
+        Check::Latency,
+        Check::Concurrency,
+        Check::CacheControl,
+        Check::CdnCache,
+        Check::PersistedQueryHash,
+        Check::ContentTypes,
+        Check::UnknownFieldRejected,
+        Check::AcceptNegotiated,
+        Check::OptionsHandled,
+        Check::HeadHandled,
+        Check::NoConnectionClose,
+        Check::MalformedJsonRejected,
+        Check::MissingQueryRejected,
+        Check::EmptyQueryRejected,
+        Check::UnicodeHandled,
+        Check::WrongVariablesRejected,
+        Check::InvalidAuthRejected,
+        Check::AuthSemantics,
+        Check::RoleAccess,
+        Check::FederationImports,
+        Check::EntitiesResolvable,
+        Check::SmokeQuery,
+        Check::RouterHealth,
+        Check::SchemaMatches,
+        Check::Stable,
+        Check::ApolloServerStackTracesHidden,
+        Check::ApolloServerCsrfPrevention,
+        Check::YogaErrorsMasked,
+        Check::YogaGraphiqlDisabled,
+        Check::HotChocolateCostAnalysis,
+        Check::HotChocolateBananaCakePopDisabled,
+        Check::GraphQLJavaDepthLimited,
+        Check::HasuraConsoleDisabled,
+        Check::HasuraAdminSecretRequired,
+        Check::HasuraDevModeDisabled,
+        Check::HasuraMetadataApiDisabled,
+        Check::SchemaValid,
+        Check::SchemaLint,
+        Check::BreakingChanges,
+    ];
+
+    pub const fn name(&self) -> &'static str {
+        match self {
+            Check::BasicQuery => "basic_query",
+            Check::AuthEnforced => "auth_enforced",
+            Check::Subgraph => "subgraph",
+            Check::SubgraphSecured => "subgraph_secured",
+            Check::IntrospectionDisabled => "introspection_disabled",
+            Check::Latency => "latency",
+            Check::Concurrency => "concurrency",
+            Check::CacheControl => "cache_control",
+            Check::CdnCache => "cdn_cache",
+            Check::PersistedQueryHash => "persisted_query_hash",
+            Check::ContentTypes => "content_types",
+            Check::UnknownFieldRejected => "unknown_field_rejected",
+            Check::AcceptNegotiated => "accept_negotiated",
+            Check::OptionsHandled => "options_handled",
+            Check::HeadHandled => "head_handled",
+            Check::NoConnectionClose => "no_connection_close",
+            Check::MalformedJsonRejected => "malformed_json_rejected",
+            Check::MissingQueryRejected => "missing_query_rejected",
+            Check::EmptyQueryRejected => "empty_query_rejected",
+            Check::UnicodeHandled => "unicode_handled",
+            Check::WrongVariablesRejected => "wrong_variables_rejected",
+            Check::InvalidAuthRejected => "invalid_auth_rejected",
+            Check::AuthSemantics => "auth_semantics",
+            Check::RoleAccess => "role_access",
+            Check::FederationImports => "federation_imports",
+            Check::EntitiesResolvable => "entities_resolvable",
+            Check::SmokeQuery => "smoke_query",
+            Check::RouterHealth => "router_health",
+            Check::SchemaMatches => "schema_matches",
+            Check::Stable => "stable",
+            Check::ApolloServerStackTracesHidden => "apollo_server_stack_traces_hidden",
+            Check::ApolloServerCsrfPrevention => "apollo_server_csrf_prevention",
+            Check::YogaErrorsMasked => "yoga_errors_masked",
+            Check::YogaGraphiqlDisabled => "yoga_graphiql_disabled",
+            Check::HotChocolateCostAnalysis => "hotchocolate_cost_analysis",
+            Check::HotChocolateBananaCakePopDisabled => "hotchocolate_banana_cake_pop_disabled",
+            Check::GraphQLJavaDepthLimited => "graphql_java_depth_limited",
+            Check::HasuraConsoleDisabled => "hasura_console_disabled",
+            Check::HasuraAdminSecretRequired => "hasura_admin_secret_required",
+            Check::HasuraDevModeDisabled => "hasura_dev_mode_disabled",
+            Check::HasuraMetadataApiDisabled => "hasura_metadata_api_disabled",
+            Check::SchemaValid => "schema_valid",
+            Check::SchemaLint => "schema_lint",
+            Check::BreakingChanges => "breaking_changes",
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, Hash, PartialEq, Serialize)]
+pub struct CheckResult {
+    pub check: Check,
+    pub outcome: Outcome,
+    /// How long the check took. Checks which share requests are each counted in full.
+    #[serde(
+        rename = "duration_ms",
+        serialize_with = "serialize_millis",
+        deserialize_with = "deserialize_millis"
+    )]
+    pub duration: Duration,
+    /// How much a failure of this check matters, which is the severity of the error unless
+    /// configured otherwise.
+    pub severity: Severity,
+    /// The method and URL of the last request the check sent before it failed, e.g.,
+    /// `POST https://example.com/graphql`. `None` if it passed or didn't send anything itself.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub request: Option<String>,
+}
+
+impl CheckResult {
+    pub const fn new(check: Check, outcome: Outcome) -> Self {
+        let severity = match &outcome {
+            Outcome::Failed(err) => err.severity(),
+            Outcome::Passed | Outcome::Skipped => Severity::Error,
+        };
+        Self {
+            check,
+            outcome,
+            duration: Duration::ZERO,
+            severity,
+            request: None,
+        }
+    }
+
+    pub const fn error(&self) -> Option<&Error> {
+        match &self.outcome {
+            Outcome::Failed(err) => Some(err),
+            Outcome::Passed | Outcome::Skipped => None,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, Hash, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case", tag = "status", content = "error")]
+pub enum Outcome {
+    Passed,
+    Failed(#[serde(with = "failed")] Error),
+    /// The check doesn't apply to the provided configuration.
+    Skipped,
+}
+
+impl Outcome {
+    fn from_error(error: Option<Error>) -> Self {
+        error.map_or(Outcome::Passed, Outcome::Failed)
+    }
+}
+
+impl From<Result<(), Error>> for Outcome {
+    fn from(result: Result<(), Error>) -> Self {
+        Outcome::from_error(result.err())
+    }
+}
+
+/// Deserializes from `{"enabled": {"header": "name: value"}}` or `"disabled"`, borrowing the header
+/// unless it has to be unescaped. When serialized or debug-formatted, the value of the header is
+/// redacted so it can't leak into logs or reports.
+#[derive(Clone, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Auth<'a> {
+    Enabled {
+        #[serde(borrow)]
+        header: Cow<'a, str>,
+    },
+    Disabled,
+    /// A header from [`TokenProvider::header`], fetched again for every request. Requests which
+    /// are meant to be unauthenticated never ask for it. Shared, so that the thread of
+    /// [`Checker::stream`] can use it too.
+    ///
+    /// Each header is redacted from responses, recordings and debugging bundles once it's been
+    /// fetched, like the header of [`Auth::Enabled`].
+    #[serde(skip)]
+    Dynamic(Arc<dyn TokenProvider>),
+}
+
+/// Where [`Auth::Dynamic`] gets its header from, e.g., to refresh short-lived tokens (OAuth, STS,
+/// Vault) during a long [`Monitor`] or [`Checker::wait_until_healthy`].
+///
+/// Checks are blocking, so this is too: it's called on whichever thread sends the request, right
+/// before sending it. An async provider can block on its future there, e.g., with
+/// `tokio::runtime::Handle::block_on` from a blocking thread. Any closure returning a header is a
+/// provider.
+pub trait TokenProvider: Send + Sync {
+    /// The header to send, as `name: value`, or why there isn't one.
+    fn header(&self) -> Result<String, String>;
+}
+
+impl<F: Fn() -> Result<String, String> + Send + Sync> TokenProvider for F {
+    fn header(&self) -> Result<String, String> {
+        self()
+    }
+}
+
+/// Providers are the same if they're clones of the same [`Arc`].
+impl PartialEq for Auth<'_> {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Auth::Enabled { header }, Auth::Enabled { header: other }) => header == other,
+            (Auth::Disabled, Auth::Disabled) => true,
+            (Auth::Dynamic(provider), Auth::Dynamic(other)) => Arc::ptr_eq(provider, other),
+            _ => false,
+        }
+    }
+}
+
+impl Eq for Auth<'_> {}
+
+impl std::hash::Hash for Auth<'_> {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        std::mem::discriminant(self).hash(state);
+        match self {
+            Auth::Enabled { header } => header.hash(state),
+            Auth::Disabled => {}
+            Auth::Dynamic(provider) => Arc::as_ptr(provider).cast::<()>().hash(state),
+        }
+    }
+}
+
+impl Serialize for Auth<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            Auth::Enabled { header } => {
+                let mut variant = serializer.serialize_struct_variant("Auth", 0, "enabled", 1)?;
+                variant.serialize_field("header", &redacted_header(header))?;
+                variant.end()
+            }
+            Auth::Disabled => serializer.serialize_unit_variant("Auth", 1, "disabled"),
+            Auth::Dynamic(_) => serializer.serialize_unit_variant("Auth", 2, "dynamic"),
+        }
+    }
+}
+
+impl std::fmt::Debug for Auth<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Auth::Enabled { header } => f
+                .debug_struct("Enabled")
+                .field("header", &redacted_header(header))
+                .finish(),
+            Auth::Disabled => f.write_str("Disabled"),
+            Auth::Dynamic(_) => f.write_str("Dynamic"),
+        }
+    }
+}
+
+/// The name of a `name: value` header, with the value redacted.
+pub(crate) fn redacted_header(header: &str) -> String {
+    let name = header.split_once(':').map_or(header, |(name, _)| name);
+    format!("{name}: {REDACTED}")
+}
+
+impl Auth<'_> {
+    const fn is_enabled(&self) -> bool {
+        matches!(self, Auth::Enabled { .. } | Auth::Dynamic(_))
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, Deserialize, Eq, Hash, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Subgraph {
+    Secure,
+    Insecure,
+    #[default]
+    NotASubgraph,
+}
+
+impl Subgraph {
+    const fn required(&self) -> bool {
+        matches!(self, Subgraph::Secure | Subgraph::Insecure)
+    }
+
+    const fn security_required(&self) -> bool {
+        matches!(self, Subgraph::Secure | Subgraph::NotASubgraph)
+    }
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, Eq, Hash, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Introspection {
+    Allow,
+    Disallow,
+}
+
+/// How the server is expected to reject a request without valid auth, for
+/// [`Check::AuthEnforced`] and [`Check::InvalidAuthRejected`]. Without one, any GraphQL error or
+/// `4xx` status counts as rejected, unless every error is an `INTERNAL_SERVER_ERROR`.
+#[derive(Clone, Debug, Deserialize, Eq, Hash, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Rejection {
+    /// A response with this status, e.g., `401`.
+    Status(u16),
+    /// A GraphQL error with this `extensions.code`, e.g., `UNAUTHENTICATED`, whatever the status.
+    ErrorCode(String),
+}
+
+impl Rejection {
+    /// Parses a status code like `401`, or anything else as an error code.
+    pub fn parse(value: &str) -> Result<Self, Error> {
+        if value.is_empty() {
+            return Err(Error::BadInput(
+                "the expected rejection can't be empty".to_string(),
+            ));
+        }
+        if !value.bytes().all(|byte| byte.is_ascii_digit()) {
+            return Ok(Rejection::ErrorCode(value.to_string()));
+        }
+        match value.parse() {
+            Ok(status @ 400..=599) => Ok(Rejection::Status(status)),
+            _ => Err(Error::BadInput(format!(
+                "`{value}` isn't an error status code"
+            ))),
+        }
+    }
+
+    fn matches(&self, err: &Error) -> bool {
+        match (self, err) {
+            (Rejection::Status(expected), Error::BadStatus { status, .. }) => status == expected,
+            (Rejection::ErrorCode(expected), Error::GraphQLError(errors)) => {
+                errors.iter().any(|err| err.code() == Some(expected))
+            }
+            _ => false,
+        }
     }
 
-    let is_subgraph = if let Some(err) = subgraph_err {
-        if subgraph.required() {
-            errors.push(err);
+    /// Whether `result`, from a request without valid auth, was rejected as expected. `accepted`
+    /// is the error if the request succeeded.
+    fn check(expected: Option<&Self>, result: Result<(), Error>, accepted: Error) -> Outcome {
+        let err = match result {
+            Ok(()) => return Outcome::Failed(accepted),
+            Err(err) => err,
+        };
+        let rejected = match expected {
+            Some(expected) => expected.matches(&err),
+            // Like a 5xx status, an error the server only labels as internal is a crash, not a
+            // rejection.
+            None => match &err {
+                Error::GraphQLError(errors) => !errors
+                    .iter()
+                    .all(|err| err.kind() == Some(ErrorCode::InternalServerError)),
+                Error::BadStatus { status, .. } => *status < 500,
+                _ => false,
+            },
+        };
+        if rejected {
+            return Outcome::Passed;
         }
-        false
+        let Some(got) = describe_rejection(&err) else {
+            return Outcome::Failed(err);
+        };
+        Outcome::Failed(Error::WrongRejection {
+            expected: expected.cloned(),
+            got,
+        })
+    }
+}
+
+/// How the server rejected a request, e.g., `status 500`, or `None` if it didn't get an answer.
+fn describe_rejection(err: &Error) -> Option<String> {
+    match err {
+        Error::BadStatus { status, .. } => Some(format!("status {status}")),
+        Error::GraphQLError(errors) => {
+            let codes = errors.iter().filter_map(GraphQLError::code).join(", ");
+            Some(if codes.is_empty() {
+                "errors without a code".to_string()
+            } else {
+                format!("errors with code {codes}")
+            })
+        }
+        _ => None,
+    }
+}
+
+impl Display for Rejection {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Rejection::Status(status) => write!(f, "status {status}"),
+            Rejection::ErrorCode(code) => write!(f, "error code {code}"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test_rejection {
+    use super::*;
+
+    fn status(status: u16) -> Result<(), Error> {
+        Err(Error::BadStatus {
+            status,
+            excerpt: String::new(),
+        })
+    }
+
+    fn code(code: &str) -> Result<(), Error> {
+        let mut error = GraphQLError::new("Not allowed");
+        error.extensions = Some(json!({ "code": code }));
+        Err(Error::GraphQLError(vec![error]))
+    }
+
+    #[test]
+    fn parse() {
+        assert_eq!(Rejection::parse("401").unwrap(), Rejection::Status(401));
+        assert_eq!(
+            Rejection::parse("UNAUTHENTICATED").unwrap(),
+            Rejection::ErrorCode("UNAUTHENTICATED".to_string())
+        );
+        assert!(Rejection::parse("200").is_err());
+        assert!(Rejection::parse("").is_err());
+    }
+
+    #[test]
+    fn server_errors_arent_rejections() {
+        assert_eq!(
+            Rejection::check(None, status(403), Error::AuthNotEnforced),
+            Outcome::Passed
+        );
+        assert_eq!(
+            Rejection::check(None, status(500), Error::AuthNotEnforced),
+            Outcome::Failed(Error::WrongRejection {
+                expected: None,
+                got: "status 500".to_string()
+            })
+        );
+    }
+
+    #[test]
+    fn internal_error_is_not_a_rejection() {
+        assert_eq!(
+            Rejection::check(None, code("UNAUTHENTICATED"), Error::AuthNotEnforced),
+            Outcome::Passed
+        );
+        assert_eq!(
+            Rejection::check(None, code("INTERNAL_SERVER_ERROR"), Error::AuthNotEnforced),
+            Outcome::Failed(Error::WrongRejection {
+                expected: None,
+                got: "errors with code INTERNAL_SERVER_ERROR".to_string()
+            })
+        );
+    }
+
+    #[test]
+    fn expected() {
+        let unauthenticated = Rejection::ErrorCode("UNAUTHENTICATED".to_string());
+        assert_eq!(
+            Rejection::check(
+                Some(&unauthenticated),
+                code("UNAUTHENTICATED"),
+                Error::AuthNotEnforced
+            ),
+            Outcome::Passed
+        );
+        assert_eq!(
+            Rejection::check(
+                Some(&unauthenticated),
+                code("INTERNAL_SERVER_ERROR"),
+                Error::AuthNotEnforced
+            ),
+            Outcome::Failed(Error::WrongRejection {
+                expected: Some(unauthenticated),
+                got: "errors with code INTERNAL_SERVER_ERROR".to_string()
+            })
+        );
+        assert_eq!(
+            Rejection::check(
+                Some(&Rejection::Status(401)),
+                status(403),
+                Error::AuthNotEnforced
+            ),
+            Outcome::Failed(Error::WrongRejection {
+                expected: Some(Rejection::Status(401)),
+                got: "status 403".to_string()
+            })
+        );
+        assert_eq!(
+            Rejection::check(
+                Some(&Rejection::Status(401)),
+                Ok(()),
+                Error::AuthNotEnforced
+            ),
+            Outcome::Failed(Error::AuthNotEnforced)
+        );
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, Hash, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+#[non_exhaustive]
+pub enum Error {
+    BadUri(UriProblem),
+    BadStatus {
+        status: u16,
+        /// The start of the response body, to tell e.g. a proxy's error page from the server's.
+        excerpt: String,
+    },
+    /// No response for a reason not covered by the more specific variants below.
+    CouldNotConnect(TransportError),
+    /// The endpoint's host couldn't be resolved.
+    DnsFailed(TransportError),
+    /// A connection was made, but the TLS handshake failed, e.g., over an untrusted certificate.
+    TlsFailed(TransportError),
+    /// Nothing is listening on the endpoint's port.
+    ConnectionRefused(TransportError),
+    /// Connecting or getting a response took longer than the timeout.
+    Timeout {
+        /// The check which was waiting, if the request was sent by one.
+        check: Option<Check>,
+        /// How long the request took before giving up.
+        #[serde(
+            rename = "elapsed_ms",
+            serialize_with = "serialize_millis",
+            deserialize_with = "deserialize_millis"
+        )]
+        elapsed: Duration,
+        /// The longest the request was allowed to take, see [`Checker::timeout`].
+        #[serde(
+            rename = "budget_ms",
+            serialize_with = "serialize_millis",
+            deserialize_with = "deserialize_millis"
+        )]
+        budget: Duration,
+    },
+    /// Nothing ever accepted connections, see [`Checker::wait_for_port`].
+    ServerNotStarted {
+        address: String,
+        #[serde(
+            rename = "waited_ms",
+            serialize_with = "serialize_millis",
+            deserialize_with = "deserialize_millis"
+        )]
+        waited: Duration,
+    },
+    /// The response wasn't JSON or didn't answer `__typename`, e.g., a login page or the error
+    /// page of a load balancer.
+    #[serde(rename = "not_graphql")]
+    NotGraphQL {
+        /// The `Content-Type` of the response, without parameters, empty if there wasn't one.
+        content_type: String,
+        /// The start of the response body, see [`Error::BadStatus`].
+        excerpt: String,
+    },
+    #[serde(rename = "graphql_error")]
+    GraphQLError(Vec<GraphQLError>),
+    AuthNotEnforced,
+    BadHeader,
+    /// The [`TokenProvider`] of [`Auth::Dynamic`] didn't have a header to send.
+    TokenUnavailable(String),
+    NotASubgraph,
+    BadBoolean(String),
+    IntrospectionEnabled,
+    InsecureSubgraph,
+    BadReport(String),
+    /// A file this run was asked to write, like a report, couldn't be written.
+    CouldNotWrite(String),
+    /// A recording to replay couldn't be read, see [`Replay`].
+    BadRecording(String),
+    BadFormat(String),
+    GitHubApi(String),
+    Cancelled,
+    BadConfig(String),
+    /// An input which is valid on its own but not with the others, or out of range.
+    BadInput(String),
+    /// Neither the subgraph SDL nor introspection were available.
+    SchemaUnavailable,
+    /// A schema, e.g., a saved baseline, which couldn't be parsed.
+    BadSchema(String),
+    /// The live schema differs from the one it should match, e.g., the latest version in a registry
+    /// or the schema of another endpoint.
+    SchemaDrift {
+        /// Where the expected schema came from.
+        expected: String,
+        /// One line for each change, see [`schema::SchemaDiff`].
+        changes: Vec<String>,
+    },
+    /// A schema registry couldn't be reached or didn't return a schema.
+    Registry(String),
+    /// Results couldn't be reported to Apollo Studio.
+    Studio(String),
+    /// The server rejected a request without valid auth, but not how it was expected to. `None`
+    /// expects any GraphQL error or `4xx` status.
+    WrongRejection {
+        expected: Option<Rejection>,
+        got: String,
+    },
+    /// Unauthenticated and unauthorized requests weren't told apart, with how.
+    AuthSemantics(Vec<String>),
+    /// The response's `Cache-Control` header (if any) didn't have every directive expected.
+    Cacheable {
+        expected: String,
+        got: Option<String>,
+    },
+    /// A persisted query sent as a `GET` twice wasn't answered from a CDN's cache.
+    NotCachedByCdn,
+    /// A persisted query sent as a `GET` with auth was answered from a CDN's cache.
+    AuthenticatedResponseCached,
+    /// A persisted query was registered under a hash which doesn't match its document.
+    PersistedQueryHashIgnored,
+    /// A query for a field which doesn't exist wasn't rejected with a validation error, and why.
+    UnknownFieldNotRejected(String),
+    /// A deliberately bad request wasn't rejected as a request error, and what went wrong instead.
+    BadRequestMishandled {
+        request: String,
+        problem: String,
+    },
+    /// A valid query with an unusual string in it wasn't answered, or the string was quoted back
+    /// changed, and what happened instead.
+    PayloadMishandled {
+        payload: String,
+        problem: String,
+    },
+    /// Content types which were accepted but shouldn't be, or the other way around.
+    ContentTypes(Vec<String>),
+    /// A request with this `Accept` header got a response the client didn't ask for.
+    AcceptNotNegotiated {
+        accept: String,
+        got: String,
+    },
+    /// A request with this method crashed the server or got a response it shouldn't.
+    MethodMishandled {
+        method: String,
+        problem: String,
+    },
+    /// The server asked for its connection to be closed, or a request right after another failed.
+    ConnectionClose(String),
+    /// The 95th percentile of how long the basic query took was over the limit.
+    SlowP95 {
+        #[serde(
+            rename = "p95_ms",
+            serialize_with = "serialize_millis",
+            deserialize_with = "deserialize_millis"
+        )]
+        p95: Duration,
+        #[serde(
+            rename = "limit_ms",
+            serialize_with = "serialize_millis",
+            deserialize_with = "deserialize_millis"
+        )]
+        limit: Duration,
+    },
+    /// Some of the basic queries sent at the same time failed, with the first error.
+    ConcurrentFailures {
+        failed: u32,
+        concurrency: u32,
+        first: String,
+    },
+    /// The basic queries sent at the same time took longer than the budget, all together.
+    ConcurrencyTooSlow {
+        #[serde(
+            rename = "took_ms",
+            serialize_with = "serialize_millis",
+            deserialize_with = "deserialize_millis"
+        )]
+        took: Duration,
+        #[serde(
+            rename = "budget_ms",
+            serialize_with = "serialize_millis",
+            deserialize_with = "deserialize_millis"
+        )]
+        budget: Duration,
+    },
+    /// Checks whose outcome changed between runs, with the outcome of each run.
+    Flaky(Vec<String>),
+    /// Too few runs passed while monitoring, see [`Monitor`].
+    LowAvailability {
+        passed: u32,
+        runs: u32,
+        /// The lowest percentage of runs which had to pass.
+        min: u8,
+    },
+    /// Roles which could run operations they shouldn't, or couldn't run ones they should.
+    AccessViolations(Vec<String>),
+    /// The server answered a query sent with an invalid (e.g., expired) auth header.
+    InvalidAuthAccepted,
+    /// Problems with what the subgraph's federation `@link` imports, compared to what it uses.
+    BadFederationImports(Vec<String>),
+    /// Entities the subgraph couldn't look up with `_entities`, with why.
+    EntitiesUnresolvable(Vec<String>),
+    /// The router didn't fetch from these subgraphs for the smoke query.
+    SubgraphsNotQueried(Vec<String>),
+    /// The router didn't include its query plan in the response.
+    QueryPlanUnavailable,
+    /// The router's health check said it's not `live` or not `ready`.
+    RouterUnhealthy {
+        probe: String,
+        status: u16,
+    },
+    /// The router is healthy but the endpoint failed, so the problem is probably behind it.
+    SubgraphProblem,
+    /// Errors include stack traces.
+    StackTraces,
+    /// Unexpected errors aren't replaced with a generic message.
+    ErrorsUnmasked,
+    /// A GraphQL IDE is served, named here.
+    IdeEnabled(String),
+    CsrfPreventionDisabled,
+    CostAnalysisDisabled,
+    /// A query nested this deep was answered.
+    NoDepthLimit(usize),
+    HasuraConsoleEnabled,
+    HasuraAdminSecretNotRequired,
+    /// Errors include internal details like SQL, see `HASURA_GRAPHQL_DEV_MODE`.
+    HasuraDevMode,
+    HasuraMetadataApiExposed,
+    /// Names which don't follow the usual conventions and similar style problems in a schema.
+    LintViolations(Vec<String>),
+    /// Changes to a schema which would break its clients, compared to a baseline.
+    BreakingChanges(Vec<String>),
+}
+
+/// Why a URL can't be checked, found before making any requests.
+#[derive(Clone, Debug, Deserialize, Eq, Hash, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+#[non_exhaustive]
+pub enum UriProblem {
+    MissingScheme,
+    UnsupportedScheme(String),
+    BadPort,
+    /// Credentials in the URL would end up in logs, use the `auth` input instead.
+    EmbeddedCredentials,
+    Invalid(String),
+}
+
+impl Display for UriProblem {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            UriProblem::MissingScheme => write!(f, "missing a scheme like `https://`"),
+            UriProblem::UnsupportedScheme(scheme) => {
+                write!(
+                    f,
+                    "unsupported scheme `{scheme}`, only `http` and `https` work"
+                )
+            }
+            UriProblem::BadPort => write!(f, "the port is not a number from 0 to 65535"),
+            UriProblem::EmbeddedCredentials => write!(
+                f,
+                "contains a username or password, which should be passed with `auth` instead"
+            ),
+            UriProblem::Invalid(reason) => write!(f, "{reason}"),
+        }
+    }
+}
+
+/// How much a failure matters. Only [`Severity::Error`] should fail a run, warnings are
+/// findings worth surfacing which shouldn't block a deploy.
+#[derive(Clone, Copy, Debug, Default, Deserialize, Eq, Hash, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    #[default]
+    Error,
+    Warning,
+}
+
+impl Error {
+    pub const fn severity(&self) -> Severity {
+        match self {
+            // The server is up, it just won't answer this often.
+            Error::BadStatus { status, .. }
+                if matches!(StatusCategory::of(*status), StatusCategory::RateLimited) =>
+            {
+                Severity::Warning
+            }
+            Error::BadUri(_)
+            | Error::BadStatus { .. }
+            | Error::CouldNotConnect(_)
+            | Error::DnsFailed(_)
+            | Error::TlsFailed(_)
+            | Error::ConnectionRefused(_)
+            | Error::Timeout { .. }
+            | Error::ServerNotStarted { .. }
+            | Error::NotGraphQL { .. }
+            | Error::GraphQLError(_)
+            | Error::AuthNotEnforced
+            | Error::BadHeader
+            | Error::TokenUnavailable(_)
+            | Error::NotASubgraph
+            | Error::BadBoolean(_)
+            | Error::IntrospectionEnabled
+            | Error::InsecureSubgraph
+            | Error::BadReport(_)
+            | Error::CouldNotWrite(_)
+            | Error::BadRecording(_)
+            | Error::BadFormat(_)
+            | Error::Cancelled
+            | Error::BadConfig(_)
+            | Error::BadInput(_)
+            | Error::SchemaUnavailable
+            | Error::BadSchema(_)
+            | Error::SchemaDrift { .. }
+            | Error::Registry(_)
+            | Error::WrongRejection { .. }
+            | Error::InvalidAuthAccepted
+            | Error::AuthSemantics(_)
+            | Error::AccessViolations(_)
+            | Error::Cacheable { .. }
+            | Error::NotCachedByCdn
+            | Error::AuthenticatedResponseCached
+            | Error::PersistedQueryHashIgnored
+            | Error::UnknownFieldNotRejected(_)
+            | Error::BadRequestMishandled { .. }
+            | Error::PayloadMishandled { .. }
+            | Error::ContentTypes(_)
+            | Error::AcceptNotNegotiated { .. }
+            | Error::MethodMishandled { .. }
+            | Error::ConnectionClose(_)
+            | Error::SlowP95 { .. }
+            | Error::Flaky(_)
+            | Error::ConcurrentFailures { .. }
+            | Error::ConcurrencyTooSlow { .. }
+            | Error::LowAvailability { .. }
+            | Error::BadFederationImports(_)
+            | Error::EntitiesUnresolvable(_)
+            | Error::SubgraphsNotQueried(_)
+            | Error::QueryPlanUnavailable
+            | Error::RouterUnhealthy { .. }
+            | Error::StackTraces
+            | Error::ErrorsUnmasked
+            | Error::IdeEnabled(_)
+            | Error::CsrfPreventionDisabled
+            | Error::CostAnalysisDisabled
+            | Error::NoDepthLimit(_)
+            | Error::HasuraConsoleEnabled
+            | Error::HasuraAdminSecretNotRequired
+            | Error::HasuraDevMode
+            | Error::HasuraMetadataApiExposed
+            | Error::BreakingChanges(_) => Severity::Error,
+            Error::GitHubApi(_)
+            | Error::Studio(_)
+            | Error::SubgraphProblem
+            | Error::LintViolations(_) => Severity::Warning,
+        }
+    }
+
+    /// A stable identifier for the kind of failure, used to match failures between runs.
+    pub const fn code(&self) -> &'static str {
+        match self {
+            Error::BadUri(_) => "bad_uri",
+            Error::BadStatus { .. } => "bad_status",
+            Error::CouldNotConnect(_) => "could_not_connect",
+            Error::DnsFailed(_) => "dns_failed",
+            Error::TlsFailed(_) => "tls_failed",
+            Error::ConnectionRefused(_) => "connection_refused",
+            Error::Timeout { .. } => "timeout",
+            Error::ServerNotStarted { .. } => "server_not_started",
+            Error::NotGraphQL { .. } => "not_graphql",
+            Error::GraphQLError(_) => "graphql_error",
+            Error::AuthNotEnforced => "auth_not_enforced",
+            Error::BadHeader => "bad_header",
+            Error::TokenUnavailable(_) => "token_unavailable",
+            Error::NotASubgraph => "not_a_subgraph",
+            Error::BadBoolean(_) => "bad_boolean",
+            Error::IntrospectionEnabled => "introspection_enabled",
+            Error::InsecureSubgraph => "insecure_subgraph",
+            Error::BadReport(_) => "bad_report",
+            Error::CouldNotWrite(_) => "could_not_write",
+            Error::BadRecording(_) => "bad_recording",
+            Error::BadFormat(_) => "bad_format",
+            Error::GitHubApi(_) => "github_api",
+            Error::Cancelled => "cancelled",
+            Error::BadConfig(_) => "bad_config",
+            Error::BadInput(_) => "bad_input",
+            Error::SchemaUnavailable => "schema_unavailable",
+            Error::BadSchema(_) => "bad_schema",
+            Error::SchemaDrift { .. } => "schema_drift",
+            Error::Registry(_) => "registry",
+            Error::Studio(_) => "studio",
+            Error::WrongRejection { .. } => "wrong_rejection",
+            Error::InvalidAuthAccepted => "invalid_auth_accepted",
+            Error::AuthSemantics(_) => "auth_semantics",
+            Error::AccessViolations(_) => "access_violations",
+            Error::Cacheable { .. } => "cacheable",
+            Error::NotCachedByCdn => "not_cached_by_cdn",
+            Error::AuthenticatedResponseCached => "authenticated_response_cached",
+            Error::PersistedQueryHashIgnored => "persisted_query_hash_ignored",
+            Error::UnknownFieldNotRejected(_) => "unknown_field_not_rejected",
+            Error::BadRequestMishandled { .. } => "bad_request_mishandled",
+            Error::PayloadMishandled { .. } => "payload_mishandled",
+            Error::ContentTypes(_) => "content_types",
+            Error::AcceptNotNegotiated { .. } => "accept_not_negotiated",
+            Error::MethodMishandled { .. } => "method_mishandled",
+            Error::ConnectionClose(_) => "connection_close",
+            Error::SlowP95 { .. } => "slow_p95",
+            Error::Flaky(_) => "flaky",
+            Error::ConcurrentFailures { .. } => "concurrent_failures",
+            Error::ConcurrencyTooSlow { .. } => "concurrency_too_slow",
+            Error::LowAvailability { .. } => "low_availability",
+            Error::BadFederationImports(_) => "bad_federation_imports",
+            Error::EntitiesUnresolvable(_) => "entities_unresolvable",
+            Error::SubgraphsNotQueried(_) => "subgraphs_not_queried",
+            Error::QueryPlanUnavailable => "query_plan_unavailable",
+            Error::RouterUnhealthy { .. } => "router_unhealthy",
+            Error::SubgraphProblem => "subgraph_problem",
+            Error::StackTraces => "stack_traces",
+            Error::ErrorsUnmasked => "errors_unmasked",
+            Error::IdeEnabled(_) => "ide_enabled",
+            Error::CsrfPreventionDisabled => "csrf_prevention_disabled",
+            Error::CostAnalysisDisabled => "cost_analysis_disabled",
+            Error::NoDepthLimit(_) => "no_depth_limit",
+            Error::HasuraConsoleEnabled => "hasura_console_enabled",
+            Error::HasuraAdminSecretNotRequired => "hasura_admin_secret_not_required",
+            Error::HasuraDevMode => "hasura_dev_mode",
+            Error::HasuraMetadataApiExposed => "hasura_metadata_api_exposed",
+            Error::LintViolations(_) => "lint_violations",
+            Error::BreakingChanges(_) => "breaking_changes",
+        }
+    }
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Error::BadUri(problem) => write!(f, "Bad URI: {problem}"),
+            Error::CouldNotConnect(_) => write!(f, "Could not connect"),
+            Error::DnsFailed(_) => write!(
+                f,
+                "Could not resolve the host (check the URL for typos, and that the runner can reach the DNS it's registered in)"
+            ),
+            Error::TlsFailed(_) => write!(
+                f,
+                "TLS handshake failed (check that the certificate is for this host, hasn't expired and is signed by a trusted CA)"
+            ),
+            Error::ConnectionRefused(_) => write!(
+                f,
+                "Connection refused (check the port, or set `wait_for_port` if the server is still starting)"
+            ),
+            Error::Timeout {
+                elapsed, budget, ..
+            } => write!(
+                f,
+                "Timed out after {}ms waiting for a response, the budget is {}ms (raise `timeout_ms` if the server is just slow)",
+                elapsed.as_millis(),
+                budget.as_millis()
+            ),
+            Error::ServerNotStarted { address, waited } => write!(
+                f,
+                "Server never started, nothing accepted connections on {address} within {}s",
+                waited.as_secs()
+            ),
+            Error::NotGraphQL { excerpt, .. } if excerpt.is_empty() => {
+                write!(f, "Not GraphQL, the response was empty")
+            }
+            Error::NotGraphQL {
+                content_type,
+                excerpt,
+            } if content_type.is_empty() => write!(f, "Not GraphQL, got response: {excerpt}"),
+            Error::NotGraphQL {
+                content_type,
+                excerpt,
+            } => write!(f, "Not GraphQL, got {content_type} response: {excerpt}"),
+            Error::GraphQLError(errors) => write!(
+                f,
+                "Received error from GraphQL server: {}",
+                errors.iter().join("; ")
+            ),
+            Error::AuthNotEnforced => {
+                write!(f, "Able to make queries with no authentication header")
+            }
+            Error::BadHeader => write!(
+                f,
+                "Provided `auth` input was not a valid header in the format of `name: value`"
+            ),
+            Error::TokenUnavailable(message) => {
+                write!(f, "Could not get a token to authenticate with: {message}")
+            }
+            Error::BadStatus { status, excerpt } => {
+                let description = StatusCategory::of(*status).describe();
+                write!(f, "Got status code: {status}, {description}")?;
+                if !excerpt.is_empty() {
+                    write!(f, ", with response: {excerpt}")?;
+                }
+                Ok(())
+            }
+            Error::NotASubgraph => write!(f, "GraphQL endpoint is not a subgraph"),
+            Error::IntrospectionEnabled => write!(
+                f,
+                "Introspection is enabled for the GraphQL server but not allowed"
+            ),
+            Error::BadBoolean(name) => write!(f, "Input `{name}` can only be `true` or `false`"),
+            Error::InsecureSubgraph => write!(f, "Subgraph is not protected by authentication"),
+            Error::BadReport(reason) => write!(f, "Could not read previous report: {reason}"),
+            Error::CouldNotWrite(reason) => write!(f, "Could not write {reason}"),
+            Error::BadRecording(reason) => write!(f, "Could not read recording: {reason}"),
+            Error::BadFormat(format) => {
+                write!(f, "Unknown output format `{format}`")
+            }
+            Error::GitHubApi(reason) => write!(f, "Could not report results to GitHub: {reason}"),
+            Error::Cancelled => write!(f, "Cancelled before the check finished"),
+            Error::BadConfig(reason) => write!(f, "Could not read config: {reason}"),
+            Error::BadInput(reason) => write!(f, "Invalid input: {reason}"),
+            Error::SchemaUnavailable => write!(
+                f,
+                "Could not fetch the schema, the endpoint is not a subgraph and introspection is disabled"
+            ),
+            Error::BadSchema(reason) => write!(f, "Could not parse schema: {reason}"),
+            Error::SchemaDrift { expected, changes } => write!(
+                f,
+                "Schema differs from {expected}: {}",
+                changes.iter().join(", ")
+            ),
+            Error::Registry(reason) => write!(f, "Could not fetch the registered schema: {reason}"),
+            Error::Studio(reason) => write!(f, "Could not report results to Apollo Studio: {reason}"),
+            Error::WrongRejection {
+                expected: Some(expected),
+                got,
+            } => write!(
+                f,
+                "Expected a request without valid `auth` to be rejected with {expected}, got {got}"
+            ),
+            Error::WrongRejection {
+                expected: None,
+                got,
+            } => write!(
+                f,
+                "Expected a request without valid `auth` to be rejected with a GraphQL error or a 4xx status, got {got}"
+            ),
+            Error::AuthSemantics(problems) => write!(
+                f,
+                "Unauthenticated (401) and unauthorized (403) requests aren't told apart: {}",
+                problems.iter().join("; ")
+            ),
+            Error::Cacheable {
+                expected,
+                got: Some(got),
+            } => write!(
+                f,
+                "Expected `Cache-Control: {expected}` so responses can't be cached, got `{got}`"
+            ),
+            Error::Cacheable {
+                expected,
+                got: None,
+            } => write!(
+                f,
+                "Expected `Cache-Control: {expected}` so responses can't be cached, got no `Cache-Control` header"
+            ),
+            Error::NotCachedByCdn => write!(
+                f,
+                "A persisted query sent as a GET twice wasn't answered from a CDN's cache"
+            ),
+            Error::AuthenticatedResponseCached => write!(
+                f,
+                "A persisted query sent as a GET with `auth` was answered from a CDN's cache, so it could be served to anyone"
+            ),
+            Error::PersistedQueryHashIgnored => write!(
+                f,
+                "A persisted query was registered under the hash of a different document, so anyone could poison the cache"
+            ),
+            Error::UnknownFieldNotRejected(reason) => write!(
+                f,
+                "A query for a field which doesn't exist wasn't rejected as invalid: {reason}"
+            ),
+            Error::BadRequestMishandled { request, problem } => write!(
+                f,
+                "Sending {request} wasn't rejected as a bad request: {problem}"
+            ),
+            Error::PayloadMishandled { payload, problem } => {
+                write!(f, "A query with {payload} in it wasn't handled: {problem}")
+            }
+            Error::ContentTypes(mismatches) => write!(
+                f,
+                "The server doesn't accept the expected content types: {}",
+                mismatches.iter().join(", ")
+            ),
+            Error::AcceptNotNegotiated { accept, got } => write!(
+                f,
+                "Asking for `Accept: {accept}` got {got}, which is neither that, JSON, nor a 406"
+            ),
+            Error::MethodMishandled { method, problem } => {
+                write!(f, "The {method} request wasn't handled properly: {problem}")
+            }
+            Error::ConnectionClose(problem) => {
+                write!(f, "Connections can't be reused: {problem}")
+            }
+            Error::SlowP95 { p95, limit } => write!(
+                f,
+                "The basic query's p95 latency was {}ms, over the limit of {}ms",
+                p95.as_millis(),
+                limit.as_millis()
+            ),
+            Error::ConcurrentFailures {
+                failed,
+                concurrency,
+                first,
+            } => write!(
+                f,
+                "{failed} of {concurrency} basic queries sent at the same time failed, the first with: {first}"
+            ),
+            Error::ConcurrencyTooSlow { took, budget } => write!(
+                f,
+                "Basic queries sent at the same time took {}ms, over the budget of {}ms",
+                took.as_millis(),
+                budget.as_millis()
+            ),
+            Error::LowAvailability { passed, runs, min } => write!(
+                f,
+                "Only {passed} of {runs} runs passed, below the minimum availability of {min}%"
+            ),
+            Error::Flaky(changed) => write!(
+                f,
+                "Some checks had different outcomes between runs: {}",
+                changed.iter().join("; ")
+            ),
+            Error::AccessViolations(violations) => {
+                write!(f, "Access control is wrong: {}", violations.iter().join("; "))
+            }
+            Error::InvalidAuthAccepted => write!(
+                f,
+                "Was able to make a request with an invalid `auth` header, is it only checked for presence?"
+            ),
+            Error::BadFederationImports(problems) => write!(
+                f,
+                "The federation @link doesn't match the directives used: {}",
+                problems.iter().join("; ")
+            ),
+            Error::EntitiesUnresolvable(entities) => write!(
+                f,
+                "Could not resolve entities: {}",
+                entities.iter().join(", ")
+            ),
+            Error::SubgraphsNotQueried(subgraphs) => write!(
+                f,
+                "The router didn't fetch from {} for the smoke query",
+                subgraphs.iter().join(", ")
+            ),
+            Error::QueryPlanUnavailable => write!(
+                f,
+                "The router didn't return its query plan, is `plugins.experimental.expose_query_plan` enabled?"
+            ),
+            Error::RouterUnhealthy { probe, status } => write!(
+                f,
+                "Router is not {probe}, its health check returned status code {status}"
+            ),
+            Error::SubgraphProblem => write!(
+                f,
+                "Router is live and ready but the basic query failed, so a subgraph is probably down"
+            ),
+            Error::StackTraces => write!(f, "Errors include stack traces"),
+            Error::ErrorsUnmasked => write!(
+                f,
+                "Errors are not masked, they include the original error behind them"
+            ),
+            Error::IdeEnabled(name) => write!(f, "{name} is served from the endpoint"),
+            Error::CsrfPreventionDisabled => write!(
+                f,
+                "CSRF prevention is disabled, a simple GET query was answered"
+            ),
+            Error::CostAnalysisDisabled => write!(f, "Cost analysis is disabled"),
+            Error::NoDepthLimit(depth) => write!(
+                f,
+                "No query depth limit, a query nested {depth} levels deep was answered"
+            ),
+            Error::HasuraConsoleEnabled => write!(f, "Hasura console is enabled"),
+            Error::HasuraAdminSecretNotRequired => write!(
+                f,
+                "Hasura has no admin secret, so every request is made as the admin role"
+            ),
+            Error::HasuraDevMode => write!(
+                f,
+                "Hasura is in dev mode, errors include internal details"
+            ),
+            Error::HasuraMetadataApiExposed => {
+                write!(f, "Hasura metadata API is enabled on the public endpoint")
+            }
+            Error::LintViolations(violations) => {
+                write!(f, "The schema has style problems: {}", violations.iter().join("; "))
+            }
+            Error::BreakingChanges(changes) => write!(
+                f,
+                "The schema has breaking changes: {}",
+                changes.iter().join("; ")
+            ),
+        }
+    }
+}
+
+/// How the error of [`Outcome::Failed`] is serialized: the same fields as a [`Failure`] in a saved
+/// report, plus the error itself in `details` so it can be read back.
+mod failed {
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    use crate::report::Failure;
+    use crate::Error;
+
+    #[derive(Serialize)]
+    struct Failed<'a> {
+        #[serde(flatten)]
+        failure: Failure,
+        details: &'a Error,
+    }
+
+    #[derive(Deserialize)]
+    struct Details {
+        details: Error,
+    }
+
+    pub(crate) fn serialize<S: Serializer>(
+        error: &Error,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        Failed {
+            failure: Failure::from(error),
+            details: error,
+        }
+        .serialize(serializer)
+    }
+
+    pub(crate) fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Error, D::Error> {
+        Details::deserialize(deserializer).map(|failed| failed.details)
+    }
+}
+
+fn serialize_millis<S: Serializer>(duration: &Duration, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_u128(duration.as_millis())
+}
+
+fn deserialize_millis<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Duration, D::Error> {
+    u64::deserialize(deserializer).map(Duration::from_millis)
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::CouldNotConnect(source)
+            | Error::DnsFailed(source)
+            | Error::TlsFailed(source)
+            | Error::ConnectionRefused(source) => Some(source),
+            _ => None,
+        }
+    }
+}
+
+impl Error {
+    /// A [`Error::Timeout`] which no check has claimed yet.
+    pub(crate) const fn timed_out(elapsed: Duration, budget: Duration) -> Self {
+        Error::Timeout {
+            check: None,
+            elapsed,
+            budget,
+        }
+    }
+
+    /// What went wrong with a response which should have been GraphQL, but wasn't.
+    pub(crate) fn not_graphql(response: &HttpResponse) -> Self {
+        Error::NotGraphQL {
+            content_type: response.content_type().to_string(),
+            excerpt: response.excerpt(),
+        }
+    }
+}
+
+fn basic_query(context: &Context, url: &str, auth: &Auth) -> Result<(), Error> {
+    let request = make_request(context, url, auth)?;
+    let response = context.send(
+        request,
+        Some(&json!({
+            "query": "query{__typename}",
+        })),
+    )?;
+    let body = read_json(context, &response)?;
+    if let Some(Value::String(_)) = body.pointer("/data/__typename") {
+        Ok(())
     } else {
-        true
+        Err(Error::not_graphql(&response))
+    }
+}
+
+/// What a single combined probe found out, `None` for anything it couldn't tell.
+#[derive(Clone, Debug, Default)]
+struct Probed {
+    is_subgraph: Option<bool>,
+    sdl: Option<String>,
+    introspection_enabled: Option<bool>,
+}
+
+/// Asks for `__typename`, `_service` and (if `introspection`) `__schema` in one request, working out
+/// which of them the server answers from the data and any partial errors.
+///
+/// Returns `None` if the server didn't answer `__typename`, e.g., because it rejected the whole
+/// document over one unknown field, in which case every probe has to be sent separately.
+fn combined_probe(
+    context: &Context,
+    url: &str,
+    auth: &Auth,
+    introspection: bool,
+) -> Option<Probed> {
+    let query = if introspection {
+        "query{__typename _service{sdl} __schema{queryType{name}}}"
+    } else {
+        "query{__typename _service{sdl}}"
     };
+    let request = make_request(context, url, auth).ok()?;
+    let response = context
+        .send(request, Some(&json!({ "query": query })))
+        .ok()?;
+    if response.status >= 400 {
+        return None;
+    }
+    let body: Value = serde_json::from_str(&response.body).ok()?;
+    let data = body.get("data")?;
+    data.get("__typename")?.as_str()?;
+    let errors = body
+        .get("errors")
+        .map(GraphQLError::parse_all)
+        .unwrap_or_default();
+    let answered = |field: &str| {
+        if data.get(field).is_some_and(|value| !value.is_null()) {
+            Some(true)
+        } else if errors
+            .iter()
+            .any(|err| err.path.first().and_then(Value::as_str) == Some(field))
+        {
+            Some(false)
+        } else {
+            None
+        }
+    };
+    Some(Probed {
+        is_subgraph: answered("_service"),
+        sdl: data
+            .pointer("/_service/sdl")
+            .and_then(Value::as_str)
+            .map(String::from),
+        introspection_enabled: if introspection {
+            answered("__schema")
+        } else {
+            None
+        },
+    })
+}
+
+fn post_json(context: &Context, url: &str, auth: &Auth, body: Value) -> Result<Value, Error> {
+    get_json(
+        context,
+        context.send(make_request(context, url, auth)?, Some(&body)),
+    )
+}
+
+fn make_request(context: &Context, url: &str, auth: &Auth) -> Result<HttpRequest, Error> {
+    authenticate(context, context.post(url), auth)
+}
+
+/// Adds the header of `auth` to `request`. A header from a [`TokenProvider`] is redacted from then
+/// on, since it isn't known ahead of time.
+fn authenticate(
+    context: &Context,
+    request: HttpRequest,
+    auth: &Auth,
+) -> Result<HttpRequest, Error> {
+    let header = match auth {
+        Auth::Enabled { header } => header.to_string(),
+        Auth::Dynamic(provider) => {
+            let header = provider.header().map_err(Error::TokenUnavailable)?;
+            context.add_secret(&header);
+            header
+        }
+        Auth::Disabled => return Ok(request),
+    };
+    let (header_name, header_value) = split_header(&header)?;
+    Ok(request.set(header_name, header_value))
+}
+
+/// Splits a `name: value` header, checking that it can actually be sent.
+fn split_header(header: &str) -> Result<(&str, &str), Error> {
+    let (name, value) = header.split_once(':').ok_or(Error::BadHeader)?;
+    let valid_name = !name.is_empty()
+        && name
+            .bytes()
+            .all(|byte| byte.is_ascii_alphanumeric() || b"!#$%&'*+-.^_`|~".contains(&byte));
+    if !valid_name || value.contains(['\r', '\n']) {
+        return Err(Error::BadHeader);
+    }
+    Ok((name, value.trim()))
+}
+
+fn get_json(context: &Context, response: Result<HttpResponse, Error>) -> Result<Value, Error> {
+    read_json(context, &response?)
+}
+
+/// The body of a GraphQL response, failing on `errors` unless the
+/// [`ErrorsPolicy`](graphql::ErrorsPolicy) of `context` tolerates them.
+fn read_json(context: &Context, res: &HttpResponse) -> Result<Value, Error> {
+    if res.status >= 400 {
+        return Err(Error::BadStatus {
+            status: res.status,
+            excerpt: res.excerpt(),
+        });
+    }
+    let body: Value = serde_json::from_str(&res.body).map_err(|_| Error::not_graphql(res))?;
+    if let Some(obj) = body.get("errors") {
+        let errors = GraphQLError::parse_all(obj);
+        let data = body.get("data").unwrap_or(&Value::Null);
+        if !context
+            .errors_policy
+            .tolerates(data, &errors, &context.allowed_errors)
+        {
+            return Err(Error::GraphQLError(errors));
+        }
+    }
+    Ok(body)
+}
+
+#[cfg(test)]
+mod test_error {
+    use std::error::Error as _;
+
+    use super::*;
+
+    #[test]
+    fn transport_source() {
+        let err =
+            basic_query(&Context::default(), "http://127.0.0.1:1", &Auth::Disabled).unwrap_err();
+        assert!(matches!(err, Error::ConnectionRefused(_)));
+        assert!(err
+            .source()
+            .is_some_and(|source| !source.to_string().is_empty()));
+    }
 
-    if is_subgraph && !auth.is_enabled() && subgraph.security_required() {
-        errors.push(Error::InsecureSubgraph)
+    #[test]
+    fn no_source() {
+        assert!(
+            Error::timed_out(Duration::from_secs(1), Duration::from_secs(1))
+                .source()
+                .is_none()
+        );
     }
+}
 
-    if let Introspection::Disallow = introspection {
-        if let Err(e) = require_introspection_disabled(url, auth) {
-            errors.push(e);
+#[cfg(test)]
+mod test_run_each_check {
+    use super::*;
+    use crate::testing::{query, MockTransport};
+
+    /// A server which requires auth, isn't a subgraph and has introspection enabled.
+    fn server(request: &HttpRequest) -> Result<HttpResponse, Error> {
+        if request.header("Authorization").is_none() {
+            return Ok(HttpResponse::new(401, "Unauthorized"));
         }
+        Ok(match query(request).unwrap_or_default() {
+            "query{__typename}" => {
+                HttpResponse::json(200, &json!({"data": {"__typename": "Query"}}))
+            }
+            "query{__schema{types{name}}}" => {
+                HttpResponse::json(200, &json!({"data": {"__schema": {"types": []}}}))
+            }
+            _ => HttpResponse::json(200, &json!({"errors": [{"message": "Unknown field"}]})),
+        })
     }
 
-    if errors.is_empty() {
-        Ok(())
-    } else {
-        Err(errors)
+    fn outcomes(checker: Checker) -> Vec<Outcome> {
+        checker
+            .transport(MockTransport::new(server))
+            .run()
+            .into_iter()
+            .map(|result| result.outcome)
+            .collect()
     }
-}
 
-#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
-pub enum Auth<'a> {
-    Enabled { header: &'a str },
-    Disabled,
-}
+    #[test]
+    fn with_auth() {
+        let checker = Checker::new("https://example.com/graphql").auth(Auth::Enabled {
+            header: "Authorization: Bearer token".into(),
+        });
+        assert_eq!(
+            outcomes(checker),
+            [
+                Outcome::Passed,
+                Outcome::Passed,
+                Outcome::Skipped,
+                Outcome::Skipped,
+                Outcome::Failed(Error::IntrospectionEnabled),
+                Outcome::Skipped,
+            ]
+        );
+    }
 
-impl Auth<'_> {
-    const fn is_enabled(&self) -> bool {
-        matches!(self, Auth::Enabled { .. })
+    #[test]
+    fn without_auth() {
+        let checker = Checker::new("https://example.com/graphql").allow_introspection();
+        assert!(matches!(
+            outcomes(checker)[0],
+            Outcome::Failed(Error::BadStatus { status: 401, .. })
+        ));
     }
-}
 
-#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
-pub enum Subgraph {
-    Secure,
-    Insecure,
-    NotASubgraph,
-}
+    #[test]
+    fn required_subgraph() {
+        let checker = Checker::new("https://example.com/graphql")
+            .auth(Auth::Enabled {
+                header: "Authorization: Bearer token".into(),
+            })
+            .require_subgraph();
+        assert_eq!(outcomes(checker)[2], Outcome::Failed(Error::NotASubgraph));
+    }
 
-impl Subgraph {
-    const fn required(&self) -> bool {
-        matches!(self, Subgraph::Secure | Subgraph::Insecure)
+    #[test]
+    fn subgraph_answers_in_one_request() {
+        let transport = MockTransport::new(|request| {
+            Ok(match query(request).unwrap_or_default() {
+                "query{__typename _service{sdl}}" => HttpResponse::json(
+                    200,
+                    &json!({"data": {"__typename": "Query", "_service": {"sdl": ""}}}),
+                ),
+                _ => HttpResponse::json(200, &json!({"errors": [{"message": "Unknown field"}]})),
+            })
+        });
+        let capture = Capture::default();
+        let results = Checker::new("https://example.com/graphql")
+            .subgraph(Subgraph::Insecure)
+            .allow_introspection()
+            .transport(transport)
+            .capture(&capture)
+            .run();
+        assert!(results.iter().all(|result| result.error().is_none()));
+        assert_eq!(capture.exchanges().len(), 1);
     }
 
-    const fn security_required(&self) -> bool {
-        matches!(self, Subgraph::Secure | Subgraph::NotASubgraph)
+    #[test]
+    fn combined_probes_use_partial_errors() {
+        let transport = MockTransport::new(|request| {
+            Ok(match query(request).unwrap_or_default() {
+                "query{__typename _service{sdl} __schema{queryType{name}}}" => HttpResponse::json(
+                    200,
+                    &json!({
+                        "data": {"__typename": "Query", "_service": null, "__schema": null},
+                        "errors": [
+                            {"message": "Introspection is disabled", "path": ["__schema"]}
+                        ]
+                    }),
+                ),
+                _ => HttpResponse::json(200, &json!({"errors": [{"message": "Unknown field"}]})),
+            })
+        });
+        let capture = Capture::default();
+        let report = Checker::new("https://example.com/graphql")
+            .combine_probes()
+            .transport(transport)
+            .capture(&capture)
+            .report();
+        assert!(report.passed());
+        assert_eq!(report.introspection_enabled, Some(false));
+        // `_service` wasn't answered either way, so it's asked separately
+        assert_eq!(report.is_subgraph, Some(false));
+        assert_eq!(capture.exchanges().len(), 2);
     }
 }
 
-#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
-pub enum Introspection {
-    Allow,
-    Disallow,
-}
+#[cfg(test)]
+mod test_serde {
+    use super::*;
 
-#[derive(Clone, Debug, Eq, Hash, PartialEq)]
-pub enum Error {
-    BadUri,
-    BadStatus(u16),
-    CouldNotConnect,
-    NotGraphQL,
-    GraphQLError(String),
-    AuthNotEnforced,
-    BadHeader,
-    NotASubgraph,
-    BadBoolean(&'static str),
-    IntrospectionEnabled,
-    InsecureSubgraph,
-}
+    #[test]
+    fn auth_is_redacted() {
+        let auth = Auth::Enabled {
+            header: "Authorization: Bearer hunter2".into(),
+        };
+        assert_eq!(
+            serde_json::to_value(&auth).unwrap(),
+            json!({"enabled": {"header": "Authorization: [REDACTED]"}})
+        );
+        assert_eq!(serde_json::to_value(Auth::Disabled).unwrap(), "disabled");
+        assert_eq!(
+            format!("{auth:?}"),
+            r#"Enabled { header: "Authorization: [REDACTED]" }"#
+        );
+    }
 
-impl Display for Error {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        match self {
-            Error::BadUri => write!(f, "Bad URI"),
-            Error::CouldNotConnect => write!(f, "Could not connect"),
-            Error::NotGraphQL => write!(f, "Not GraphQL"),
-            Error::GraphQLError(err) => write!(f, "Received error from GraphQL server: {err}"),
-            Error::AuthNotEnforced => {
-                write!(f, "Able to make queries with no authentication header")
+    #[test]
+    fn auth_from_config() {
+        let auth: Auth =
+            serde_json::from_str(r#"{"enabled": {"header": "Authorization: Bearer hunter2"}}"#)
+                .unwrap();
+        assert_eq!(
+            auth,
+            Auth::Enabled {
+                header: "Authorization: Bearer hunter2".into()
             }
-            Error::BadHeader => write!(
-                f,
-                "Provided `auth` input was not a valid header in the format of `name: value`"
-            ),
-            Error::BadStatus(status) => write!(f, "Got status code: {status}"),
-            Error::NotASubgraph => write!(f, "GraphQL endpoint is not a subgraph"),
-            Error::IntrospectionEnabled => write!(
-                f,
-                "Introspection is enabled for the GraphQL server but not allowed"
-            ),
-            Error::BadBoolean(name) => write!(f, "Input `{name}` can only be `true` or `false`"),
-            Error::InsecureSubgraph => write!(f, "Subgraph is not protected by authentication"),
-        }
+        );
+        let escaped: Auth =
+            serde_json::from_str(r#"{"enabled": {"header": "Authorization: Bearer \"hunter2\""}}"#)
+                .unwrap();
+        assert_eq!(
+            escaped,
+            Auth::Enabled {
+                header: r#"Authorization: Bearer "hunter2""#.into()
+            }
+        );
     }
-}
 
-fn basic_query(url: &str, auth: Auth) -> Result<(), Error> {
-    let response = make_request(url, auth)?.send_json(json!({
-        "query": "query{__typename}",
-    }));
-    let body = get_json(response)?;
-    if let Some(Value::String(_)) = body.pointer("/data/__typename") {
-        Ok(())
-    } else {
-        Err(Error::NotGraphQL)
+    #[test]
+    fn check_result() {
+        let result = CheckResult::new(
+            Check::IntrospectionDisabled,
+            Outcome::Failed(Error::IntrospectionEnabled),
+        );
+        assert_eq!(
+            serde_json::to_value(result).unwrap(),
+            json!({
+                "check": "introspection_disabled",
+                "outcome": {
+                    "status": "failed",
+                    "error": {
+                        "code": "introspection_enabled",
+                        "message": "Introspection is enabled for the GraphQL server but not allowed",
+                        "severity": "error",
+                        "details": "introspection_enabled",
+                    },
+                },
+                "duration_ms": 0,
+                "severity": "error",
+            })
+        );
     }
-}
 
-fn make_request(url: &str, auth: Auth) -> Result<Request, Error> {
-    let request = ureq::post(url);
-    if let Auth::Enabled { header } = auth {
-        let (header_name, header_value) = header.split_once(':').ok_or(Error::BadHeader)?;
-        let header_value = header_value.trim();
-        Ok(request.set(header_name, header_value))
-    } else {
-        Ok(request)
+    #[test]
+    fn report_round_trip() {
+        let report = CheckReport {
+            checks: vec![
+                CheckResult::new(Check::BasicQuery, Outcome::Passed),
+                CheckResult::new(
+                    Check::Subgraph,
+                    Outcome::Failed(Error::timed_out(
+                        Duration::from_millis(5003),
+                        Duration::from_secs(5),
+                    )),
+                ),
+                CheckResult::new(
+                    Check::IntrospectionDisabled,
+                    Outcome::Failed(Error::GraphQLError(vec![GraphQLError::new("Not allowed")])),
+                ),
+            ],
+            duration: Duration::from_millis(5100),
+            is_subgraph: Some(false),
+            ..CheckReport::default()
+        };
+        let json = serde_json::to_string(&report).unwrap();
+        assert_eq!(serde_json::from_str::<CheckReport>(&json).unwrap(), report);
     }
-}
 
-fn get_json(response: Result<Response, ureq::Error>) -> Result<Value, Error> {
-    let res = response.map_err(|err| match err {
-        ureq::Error::Status(status, _) => Error::BadStatus(status),
-        ureq::Error::Transport(t) => match t.kind() {
-            ureq::ErrorKind::InvalidUrl | ureq::ErrorKind::UnknownScheme => Error::BadUri,
-            _ => Error::CouldNotConnect,
-        },
-    })?;
-    let body: Value = res.into_json().or(Err(Error::NotGraphQL))?;
-    if let Some(obj) = body.get("errors") {
-        Err(Error::GraphQLError(obj.to_string()))
-    } else {
-        Ok(body)
+    #[test]
+    fn config_round_trip() {
+        for subgraph in [Subgraph::Secure, Subgraph::Insecure, Subgraph::NotASubgraph] {
+            let json = serde_json::to_string(&subgraph).unwrap();
+            assert_eq!(serde_json::from_str::<Subgraph>(&json).unwrap(), subgraph);
+        }
+        assert_eq!(
+            serde_json::to_value(Introspection::Disallow).unwrap(),
+            "disallow"
+        );
     }
 }
 
 #[cfg(test)]
 mod test_utils {
+    use std::borrow::Cow;
+
+    use crate::fixture::Fixture;
     use crate::Auth;
-    use const_format::formatcp;
 
-    pub const BASE_URL: &str = "https://graphql-test.up.railway.app";
-    const TOKEN: &str = env!("GRAPHQL_TOKEN");
+    pub const TOKEN_HEADER: &str = "Authorization: Bearer token";
     pub const AUTH: Auth<'static> = Auth::Enabled {
-        header: formatcp!("Authorization: Bearer {}", TOKEN),
+        header: Cow::Borrowed(TOKEN_HEADER),
     };
+
+    /// Serves `fixture` for the rest of the tests, returning its URL.
+    pub fn serve(fixture: Fixture) -> String {
+        fixture.start().expect("fixture server didn't start").url()
+    }
+
+    /// A fixture which requires [`AUTH`].
+    pub fn authenticated(fixture: Fixture) -> Fixture {
+        Fixture {
+            auth: Some(TOKEN_HEADER.to_string()),
+            ..fixture
+        }
+    }
+
+    pub fn subgraph() -> Fixture {
+        Fixture {
+            subgraph: true,
+            ..Fixture::default()
+        }
+    }
 }
 
 #[cfg(test)]
 mod test_basic_query {
+    use crate::fixture::Fixture;
+    use crate::testing::context;
     use crate::Error::*;
 
     use super::test_utils::*;
     use super::*;
 
+    fn server(response: HttpResponse) -> Context {
+        context(move |_| Ok(response.clone()))
+    }
+
     #[test]
     fn unauth_success() {
-        let url = format!("{BASE_URL}/graphql");
-        assert!(basic_query(&url, Auth::Disabled).is_ok());
+        let url = serve(Fixture::default());
+        assert!(basic_query(&Context::default(), &url, &Auth::Disabled).is_ok());
     }
 
     #[test]
     fn success_subgraph() {
-        let url = format!("{BASE_URL}/subgraph");
-        assert!(basic_query(&url, Auth::Disabled).is_ok());
+        let url = serve(subgraph());
+        assert!(basic_query(&Context::default(), &url, &Auth::Disabled).is_ok());
     }
 
     #[test]
     fn bad_url() {
-        let url = BASE_URL.to_string();
-        let url_without_scheme = url.split('/').nth(2).unwrap().to_string();
         assert_eq!(
-            basic_query(&url_without_scheme, Auth::Disabled),
-            Err(BadUri)
+            basic_query(&Context::default(), "example.com/graphql", &Auth::Disabled),
+            Err(BadUri(UriProblem::MissingScheme))
         );
     }
 
     #[test]
     fn not_found() {
-        let url = "https://doesntexist.dylananthony.com";
-        assert_eq!(basic_query(url, Auth::Disabled), Err(CouldNotConnect));
+        let url = "http://127.0.0.1:1/graphql";
+        assert!(matches!(
+            basic_query(&Context::default(), url, &Auth::Disabled),
+            Err(ConnectionRefused(_))
+        ));
+    }
+
+    #[test]
+    fn tls_failed() {
+        let url = serve(Fixture::default()).replace("http://", "https://");
+        assert!(matches!(
+            basic_query(&Context::default(), &url, &Auth::Disabled),
+            Err(TlsFailed(_))
+        ));
     }
 
     #[test]
     fn post_not_accepted() {
-        let url = format!("{BASE_URL}/no-post");
-        assert_eq!(basic_query(&url, Auth::Disabled), Err(BadStatus(405)));
+        let context = server(HttpResponse::new(405, "Method Not Allowed"));
+        assert!(matches!(
+            basic_query(&context, "https://example.com/graphql", &Auth::Disabled),
+            Err(BadStatus { status: 405, .. })
+        ));
     }
 
     #[test]
     fn no_json_returned() {
-        let url = format!("{BASE_URL}/no-json");
-        assert_eq!(basic_query(&url, Auth::Disabled), Err(NotGraphQL));
+        let context = server(
+            HttpResponse::new(200, "<html>\n  <title>Sign in</title>\n</html>")
+                .with_header("Content-Type", "text/html; charset=utf-8"),
+        );
+        let err =
+            basic_query(&context, "https://example.com/graphql", &Auth::Disabled).unwrap_err();
+        assert_eq!(
+            err,
+            NotGraphQL {
+                content_type: "text/html".to_string(),
+                excerpt: "<html> <title>Sign in</title> </html>".to_string(),
+            }
+        );
+        assert_eq!(
+            err.to_string(),
+            "Not GraphQL, got text/html response: <html> <title>Sign in</title> </html>"
+        );
     }
 
     #[test]
     fn not_graphql() {
-        let url = format!("{BASE_URL}/json");
-        assert_eq!(basic_query(&url, Auth::Disabled), Err(NotGraphQL));
+        let context = server(HttpResponse::json(200, &json!({"hello": "world"})));
+        assert_eq!(
+            basic_query(&context, "https://example.com/graphql", &Auth::Disabled),
+            Err(NotGraphQL {
+                content_type: "application/json".to_string(),
+                excerpt: r#"{"hello":"world"}"#.to_string(),
+            })
+        );
+    }
+
+    #[test]
+    fn partial_data() {
+        let response = HttpResponse::json(
+            200,
+            &json!({
+                "data": {"__typename": "Query"},
+                "errors": [{"message": "Deprecated"}],
+            }),
+        );
+        let url = "https://example.com/graphql";
+        assert!(matches!(
+            basic_query(&server(response.clone()), url, &Auth::Disabled),
+            Err(GraphQLError(_))
+        ));
+        let context = Context {
+            errors_policy: graphql::ErrorsPolicy::IgnoreIfData,
+            ..server(response)
+        };
+        assert_eq!(basic_query(&context, url, &Auth::Disabled), Ok(()));
     }
 
     #[test]
     fn auth_success() {
-        let url = format!("{BASE_URL}/graphql-auth");
-        assert_eq!(basic_query(&url, AUTH), Ok(()));
+        let url = serve(authenticated(Fixture::default()));
+        assert_eq!(basic_query(&Context::default(), &url, &AUTH), Ok(()));
     }
 
     #[test]
     fn subgraph_auth_success() {
-        let url = format!("{BASE_URL}/subgraph-auth");
-        assert!(basic_query(&url, AUTH).is_ok());
+        let url = serve(authenticated(subgraph()));
+        assert!(basic_query(&Context::default(), &url, &AUTH).is_ok());
     }
 
     #[test]
     fn auth_failure() {
-        let url = format!("{BASE_URL}/graphql-auth");
+        let url = serve(authenticated(Fixture::default()));
         assert!(matches!(
             basic_query(
+                &Context::default(),
                 &url,
-                Auth::Enabled {
-                    header: "Authorization: Bearer nottherealtoken"
+                &Auth::Enabled {
+                    header: "Authorization: Bearer nottherealtoken".into()
                 }
             ),
-            Err(GraphQLError(_))
+            Err(BadStatus { status: 401, .. })
         ));
     }
 
     #[test]
     fn missing_auth() {
-        let url = format!("{BASE_URL}/graphql-auth");
-        match basic_query(&url, Auth::Disabled) {
-            Err(BadStatus(400)) => (),
-            other => panic!("Expected Err(GraphQLError(_)), got {:?}", other),
+        let url = serve(authenticated(Fixture::default()));
+        match basic_query(&Context::default(), &url, &Auth::Disabled) {
+            Err(BadStatus { status: 401, .. }) => (),
+            other => panic!(
+                "Expected Err(BadStatus {{ status: 401, .. }}), got {:?}",
+                other
+            ),
         }
     }
 }
 
-fn check_subgraph(url: &str, auth: Auth) -> Result<(), Error> {
-    let response = make_request(url, auth)?.send_json(json!({
-        "query": "query{_service{sdl}}"
-    }));
-    if get_json(response).is_ok() {
-        Ok(())
+/// Sends the basic query with an `invalid` header, which the server should reject the same as no
+/// header at all. Catches servers which only check that the header is there.
+fn check_invalid_auth_rejected(
+    context: &Context,
+    url: &str,
+    invalid: &Auth,
+    expected: Option<&Rejection>,
+) -> Outcome {
+    Rejection::check(
+        expected,
+        basic_query(context, url, invalid),
+        Error::InvalidAuthAccepted,
+    )
+}
+
+#[cfg(test)]
+mod test_token_provider {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::{Arc, Mutex};
+
+    use super::*;
+    use crate::testing::MockTransport;
+
+    static ISSUED: AtomicUsize = AtomicUsize::new(0);
+
+    /// A new token for every request, like a provider which refreshes them.
+    fn rotating() -> Result<String, String> {
+        let token = ISSUED.fetch_add(1, Ordering::SeqCst);
+        Ok(format!("Authorization: Bearer token-{token}"))
+    }
+
+    fn unavailable() -> Result<String, String> {
+        Err("the vault is sealed".to_string())
+    }
+
+    #[test]
+    fn fetched_for_every_request() {
+        let seen = Arc::new(Mutex::new(Vec::new()));
+        let headers = Arc::clone(&seen);
+        Checker::new("https://example.com/graphql")
+            .auth(Auth::Dynamic(Arc::new(rotating)))
+            .transport(MockTransport::new(move |request| {
+                headers
+                    .lock()
+                    .unwrap()
+                    .push(request.header("Authorization").map(String::from));
+                Ok(HttpResponse::new(401, "Unauthorized"))
+            }))
+            .run();
+        let seen = seen.lock().unwrap();
+        let tokens: Vec<_> = seen.iter().flatten().collect();
+        assert!(tokens.len() > 1);
+        assert!(tokens.iter().all_unique());
+        // The unauthenticated probe for `AuthEnforced` doesn't ask the provider.
+        assert!(seen.contains(&None));
+    }
+
+    #[test]
+    fn unavailable_token() {
+        let results = Checker::new("https://example.com/graphql")
+            .auth(Auth::Dynamic(Arc::new(unavailable)))
+            .transport(MockTransport::new(|_| {
+                Ok(HttpResponse::new(401, "Unauthorized"))
+            }))
+            .run();
+        assert_eq!(
+            results[0].outcome,
+            Outcome::Failed(Error::TokenUnavailable("the vault is sealed".to_string()))
+        );
+        assert_eq!(
+            format!("{:?}", Auth::Dynamic(Arc::new(unavailable))),
+            "Dynamic"
+        );
+    }
+
+    #[test]
+    fn same_provider() {
+        let provider: Arc<dyn TokenProvider> = Arc::new(unavailable);
+        assert_eq!(
+            Auth::Dynamic(Arc::clone(&provider)),
+            Auth::Dynamic(provider)
+        );
+        assert_ne!(
+            Auth::Dynamic(Arc::new(unavailable)),
+            Auth::Dynamic(Arc::new(unavailable))
+        );
+    }
+
+    #[test]
+    fn echoed_token_is_redacted() {
+        let results = Checker::new("https://example.com/graphql")
+            .auth(Auth::Dynamic(Arc::new(|| {
+                Ok("Authorization: short-lived-token".to_string())
+            })))
+            .transport(MockTransport::new(|request| {
+                let token = request.header("Authorization").unwrap_or_default();
+                Ok(HttpResponse::new(
+                    200,
+                    format!(r#"{{"errors":[{{"message":"{token} has expired"}}]}}"#),
+                ))
+            }))
+            .run();
+        let failures: Vec<String> = results
+            .iter()
+            .filter_map(|result| match &result.outcome {
+                Outcome::Failed(err) => Some(err.to_string()),
+                Outcome::Passed | Outcome::Skipped => None,
+            })
+            .collect();
+        assert!(!failures.is_empty());
+        assert!(failures
+            .iter()
+            .all(|err| !err.contains("short-lived-token")));
+    }
+}
+
+#[cfg(test)]
+mod test_check_invalid_auth_rejected {
+    use super::*;
+    use crate::testing::context;
+
+    const INVALID: Auth = Auth::Enabled {
+        header: Cow::Borrowed("Authorization: Bearer expired"),
+    };
+
+    fn server(status: u16) -> Context {
+        context(move |_| {
+            Ok(HttpResponse::json(
+                status,
+                &json!({"data": {"__typename": "Query"}}),
+            ))
+        })
+    }
+
+    #[test]
+    fn rejected() {
+        assert_eq!(
+            check_invalid_auth_rejected(
+                &server(401),
+                "https://example.com/graphql",
+                &INVALID,
+                None
+            ),
+            Outcome::Passed
+        );
+    }
+
+    #[test]
+    fn accepted() {
+        assert_eq!(
+            check_invalid_auth_rejected(
+                &server(200),
+                "https://example.com/graphql",
+                &INVALID,
+                None
+            ),
+            Outcome::Failed(Error::InvalidAuthAccepted)
+        );
+    }
+}
+
+/// Checks that a request without credentials is rejected as unauthenticated (`401` or
+/// `UNAUTHENTICATED`), while `privileged_query` sent with `unprivileged` credentials is rejected as
+/// unauthorized (`403` or `FORBIDDEN`).
+fn check_auth_semantics(
+    context: &Context,
+    url: &str,
+    unprivileged: &Auth,
+    privileged_query: &str,
+) -> Outcome {
+    let expectations = [
+        (
+            "without credentials",
+            basic_query(context, url, &Auth::Disabled),
+            401,
+            ErrorCode::Unauthenticated,
+        ),
+        (
+            "with unprivileged credentials",
+            post_json(
+                context,
+                url,
+                unprivileged,
+                json!({ "query": privileged_query }),
+            )
+            .map(|_| ()),
+            403,
+            ErrorCode::Forbidden,
+        ),
+    ];
+    let mut problems = Vec::new();
+    for (credentials, result, status, code) in expectations {
+        let got = match result {
+            Ok(()) => "a successful response".to_string(),
+            Err(err) => {
+                let expected = [
+                    Rejection::Status(status),
+                    Rejection::ErrorCode(code.as_str().to_string()),
+                ];
+                if expected.iter().any(|expected| expected.matches(&err)) {
+                    continue;
+                }
+                match describe_rejection(&err) {
+                    Some(got) => got,
+                    None => return Outcome::Failed(err),
+                }
+            }
+        };
+        problems.push(format!(
+            "{credentials} expected status {status} or error code {code}, got {got}"
+        ));
+    }
+    if problems.is_empty() {
+        Outcome::Passed
+    } else {
+        Outcome::Failed(Error::AuthSemantics(problems))
+    }
+}
+
+#[cfg(test)]
+mod test_check_auth_semantics {
+    use super::*;
+    use crate::testing::context;
+
+    const UNPRIVILEGED: Auth = Auth::Enabled {
+        header: Cow::Borrowed("Authorization: Bearer reader"),
+    };
+
+    fn server(unauthenticated: u16, forbidden: u16) -> Context {
+        context(move |request| {
+            let status = if request.headers.is_empty() {
+                unauthenticated
+            } else {
+                forbidden
+            };
+            Ok(HttpResponse::json(status, &json!({"data": null})))
+        })
+    }
+
+    fn check(context: &Context) -> Outcome {
+        check_auth_semantics(
+            context,
+            "https://example.com/graphql",
+            &UNPRIVILEGED,
+            "mutation{deleteEverything}",
+        )
+    }
+
+    #[test]
+    fn told_apart() {
+        assert_eq!(check(&server(401, 403)), Outcome::Passed);
+    }
+
+    #[test]
+    fn not_told_apart() {
+        assert_eq!(
+            check(&server(403, 200)),
+            Outcome::Failed(Error::AuthSemantics(vec![
+                "without credentials expected status 401 or error code UNAUTHENTICATED, got status 403"
+                    .to_string(),
+                "with unprivileged credentials expected status 403 or error code FORBIDDEN, got a successful response"
+                    .to_string(),
+            ]))
+        );
+    }
+}
+
+/// Asks the health check of an Apollo Router (e.g., `http://localhost:8088/health`) whether the
+/// router is live and then whether it's ready. A healthy router in front of a failing endpoint
+/// points at a subgraph, which is reported as a warning.
+fn check_router_health(context: &Context, health_url: &str, basic_query_failed: bool) -> Outcome {
+    let separator = if health_url.contains('?') { '&' } else { '?' };
+    for probe in ["live", "ready"] {
+        let request = HttpRequest::new("GET", &format!("{health_url}{separator}{probe}"));
+        match context.send(request, None) {
+            Ok(response) if response.status == 200 => {}
+            Ok(response) => {
+                return Outcome::Failed(Error::RouterUnhealthy {
+                    probe: probe.to_string(),
+                    status: response.status,
+                })
+            }
+            Err(err) => return Outcome::Failed(err),
+        }
+    }
+    if basic_query_failed {
+        Outcome::Failed(Error::SubgraphProblem)
     } else {
-        Err(Error::NotASubgraph)
+        Outcome::Passed
+    }
+}
+
+/// Returns the subgraph's SDL if it answered `_service`.
+fn check_subgraph(context: &Context, url: &str, auth: &Auth) -> Result<String, Error> {
+    let response = post_json(
+        context,
+        url,
+        auth,
+        json!({
+            "query": "query{_service{sdl}}"
+        }),
+    );
+    match response {
+        Ok(body) => Ok(body
+            .pointer("/data/_service/sdl")
+            .and_then(Value::as_str)
+            .unwrap_or_default()
+            .to_string()),
+        Err(err) if err == Error::Cancelled || ErrorKind::of(&err).is_some() => Err(err),
+        Err(_) => Err(Error::NotASubgraph),
+    }
+}
+
+#[cfg(test)]
+mod test_check_router_health {
+    use super::*;
+    use crate::testing::context;
+
+    fn router(live: u16, ready: u16) -> Context {
+        context(move |request| {
+            Ok(HttpResponse::new(
+                if request.url.ends_with("?live") {
+                    live
+                } else {
+                    ready
+                },
+                "",
+            ))
+        })
+    }
+
+    const HEALTH_URL: &str = "http://localhost:8088/health";
+
+    #[test]
+    fn healthy() {
+        assert_eq!(
+            check_router_health(&router(200, 200), HEALTH_URL, false),
+            Outcome::Passed
+        );
+    }
+
+    #[test]
+    fn not_ready() {
+        assert_eq!(
+            check_router_health(&router(200, 503), HEALTH_URL, true),
+            Outcome::Failed(Error::RouterUnhealthy {
+                probe: "ready".to_string(),
+                status: 503
+            })
+        );
+    }
+
+    #[test]
+    fn subgraph_problem() {
+        assert_eq!(
+            check_router_health(&router(200, 200), HEALTH_URL, true),
+            Outcome::Failed(Error::SubgraphProblem)
+        );
     }
 }
 
 #[cfg(test)]
 mod test_check_subgraph {
+    use crate::fixture::{Fixture, SUBGRAPH_SDL};
     use crate::Error::NotASubgraph;
 
     use super::test_utils::*;
@@ -293,25 +2539,41 @@ mod test_check_subgraph {
 
     #[test]
     fn happy() {
-        let url = format!("{BASE_URL}/subgraph");
-        check_subgraph(&url, Auth::Disabled).unwrap();
+        let url = serve(subgraph());
+        assert_eq!(
+            check_subgraph(&Context::default(), &url, &Auth::Disabled),
+            Ok(SUBGRAPH_SDL.to_string())
+        );
     }
 
     #[test]
     fn happy_with_auth() {
-        let url = format!("{BASE_URL}/subgraph-auth");
-        check_subgraph(&url, AUTH).unwrap();
+        let url = serve(authenticated(subgraph()));
+        check_subgraph(&Context::default(), &url, &AUTH).unwrap();
     }
 
     #[test]
     fn not_a_subgraph() {
-        let url = format!("{BASE_URL}/graphql");
-        assert_eq!(check_subgraph(&url, Auth::Disabled), Err(NotASubgraph));
+        let url = serve(Fixture::default());
+        assert_eq!(
+            check_subgraph(&Context::default(), &url, &Auth::Disabled),
+            Err(NotASubgraph)
+        );
+    }
+
+    #[test]
+    fn unreachable() {
+        let result = check_subgraph(&Context::default(), "http://127.0.0.1:1", &Auth::Disabled);
+        assert!(
+            matches!(result, Err(Error::ConnectionRefused(_))),
+            "{result:?}"
+        );
     }
 }
 
 #[cfg(test)]
 mod test_require_introspection_disabled {
+    use crate::fixture::Fixture;
     use crate::Error::IntrospectionEnabled;
 
     use super::test_utils::*;
@@ -319,25 +2581,33 @@ mod test_require_introspection_disabled {
 
     #[test]
     fn happy() {
-        let url = format!("{BASE_URL}/graphql-no-introspection");
-        require_introspection_disabled(&url, Auth::Disabled).unwrap();
+        let url = serve(Fixture {
+            introspection: false,
+            ..Fixture::default()
+        });
+        require_introspection_disabled(&Context::default(), &url, &Auth::Disabled).unwrap();
     }
 
     #[test]
     fn introspection_enabled() {
-        let url = format!("{BASE_URL}/graphql");
+        let url = serve(Fixture::default());
         assert_eq!(
-            require_introspection_disabled(&url, Auth::Disabled),
+            require_introspection_disabled(&Context::default(), &url, &Auth::Disabled),
             Err(IntrospectionEnabled)
         );
     }
 }
 
-fn require_introspection_disabled(url: &str, auth: Auth) -> Result<(), Error> {
-    let response = make_request(url, auth)?.send_json(json!({
-        "query": "query{__schema{types{name}}}"
-    }));
-    match get_json(response) {
+fn require_introspection_disabled(context: &Context, url: &str, auth: &Auth) -> Result<(), Error> {
+    let response = post_json(
+        context,
+        url,
+        auth,
+        json!({
+            "query": "query{__schema{types{name}}}"
+        }),
+    );
+    match response {
         Ok(value) => {
             if let Some(Object(_)) = value.pointer("/data/__schema") {
                 return Err(Error::IntrospectionEnabled);
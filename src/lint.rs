@@ -0,0 +1,171 @@
+use graphql_parser::schema::{
+    Definition, Directive, Document, EnumValue, Field, InputValue, TypeDefinition, TypeExtension,
+};
+
+/// Finds the usual style problems in a schema: type names which aren't `PascalCase`, fields and
+/// arguments which aren't `camelCase`, enum values which aren't `SCREAMING_SNAKE_CASE`, and
+/// `@deprecated` without a `reason`. Names starting with `_` (like federation's `_service`) are
+/// left alone.
+pub(crate) fn lint(document: &Document<'static, String>) -> Vec<String> {
+    let mut lint = Lint::default();
+    for definition in &document.definitions {
+        match definition {
+            Definition::TypeDefinition(TypeDefinition::Object(ty)) => {
+                lint.type_name(&ty.name);
+                lint.fields(&ty.name, &ty.fields);
+            }
+            Definition::TypeDefinition(TypeDefinition::Interface(ty)) => {
+                lint.type_name(&ty.name);
+                lint.fields(&ty.name, &ty.fields);
+            }
+            Definition::TypeDefinition(TypeDefinition::InputObject(ty)) => {
+                lint.type_name(&ty.name);
+                lint.input_fields(&ty.name, &ty.fields);
+            }
+            Definition::TypeDefinition(TypeDefinition::Enum(ty)) => {
+                lint.type_name(&ty.name);
+                lint.enum_values(&ty.name, &ty.values);
+            }
+            Definition::TypeDefinition(TypeDefinition::Scalar(ty)) => lint.type_name(&ty.name),
+            Definition::TypeDefinition(TypeDefinition::Union(ty)) => lint.type_name(&ty.name),
+            Definition::TypeExtension(TypeExtension::Object(ty)) => {
+                lint.fields(&ty.name, &ty.fields);
+            }
+            Definition::TypeExtension(TypeExtension::Interface(ty)) => {
+                lint.fields(&ty.name, &ty.fields);
+            }
+            Definition::TypeExtension(TypeExtension::InputObject(ty)) => {
+                lint.input_fields(&ty.name, &ty.fields);
+            }
+            Definition::TypeExtension(TypeExtension::Enum(ty)) => {
+                lint.enum_values(&ty.name, &ty.values);
+            }
+            Definition::TypeExtension(_)
+            | Definition::SchemaDefinition(_)
+            | Definition::DirectiveDefinition(_) => {}
+        }
+    }
+    lint.0
+}
+
+#[derive(Default)]
+struct Lint(Vec<String>);
+
+impl Lint {
+    fn type_name(&mut self, name: &str) {
+        if !is_private(name) && !is_pascal_case(name) {
+            self.0.push(format!("type `{name}` should be PascalCase"));
+        }
+    }
+
+    fn fields(&mut self, type_name: &str, fields: &[Field<'static, String>]) {
+        for field in fields {
+            let coordinate = format!("{type_name}.{}", field.name);
+            self.field_name("field", &coordinate, &field.name);
+            self.deprecation(&coordinate, &field.directives);
+            for argument in &field.arguments {
+                let name = &argument.name;
+                self.field_name("argument", &format!("{coordinate}({name}:)"), name);
+            }
+        }
+    }
+
+    fn input_fields(&mut self, type_name: &str, fields: &[InputValue<'static, String>]) {
+        for field in fields {
+            let coordinate = format!("{type_name}.{}", field.name);
+            self.field_name("input field", &coordinate, &field.name);
+            self.deprecation(&coordinate, &field.directives);
+        }
+    }
+
+    fn enum_values(&mut self, type_name: &str, values: &[EnumValue<'static, String>]) {
+        for value in values {
+            let coordinate = format!("{type_name}.{}", value.name);
+            let screaming = value
+                .name
+                .chars()
+                .all(|c| c.is_ascii_uppercase() || c.is_ascii_digit() || c == '_');
+            if !screaming {
+                self.0.push(format!(
+                    "enum value `{coordinate}` should be SCREAMING_SNAKE_CASE"
+                ));
+            }
+            self.deprecation(&coordinate, &value.directives);
+        }
+    }
+
+    fn field_name(&mut self, kind: &str, coordinate: &str, name: &str) {
+        if !is_private(name) && !is_camel_case(name) {
+            self.0
+                .push(format!("{kind} `{coordinate}` should be camelCase"));
+        }
+    }
+
+    fn deprecation(&mut self, coordinate: &str, directives: &[Directive<'static, String>]) {
+        let without_reason = directives.iter().any(|directive| {
+            directive.name == "deprecated"
+                && !directive
+                    .arguments
+                    .iter()
+                    .any(|(argument, _)| argument == "reason")
+        });
+        if without_reason {
+            self.0
+                .push(format!("`{coordinate}` is deprecated without a reason"));
+        }
+    }
+}
+
+fn is_private(name: &str) -> bool {
+    name.starts_with('_')
+}
+
+fn is_pascal_case(name: &str) -> bool {
+    name.starts_with(|c: char| c.is_ascii_uppercase()) && !name.contains('_')
+}
+
+fn is_camel_case(name: &str) -> bool {
+    name.starts_with(|c: char| c.is_ascii_lowercase()) && !name.contains('_')
+}
+
+#[cfg(test)]
+mod test_lint {
+    use super::*;
+    use crate::federation::parse_subgraph_schema;
+
+    fn lint_sdl(sdl: &str) -> Vec<String> {
+        lint(&parse_subgraph_schema(sdl).unwrap())
+    }
+
+    #[test]
+    fn clean() {
+        let sdl = r#"
+            extend schema @link(url: "https://specs.apollo.dev/federation/v2.3", import: ["@key"])
+            type Query { user(id: ID!): User _service: String }
+            type User @key(fields: "id") { id: ID! name: String @deprecated(reason: "Use `fullName`") }
+            enum Role { ADMIN READ_ONLY }
+        "#;
+        assert_eq!(lint_sdl(sdl), Vec::<String>::new());
+    }
+
+    #[test]
+    fn problems() {
+        let sdl = r"
+            type Query { user(user_id: ID!): user_profile }
+            type user_profile { FullName: String @deprecated }
+            enum Role { admin }
+            extend type Query { first_user: user_profile }
+        ";
+        assert_eq!(
+            lint_sdl(sdl),
+            [
+                "argument `Query.user(user_id:)` should be camelCase",
+                "type `user_profile` should be PascalCase",
+                "field `user_profile.FullName` should be camelCase",
+                "`user_profile.FullName` is deprecated without a reason",
+                "enum value `Role.admin` should be SCREAMING_SNAKE_CASE",
+                "field `Query.first_user` should be camelCase",
+            ]
+        );
+    }
+}
@@ -0,0 +1,143 @@
+use graphql_check_action::report::Failure;
+use graphql_check_action::{Error, Severity};
+use serde_json::json;
+
+use crate::format::problem_line;
+
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum LogFormat {
+    /// Plain lines, with problems in the format understood by the bundled problem matcher.
+    #[default]
+    Text,
+    /// One JSON object per line, for other automation to parse.
+    Json,
+}
+
+impl LogFormat {
+    pub fn parse(value: &str) -> Result<Self, Error> {
+        match value {
+            "text" => Ok(LogFormat::Text),
+            "json" => Ok(LogFormat::Json),
+            other => Err(Error::BadFormat(other.to_string())),
+        }
+    }
+}
+
+/// Everything written to stderr goes through here, so `--quiet` and `--log-format` apply to all of it.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Logger {
+    format: LogFormat,
+    quiet: bool,
+}
+
+impl Logger {
+    pub const fn new(format: LogFormat, quiet: bool) -> Self {
+        Self { format, quiet }
+    }
+
+    /// Logs something informational, which is hidden by `--quiet`.
+    pub fn info(&self, message: &str) {
+        if let Some(line) = self.info_line(message) {
+            eprintln!("{line}");
+        }
+    }
+
+    /// Logs a problem found during the run. Warnings are hidden by `--quiet`, errors never are.
+    pub fn problem(&self, error: &Error, severity: Severity) {
+        self.failure(&Failure::new(error, severity));
+    }
+
+    /// Like [`Logger::problem`], for a failure which may have come from a check.
+    pub fn failure(&self, failure: &Failure) {
+        if let Some(line) = self.problem_line(failure) {
+            eprintln!("{line}");
+        }
+    }
+
+    fn info_line(&self, message: &str) -> Option<String> {
+        if self.quiet {
+            return None;
+        }
+        Some(match self.format {
+            LogFormat::Text => message.to_string(),
+            LogFormat::Json => json!({ "level": "info", "message": message }).to_string(),
+        })
+    }
+
+    fn problem_line(&self, failure: &Failure) -> Option<String> {
+        if self.quiet && failure.severity == Severity::Warning {
+            return None;
+        }
+        Some(match self.format {
+            LogFormat::Text => problem_line(failure),
+            LogFormat::Json => {
+                let mut line = json!({
+                    "level": failure.severity,
+                    "code": failure.code,
+                    "message": failure.message,
+                });
+                if let Some(check) = failure.check {
+                    line["check"] = json!(check);
+                }
+                if let Some(request) = &failure.request {
+                    line["request"] = json!(request);
+                }
+                line.to_string()
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod test_logger {
+    use std::time::Duration;
+
+    use graphql_check_action::{Check, CheckResult, Outcome};
+
+    use super::*;
+
+    const TIMEOUT: Error = Error::Timeout {
+        check: None,
+        elapsed: Duration::from_millis(5003),
+        budget: Duration::from_secs(5),
+    };
+
+    #[test]
+    fn quiet_hides_info_and_warnings() {
+        let logger = Logger::new(LogFormat::Text, true);
+        assert_eq!(logger.info_line("hello"), None);
+        assert_eq!(
+            logger.problem_line(&Failure::new(
+                &Error::GitHubApi("nope".to_string()),
+                Severity::Warning
+            )),
+            None
+        );
+        assert!(logger
+            .problem_line(&Failure::new(&TIMEOUT, Severity::Error))
+            .is_some());
+    }
+
+    #[test]
+    fn json() {
+        let logger = Logger::new(LogFormat::Json, false);
+        assert_eq!(
+            logger
+                .problem_line(&Failure::new(&TIMEOUT, Severity::Error))
+                .unwrap(),
+            r#"{"code":"timeout","level":"error","message":"Timed out after 5003ms waiting for a response, the budget is 5000ms (raise `timeout_ms` if the server is just slow)"}"#
+        );
+        let mut result = CheckResult::new(Check::BasicQuery, Outcome::Failed(TIMEOUT));
+        result.request = Some("POST https://example.com/graphql".to_string());
+        assert_eq!(
+            logger
+                .problem_line(&Failure::of_check(&result).unwrap())
+                .unwrap(),
+            r#"{"check":"basic_query","code":"timeout","level":"error","message":"Timed out after 5003ms waiting for a response, the budget is 5000ms (raise `timeout_ms` if the server is just slow)","request":"POST https://example.com/graphql"}"#
+        );
+        assert_eq!(
+            logger.info_line("hello").unwrap(),
+            r#"{"level":"info","message":"hello"}"#
+        );
+    }
+}
@@ -1,71 +1,795 @@
-use graphql_check_action::{run_checks, Auth, Error, Introspection, Subgraph};
+use clap::{CommandFactory, Parser};
+use clap_complete::generate;
+use graphql_check_action::baseline::compare;
+use graphql_check_action::config::CheckConfig;
+use graphql_check_action::report::{Failure, Report};
+use graphql_check_action::schema::{diff, Schema};
+use graphql_check_action::{
+    Capture, CheckReport, CheckResult, Checker, ColdStart, DnsCache, Error, Replay, Severity,
+};
 use itertools::Itertools;
-use std::env;
-use std::fs::write;
+use serde_json::json;
+use std::collections::BTreeMap;
+use std::fs::{read_to_string, write};
+use std::path::Path;
 use std::process::exit;
+use std::time::{Duration, Instant};
+use std::{env, io};
+use ureq::Agent;
 
-fn main() {
-    let github_output_path = env::var("GITHUB_OUTPUT").unwrap();
+use crate::artifacts::write_bundle;
+use crate::cli::{
+    compatible_args, is_true, CheckArgs, Cli, Command, EndpointArgs, FixtureArgs, SchemaCommand,
+    VERSION,
+};
+use crate::format::{failed, markdown, rover, tap, text, Format};
+use crate::github::{annotations, head_sha, pull_request_number, workflow_path, GitHub};
+use crate::hive::{compare_with_registry, Hive};
+use crate::log::Logger;
+use crate::output::Outputs;
+use crate::studio::{violations, Studio};
 
-    let args: Vec<String> = env::args().collect();
-    let url = &args[1];
-    let auth = match args[2].as_str() {
-        "" => Auth::Disabled,
-        header => Auth::Enabled { header },
-    };
-    let subgraph_input = &args[3];
-    let allow_introspection = &args[4];
-    let insecure_subgraph = &args[5];
+mod artifacts;
+mod cli;
+mod format;
+mod github;
+mod hive;
+mod log;
+mod output;
+mod studio;
+
+/// The checks ran, and something failed.
+const EXIT_FAILED: i32 = 1;
+/// The checks couldn't run at all because of bad inputs or config. clap exits with the same code
+/// for arguments it can't parse.
+const EXIT_USAGE: i32 = 2;
+/// What Docker expects from a `HEALTHCHECK` which failed, 2 is reserved.
+const EXIT_UNHEALTHY: i32 = 1;
+/// How long the probe in `--healthcheck` may take unless a timeout is configured.
+const HEALTHCHECK_TIMEOUT: Duration = Duration::from_secs(5);
+
+fn main() {
+    let cli = Cli::parse_from(compatible_args(env::args(), |name| env::var(name).ok()));
+    let (log_format, quiet) = (cli.log_format, cli.quiet);
+    match cli.into_command() {
+        Command::Check(args) => check(&args, &Logger::new(log_format, quiet || args.healthcheck)),
+        Command::Introspect { endpoint, output } => {
+            introspect(
+                &endpoint,
+                output.as_deref(),
+                &Logger::new(log_format, quiet),
+            );
+        }
+        Command::Schema(SchemaCommand::Diff { endpoint, baseline }) => {
+            schema_diff(&endpoint, &baseline, &Logger::new(log_format, quiet));
+        }
+        Command::ServeFixture(args) => serve_fixture(&args, &Logger::new(log_format, quiet)),
+        Command::Completions { shell } => generate(
+            shell,
+            &mut Cli::command(),
+            env!("CARGO_PKG_NAME"),
+            &mut io::stdout(),
+        ),
+    }
+}
 
+fn check(args: &CheckArgs, logger: &Logger) {
+    // `GITHUB_OUTPUT` is only set in GitHub Actions, elsewhere outputs go to `--output` or stdout.
+    let output_path = args
+        .output
+        .clone()
+        .or_else(|| env::var("GITHUB_OUTPUT").ok());
     let mut errors = Vec::new();
+    logger.info(&format!("graphql-check-action {VERSION}"));
 
-    let subgraph_required = parse_boolean(subgraph_input, "subgraph").unwrap_or_else(|err| {
-        errors.push(err);
-        false
-    });
-    let allow_insecure_subgraph = parse_boolean(insecure_subgraph, "insecure_subgraph")
-        .unwrap_or_else(|err| {
+    let (config, mut problems) = args.check_config();
+    problems.extend(preflight(args, &config));
+    if !problems.is_empty() {
+        bad_inputs(args, &problems, logger, output_path.as_deref());
+    }
+    if args.healthcheck {
+        exit(healthcheck(&config, logger));
+    }
+    if config.sdl.is_some() {
+        check_sdl(&config, logger, output_path.as_deref());
+        return;
+    }
+    if !config.endpoints.is_empty() {
+        check_endpoints(&config, logger, output_path.as_deref());
+        return;
+    }
+    if config.monitor_for.is_some() {
+        check_monitor(args, &config, logger, output_path.as_deref());
+        return;
+    }
+    let url = config.url.as_str();
+    let auth = config.auth();
+    let previous_report = match &args.previous_report {
+        None => None,
+        Some(path) => match read_to_string(path)
+            .map_err(|err| Error::BadReport(err.to_string()))
+            .and_then(|json| Report::from_json(&json))
+        {
+            Ok(report) => Some(report),
+            Err(err) => {
+                errors.push(err);
+                None
+            }
+        },
+    };
+    let capture = Capture::new(auth);
+    // One agent for every request, so connections are reused and the host is only resolved once.
+    let dns = DnsCache::new();
+    let mut checker = config.checker().agent(dns.agent());
+    if let Some(path) = &args.replay {
+        match read_to_string(path)
+            .map_err(|err| Error::BadRecording(format!("{path}: {err}")))
+            .and_then(|json| Replay::from_json(&json))
+        {
+            Ok(replay) => checker = checker.transport(replay),
+            Err(err) => {
+                logger.problem(&err, err.severity());
+                exit(EXIT_USAGE);
+            }
+        }
+    }
+    // Capturing while waiting too, so the cold start is measured from the first request to reach
+    // the server rather than the first after it's been woken up.
+    let captured = checker.clone().capture(&capture);
+    let check_report = match wait_for_server(&captured, args.wait_for_port(), logger) {
+        Ok(()) => captured.report(),
+        Err(err) => {
+            errors.push(err);
+            CheckReport::default()
+        }
+    };
+    let results = only_warn_ignored(check_report.checks, &config.exit_codes);
+    if is_true(&args.log_ip) {
+        for (host, addresses) in dns.addresses() {
+            let addresses = addresses.iter().join(", ");
+            logger.info(&format!("Resolved {host} to {addresses}"));
+        }
+    }
+    match args.format {
+        Format::Text => print!("{}", text(&results, &errors)),
+        Format::Tap => print!("{}", tap(&results, &errors)),
+        Format::Rover => print!("{}", rover(&results, &errors)),
+    }
+    if is_true(&args.comment) {
+        let body = markdown(url, &results, &errors);
+        if let Err(err) = comment_on_pull_request(&body) {
+            errors.push(err);
+        }
+    }
+    if is_true(&args.check_run) {
+        if let Err(err) = create_check_run(url, &results, &errors) {
             errors.push(err);
-            false
+        }
+    }
+    let schema = (args.artifact_dir.is_some() || args.hive_endpoint.is_some())
+        .then(|| checker.schema())
+        .flatten();
+    if let (Some(endpoint), Some(token)) = (&args.hive_endpoint, &args.hive_token) {
+        let hive = Hive::new(dns.agent(), endpoint, token);
+        let registered = hive.latest_sdl().and_then(|sdl| match &schema {
+            Some(live) => compare_with_registry(sdl, live),
+            None => Err(Error::SchemaUnavailable),
         });
-    let subgraph = match (subgraph_required, allow_insecure_subgraph) {
-        (true, true) => Subgraph::Insecure,
-        (true, false) => Subgraph::Secure,
-        (false, _) => Subgraph::NotASubgraph,
+        if let Err(err) = registered {
+            errors.push(err);
+        }
+    }
+    if let Err(err) = complete_custom_check(args, dns.agent(), &results, &errors) {
+        errors.push(err);
+    }
+    let problems = collect_problems(&errors, &results, &config.exit_codes);
+    let (failures, warnings): (Vec<_>, Vec<_>) = problems
+        .iter()
+        .partition(|failure| failure.severity == Severity::Error);
+
+    let report = Report {
+        endpoint: url.to_string(),
+        failures: problems.clone(),
+        retried: check_report.retried,
+        throttled: check_report.throttled,
     };
-    let introspection = match allow_introspection.as_str() {
-        "true" => Introspection::Allow,
-        "false" => Introspection::Disallow,
-        "" => match subgraph {
-            Subgraph::NotASubgraph => Introspection::Disallow,
-            Subgraph::Secure | Subgraph::Insecure => Introspection::Allow,
-        },
-        _ => {
-            errors.push(Error::BadBoolean("allow_introspection"));
-            Introspection::Allow
+    for retried in &report.retried {
+        logger.info(&format!(
+            "Retried {} {}: {}",
+            retried.method,
+            retried.url,
+            retried.attempts.join(", ")
+        ));
+    }
+    for throttled in &report.throttled {
+        logger.info(&format!(
+            "Throttled {} {}: status {}, retry after {}s",
+            throttled.method, throttled.url, throttled.status, throttled.retry_after_secs
+        ));
+    }
+    // Files which couldn't be written fail the run, whatever the checks found.
+    let mut unwritten = Vec::new();
+    if let Some(report_path) = &args.report {
+        if let Err(err) = write_file(report_path, report.to_json()) {
+            unwritten.push(Failure::from(&err));
+        }
+    }
+    if let Some(record_path) = &args.record {
+        let recording = serde_json::to_string_pretty(&capture.exchanges()).unwrap();
+        if let Err(err) = write_file(record_path, recording) {
+            unwritten.push(Failure::from(&err));
+        }
+    }
+    if let Some(artifact_dir) = &args.artifact_dir {
+        if let Err(err) = write_bundle(Path::new(artifact_dir), &report, schema.as_ref(), &capture)
+        {
+            let err = Error::CouldNotWrite(format!("{artifact_dir}: {err}"));
+            unwritten.push(Failure::from(&err));
+        }
+    }
+    let failures: Vec<&Failure> = failures.into_iter().chain(&unwritten).collect();
+    let should_fail = !unwritten.is_empty()
+        || match previous_report {
+            Some(previous) => {
+                let comparison = compare(&previous, &report);
+                for (label, failures) in [
+                    ("New failure", &comparison.new),
+                    ("Known failure", &comparison.known),
+                    ("Fixed", &comparison.fixed),
+                ] {
+                    for failure in failures {
+                        logger.info(&format!("{label}: {}", failure.message));
+                    }
+                }
+                if is_true(&args.new_failures_only) {
+                    comparison.has_regressions()
+                } else {
+                    !failures.is_empty()
+                }
+            }
+            None => !failures.is_empty(),
+        };
+
+    let mut outputs = Outputs::new();
+    if is_true(&args.capabilities) {
+        let capabilities_json = serde_json::to_string(&checker.capabilities()).unwrap();
+        logger.info(&format!("Capabilities: {capabilities_json}"));
+        outputs.set("capabilities", capabilities_json);
+    }
+    if let Some(cold_start) = ColdStart::new(&capture.exchanges()) {
+        let warm = cold_start.warm.map_or_else(
+            || "no later requests".to_string(),
+            |warm| format!("later ones a median of {}ms", warm.p50.as_millis()),
+        );
+        logger.info(&format!(
+            "The first request took {}ms, {warm}",
+            cold_start.first.as_millis()
+        ));
+        outputs.set("cold_start_ms", cold_start.first.as_millis().to_string());
+        if let Some(warm) = cold_start.warm {
+            outputs.set("warm_p50_ms", warm.p50.as_millis().to_string());
+        }
+    }
+    if let Some(latency) = check_report.latency {
+        logger.info(&format!(
+            "Latency over {} samples: p50 {}ms, p95 {}ms, max {}ms",
+            latency.samples,
+            latency.p50.as_millis(),
+            latency.p95.as_millis(),
+            latency.max.as_millis()
+        ));
+        outputs.set("latency_p50_ms", latency.p50.as_millis().to_string());
+        outputs.set("latency_p95_ms", latency.p95.as_millis().to_string());
+        outputs.set("latency_max_ms", latency.max.as_millis().to_string());
+    }
+    if let Some(latency) = check_report.concurrent_latency {
+        logger.info(&format!(
+            "Latency of {} concurrent queries: p95 {}ms, max {}ms",
+            latency.samples,
+            latency.p95.as_millis(),
+            latency.max.as_millis()
+        ));
+        outputs.set("concurrent_p95_ms", latency.p95.as_millis().to_string());
+        outputs.set("concurrent_max_ms", latency.max.as_millis().to_string());
+    }
+    if !check_report.negotiated.is_empty() {
+        let negotiated_json = serde_json::to_string(&check_report.negotiated).unwrap();
+        logger.info(&format!("Negotiated: {negotiated_json}"));
+        outputs.set("negotiated", negotiated_json);
+    }
+    for warning in &warnings {
+        logger.failure(warning);
+    }
+    outputs.set("warning", warnings.iter().join(", "));
+    // Always set, even when empty, so later steps can use them without checking first.
+    let errors_str = if should_fail {
+        for failure in &failures {
+            logger.failure(failure);
         }
+        failures.iter().join(", ")
+    } else {
+        String::new()
     };
-    if let Some(errs) = run_checks(url, auth, subgraph, introspection).err() {
-        errors.extend(errs)
+    outputs.set("error", errors_str);
+    outputs.set("result", if should_fail { "fail" } else { "pass" });
+    write_outputs(&outputs, output_path.as_deref(), args.format, logger);
+    if should_fail {
+        exit(exit_code(
+            failures.iter().map(|failure| failure.code.as_str()),
+            &config.exit_codes,
+        ));
     }
+}
 
-    if !errors.is_empty() {
-        let errors_str = errors
+/// Checks every one of `endpoints` at once, setting `status_<name>` and `errors_<name>` outputs for
+/// each so a workflow can react to them separately, plus the usual outputs for all of them and a
+/// `summary` of both as a JSON object.
+fn check_endpoints(config: &CheckConfig, logger: &Logger, output_path: Option<&str>) {
+    let fleet = config.fleet().report();
+    let mut outputs = Outputs::new();
+    let mut summary = BTreeMap::new();
+    let mut all_failures = Vec::new();
+    let mut all_warnings = Vec::new();
+    for (name, endpoint) in config.endpoints.keys().zip(&fleet.endpoints) {
+        println!("{name} ({})", endpoint.endpoint);
+        print!("{}", text(&endpoint.report.checks, &[]));
+        let (failures, warnings): (Vec<_>, Vec<_>) =
+            collect_problems(&[], &endpoint.report.checks, &config.exit_codes)
+                .into_iter()
+                .partition(|failure| failure.severity == Severity::Error);
+        for failure in failures.iter().chain(&warnings) {
+            logger.failure(failure);
+        }
+        let status = if failures.is_empty() { "pass" } else { "fail" };
+        let errors = failures.iter().join(", ");
+        outputs.set(&format!("status_{name}"), status);
+        outputs.set(&format!("errors_{name}"), errors.clone());
+        summary.insert(name.as_str(), json!({"status": status, "errors": errors}));
+        all_warnings.extend(warnings.iter().map(|warning| format!("{name}: {warning}")));
+        all_failures.extend(failures.into_iter().map(|failure| (name, failure)));
+    }
+    outputs.set("summary", serde_json::to_string(&summary).unwrap());
+    outputs.set("warning", all_warnings.join(", "));
+    outputs.set(
+        "error",
+        all_failures
             .iter()
-            .unique()
-            .map(|e| e.to_string())
-            .collect::<Vec<String>>()
-            .join(", ");
-        eprintln!("Error: {errors_str}");
-        write(github_output_path, format!("error={errors_str}")).unwrap();
-        exit(1);
+            .map(|(name, failure)| format!("{name}: {failure}"))
+            .join(", "),
+    );
+    let should_fail = !all_failures.is_empty();
+    outputs.set("result", if should_fail { "fail" } else { "pass" });
+    write_outputs(&outputs, output_path, Format::Text, logger);
+    if should_fail {
+        let codes = all_failures
+            .iter()
+            .map(|(_, failure)| failure.code.as_str());
+        exit(exit_code(codes, &config.exit_codes));
+    }
+}
+
+/// Runs the checks which only need a schema on the SDL file given as `sdl`, without sending any
+/// requests.
+fn check_sdl(config: &CheckConfig, logger: &Logger, output_path: Option<&str>) {
+    let (results, errors) = match config.check_sdl() {
+        Ok(results) => (results, Vec::new()),
+        Err(err) => (Vec::new(), vec![err]),
+    };
+    print!("{}", text(&results, &errors));
+    let (failures, warnings): (Vec<_>, Vec<_>) =
+        collect_problems(&errors, &results, &config.exit_codes)
+            .into_iter()
+            .partition(|failure| failure.severity == Severity::Error);
+    for failure in failures.iter().chain(&warnings) {
+        logger.failure(failure);
+    }
+    let mut outputs = Outputs::new();
+    outputs.set("warning", warnings.iter().join(", "));
+    outputs.set("error", failures.iter().join(", "));
+    outputs.set("result", if failures.is_empty() { "pass" } else { "fail" });
+    write_outputs(&outputs, output_path, Format::Text, logger);
+    if !failures.is_empty() {
+        exit(exit_code(
+            failures.iter().map(|failure| failure.code.as_str()),
+            &config.exit_codes,
+        ));
     }
 }
 
-fn parse_boolean(value: &str, name: &'static str) -> Result<bool, Error> {
-    match value {
-        "true" => Ok(true),
-        "false" => Ok(false),
-        _ => Err(Error::BadBoolean(name)),
+/// Runs the checks over and over for `monitor_for`, printing a line for each run, and fails if
+/// fewer than `min_availability` percent of them passed.
+fn check_monitor(
+    args: &CheckArgs,
+    config: &CheckConfig,
+    logger: &Logger,
+    output_path: Option<&str>,
+) {
+    let mut outputs = Outputs::new();
+    let failure = match wait_for_server(&config.checker(), args.wait_for_port(), logger) {
+        Err(err) => Some(err),
+        Ok(()) => {
+            let monitor = config.monitor().expect("only called with `monitor_for`");
+            let mut run = 0;
+            let report = monitor.run_with(&mut |check_report| {
+                run += 1;
+                let failures = check_report
+                    .checks
+                    .iter()
+                    .filter(|result| result.severity == Severity::Error)
+                    .filter_map(CheckResult::error)
+                    .unique()
+                    .join(", ");
+                if failures.is_empty() {
+                    println!("PASS run {run}");
+                } else {
+                    println!("FAIL run {run}: {failures}");
+                }
+            });
+            let availability = report.availability();
+            logger.info(&format!(
+                "{} of {} runs passed ({availability:.1}%)",
+                report.passed, report.runs
+            ));
+            outputs.set("availability", format!("{availability:.1}"));
+            outputs.set(
+                "error_counts",
+                serde_json::to_string(&report.error_counts).unwrap(),
+            );
+            let min = config.min_availability.unwrap_or(100);
+            (availability < f64::from(min)).then_some(Error::LowAvailability {
+                passed: report.passed,
+                runs: report.runs,
+                min,
+            })
+        }
+    };
+    outputs.set("warning", "");
+    outputs.set(
+        "error",
+        failure
+            .as_ref()
+            .map(ToString::to_string)
+            .unwrap_or_default(),
+    );
+    outputs.set("result", if failure.is_some() { "fail" } else { "pass" });
+    write_outputs(&outputs, output_path, Format::Text, logger);
+    if let Some(failure) = failure {
+        logger.problem(&failure, Severity::Error);
+        exit(exit_code([failure.code()], &config.exit_codes));
     }
 }
+
+/// Every distinct problem from `errors` and failed `results`, with how much each matters and the
+/// check it came from. Failures which `exit_codes` maps to 0 don't fail the run, so they're only
+/// worth a warning.
+fn collect_problems(
+    errors: &[Error],
+    results: &[CheckResult],
+    exit_codes: &BTreeMap<String, u8>,
+) -> Vec<Failure> {
+    errors
+        .iter()
+        .map(|err| Failure::new(err, err.severity()))
+        .chain(results.iter().filter_map(Failure::of_check))
+        .map(|mut failure| {
+            if exit_codes.get(&failure.code) == Some(&0) {
+                failure.severity = Severity::Warning;
+            }
+            failure
+        })
+        .unique()
+        .collect()
+}
+
+/// `results`, with failures which `exit_codes` maps to 0 only warning, since they don't fail the
+/// run.
+fn only_warn_ignored(
+    mut results: Vec<CheckResult>,
+    exit_codes: &BTreeMap<String, u8>,
+) -> Vec<CheckResult> {
+    for result in &mut results {
+        if result
+            .error()
+            .is_some_and(|err| exit_codes.get(err.code()) == Some(&0))
+        {
+            result.severity = Severity::Warning;
+        }
+    }
+    results
+}
+
+/// The highest exit code `exit_codes` maps any of the error `codes` to, with [`EXIT_FAILED`] for
+/// those it doesn't map.
+fn exit_code<'a>(
+    codes: impl IntoIterator<Item = &'a str>,
+    exit_codes: &BTreeMap<String, u8>,
+) -> i32 {
+    codes
+        .into_iter()
+        .map(|code| {
+            exit_codes
+                .get(code)
+                .map_or(EXIT_FAILED, |code| i32::from(*code))
+        })
+        .max()
+        .unwrap_or(EXIT_FAILED)
+}
+
+/// Writes `contents` to `path`, which came from an input.
+fn write_file(path: &str, contents: impl AsRef<[u8]>) -> Result<(), Error> {
+    write(path, contents).map_err(|err| Error::CouldNotWrite(format!("{path}: {err}")))
+}
+
+/// Writes `outputs` to `path`, or prints them if there isn't one. Later steps rely on the outputs,
+/// so the run fails if they can't be written.
+fn write_outputs(outputs: &Outputs, path: Option<&str>, format: Format, logger: &Logger) {
+    match path {
+        Some(path) => {
+            if let Err(err) = outputs.write_to(path) {
+                let err = Error::CouldNotWrite(format!("{path}: {err}"));
+                logger.problem(&err, err.severity());
+                exit(EXIT_FAILED);
+            }
+        }
+        None if format == Format::Text => print!("{}", outputs.summary()),
+        None => {}
+    }
+}
+
+/// Reports `problems` with the inputs, setting the outputs like any other failure, and exits.
+fn bad_inputs(
+    args: &CheckArgs,
+    problems: &[Error],
+    logger: &Logger,
+    output_path: Option<&str>,
+) -> ! {
+    for problem in problems {
+        logger.problem(problem, problem.severity());
+    }
+    let mut outputs = Outputs::new();
+    outputs.set("warning", "");
+    outputs.set("error", problems.iter().join(", "));
+    outputs.set("result", "fail");
+    write_outputs(&outputs, output_path, args.format, logger);
+    exit(if args.healthcheck {
+        EXIT_UNHEALTHY
+    } else {
+        EXIT_USAGE
+    });
+}
+
+/// Every problem with the inputs, found before making any requests.
+fn preflight(args: &CheckArgs, config: &CheckConfig) -> Vec<Error> {
+    let mut problems = config.validate();
+    if is_true(&args.new_failures_only) && args.previous_report.is_none() {
+        problems.push(Error::BadInput(
+            "`new_failures_only` needs a `previous_report` to compare against".to_string(),
+        ));
+    }
+    if args.hive_endpoint.is_some() != args.hive_token.is_some() {
+        problems.push(Error::BadInput(
+            "`hive_endpoint` and `hive_token` must be given together".to_string(),
+        ));
+    }
+    let studio = [
+        &args.apollo_key,
+        &args.apollo_graph_ref,
+        &args.apollo_workflow_id,
+        &args.apollo_task_id,
+    ];
+    if studio.iter().any(|input| input.is_some()) && !studio.iter().all(|input| input.is_some()) {
+        problems.push(Error::BadInput(
+            "`apollo_key`, `apollo_graph_ref`, `apollo_workflow_id` and `apollo_task_id` must be given together".to_string(),
+        ));
+    }
+    if args.replay.is_some() && args.wait_for_port.is_some() {
+        problems.push(Error::BadInput(
+            "`replay` can't be combined with `wait_for_port`".to_string(),
+        ));
+    }
+    if args.healthcheck && args.wait_for_port.is_some() {
+        problems.push(Error::BadInput(
+            "`--healthcheck` can't be combined with `--wait-for-port`".to_string(),
+        ));
+    }
+    // Both run the checks more than once, so anything about a single run doesn't apply.
+    let single_run_only = [
+        ("format", args.format != Format::Text),
+        ("report", args.report.is_some()),
+        ("previous_report", args.previous_report.is_some()),
+        ("comment", is_true(&args.comment)),
+        ("check_run", is_true(&args.check_run)),
+        ("artifact_dir", args.artifact_dir.is_some()),
+        ("record", args.record.is_some()),
+        ("replay", args.replay.is_some()),
+        ("capabilities", is_true(&args.capabilities)),
+        ("log_ip", is_true(&args.log_ip)),
+        ("hive_endpoint", args.hive_endpoint.is_some()),
+        ("apollo_key", args.apollo_key.is_some()),
+        ("healthcheck", args.healthcheck),
+    ];
+    let wait_for_port = ("wait_for_port", args.wait_for_port.is_some());
+    let monitor_for = ("monitor_for", config.monitor_for.is_some());
+    // Each mode, whether it's enabled, and more inputs it can't be combined with.
+    let modes = [
+        (
+            "endpoints",
+            !config.endpoints.is_empty(),
+            vec![wait_for_port],
+        ),
+        (
+            "monitor_for",
+            config.monitor_for.is_some(),
+            vec![("endpoints", !config.endpoints.is_empty())],
+        ),
+        (
+            "sdl",
+            config.sdl.is_some(),
+            vec![wait_for_port, monitor_for],
+        ),
+    ];
+    for (mode, _, also) in modes.iter().filter(|(_, enabled, _)| *enabled) {
+        let conflicting = single_run_only
+            .iter()
+            .chain(also)
+            .filter(|(_, given)| *given);
+        for (input, _) in conflicting {
+            problems.push(Error::BadInput(format!(
+                "`{input}` can't be combined with `{mode}`"
+            )));
+        }
+    }
+    problems
+}
+
+/// Sends the basic query once, returning the exit code Docker expects from a `HEALTHCHECK`. Only
+/// a failure is logged.
+fn healthcheck(config: &CheckConfig, logger: &Logger) -> i32 {
+    let mut checker = config.checker();
+    if config.timeout_ms.is_none() {
+        checker = checker.timeout(HEALTHCHECK_TIMEOUT);
+    }
+    match checker.healthy() {
+        Ok(()) => 0,
+        Err(err) => {
+            logger.problem(&err, Severity::Error);
+            EXIT_UNHEALTHY
+        }
+    }
+}
+
+/// Prints the schema of the endpoint, or writes it to `output`.
+fn introspect(endpoint: &EndpointArgs, output: Option<&str>, logger: &Logger) {
+    let config = endpoint.config();
+    let schema = fetch_schema(&config, logger);
+    match output {
+        Some(path) => {
+            if let Err(err) = write(path, schema.to_string()) {
+                logger.info(&format!("Could not write schema to {path}: {err}"));
+                exit(EXIT_FAILED);
+            }
+        }
+        None => println!("{schema}"),
+    }
+}
+
+/// Prints the URL of the fixture server once it's listening, then serves until killed.
+fn serve_fixture(args: &FixtureArgs, logger: &Logger) {
+    match args.fixture().serve((args.host.as_str(), args.port)) {
+        Ok(server) => {
+            println!("{}", server.url());
+            server.wait();
+        }
+        Err(err) => {
+            logger.info(&format!(
+                "Could not serve on {}:{}: {err}",
+                args.host, args.port
+            ));
+            exit(EXIT_USAGE);
+        }
+    }
+}
+
+/// Prints how the live schema differs from `baseline`, failing if it does at all.
+fn schema_diff(endpoint: &EndpointArgs, baseline: &str, logger: &Logger) {
+    let baseline = match read_to_string(baseline)
+        .map_err(|err| Error::BadSchema(format!("{baseline}: {err}")))
+        .and_then(|text| Schema::parse(&text))
+    {
+        Ok(schema) => schema,
+        Err(err) => {
+            logger.problem(&err, err.severity());
+            exit(EXIT_USAGE);
+        }
+    };
+    let config = endpoint.config();
+    let live = fetch_schema(&config, logger);
+    match diff(&baseline, &live) {
+        Ok(diff) if diff.is_empty() => logger.info("The schema matches the baseline"),
+        Ok(diff) => {
+            print!("{diff}");
+            exit(EXIT_FAILED);
+        }
+        Err(err) => {
+            logger.problem(&err, err.severity());
+            exit(EXIT_FAILED);
+        }
+    }
+}
+
+/// Fetches the schema for `introspect` and `schema diff`, exiting if the inputs are bad or there
+/// is no schema to fetch.
+fn fetch_schema(config: &CheckConfig, logger: &Logger) -> Schema {
+    let problems = config.validate();
+    if !problems.is_empty() {
+        for problem in &problems {
+            logger.problem(problem, problem.severity());
+        }
+        exit(EXIT_USAGE);
+    }
+    config.checker().schema().unwrap_or_else(|| {
+        logger.problem(&Error::SchemaUnavailable, Severity::Error);
+        exit(EXIT_FAILED);
+    })
+}
+
+/// Waits for a server started earlier in the job, if asked to.
+fn wait_for_server(checker: &Checker, seconds: Option<u64>, logger: &Logger) -> Result<(), Error> {
+    let Some(seconds) = seconds else {
+        return Ok(());
+    };
+    let timeout = Duration::from_secs(seconds);
+    let start = Instant::now();
+    checker.wait_for_port(timeout)?;
+    // An open port doesn't mean the server answers queries yet. If it never does, the checks will
+    // say what's wrong with it.
+    if let Err(err) = checker.wait_until_healthy(timeout.saturating_sub(start.elapsed())) {
+        logger.info(&format!("Server is up but not answering queries: {err}"));
+    }
+    Ok(())
+}
+
+fn comment_on_pull_request(body: &str) -> Result<(), Error> {
+    let pull_request = pull_request_number().ok_or_else(|| {
+        Error::GitHubApi("this workflow run is not for a pull request".to_string())
+    })?;
+    GitHub::from_env()?.upsert_comment(pull_request, body)
+}
+
+fn create_check_run(
+    url: &str,
+    results: &[CheckResult],
+    other_errors: &[Error],
+) -> Result<(), Error> {
+    let head_sha = head_sha()
+        .ok_or_else(|| Error::GitHubApi("could not determine the commit to check".to_string()))?;
+    let path = workflow_path().unwrap_or_else(|| ".github".to_string());
+    GitHub::from_env()?.create_check_run(
+        &head_sha,
+        failed(results, other_errors),
+        &markdown(url, results, other_errors),
+        &annotations(results, other_errors, &path),
+    )
+}
+
+fn complete_custom_check(
+    args: &CheckArgs,
+    agent: Agent,
+    results: &[CheckResult],
+    other_errors: &[Error],
+) -> Result<(), Error> {
+    let (Some(key), Some(graph_ref), Some(workflow_id), Some(task_id)) = (
+        &args.apollo_key,
+        &args.apollo_graph_ref,
+        &args.apollo_workflow_id,
+        &args.apollo_task_id,
+    ) else {
+        return Ok(());
+    };
+    Studio::new(agent, key, graph_ref).complete_custom_check(
+        workflow_id,
+        task_id,
+        failed(results, other_errors),
+        &violations(results, other_errors),
+    )
+}
@@ -0,0 +1,339 @@
+use serde::Serialize;
+
+use crate::Check;
+
+/// Where the README explains the check, by the anchor of its section.
+macro_rules! docs {
+    ($anchor:literal) => {
+        concat!("https://github.com/dbanty/graphql-check-action#", $anchor)
+    };
+}
+
+/// What a check is for and what to do when it fails, so a failure explains itself wherever it's
+/// shown. See [`Check::info`].
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Serialize)]
+pub struct CheckInfo {
+    /// A human-readable name, like "Introspection disabled".
+    pub title: &'static str,
+    /// What the check makes sure of, in a sentence.
+    pub description: &'static str,
+    /// The section of the README which explains the check and its inputs.
+    pub docs_url: &'static str,
+    /// What usually fixes a failure.
+    pub remediation: &'static str,
+}
+
+impl Check {
+    /// The metadata of the check. Every check has some, see [`Check::ALL`].
+    pub const fn info(self) -> CheckInfo {
+        let (title, description, docs_url, remediation) = match self {
+            Check::BasicQuery => (
+                "Endpoint reachable",
+                "The endpoint answers `query { __typename }` with `data`.",
+                docs!("endpoint-reachable"),
+                "Make sure the URL is right and reachable from the runner, and that the server answers GraphQL `POST`s with JSON. Set `wait_for_port` if the server is started earlier in the job.",
+            ),
+            Check::AuthEnforced => (
+                "Authentication enforced",
+                "Queries without the `auth` header are rejected.",
+                docs!("authentication-enforced"),
+                "Reject requests without valid credentials before executing anything, e.g., in the context function of the server or at the gateway.",
+            ),
+            Check::Subgraph => (
+                "Subgraph compatibility",
+                "The endpoint answers the federation `_service { sdl }` query.",
+                docs!("subgraph-compatibility"),
+                "Build the schema with the federation support of your framework (e.g., `buildSubgraphSchema` in Apollo Server), or set `subgraph: false` if this isn't a subgraph.",
+            ),
+            Check::SubgraphSecured => (
+                "Subgraph secured",
+                "A subgraph requires authentication, since anyone who can reach it can skip the router.",
+                docs!("authentication-enforced"),
+                "Require auth and provide it with `auth`, or only expose the subgraph on a private network. Set `insecure_subgraph: true` if it's meant to be public.",
+            ),
+            Check::IntrospectionDisabled => (
+                "Introspection disabled",
+                "`__schema` isn't answered.",
+                docs!("introspection-disabled"),
+                "Turn introspection off in production, e.g., with `introspection: false` in the `ApolloServer` options or the `useDisableIntrospection` plugin in Yoga. Set `allow_introspection: true` if it's meant to be public.",
+            ),
+            Check::Latency => (
+                "Latency",
+                "The p95 of `latency_samples` basic queries is within `max_p95_ms`.",
+                docs!("latency"),
+                "Find out what makes the basic query slow, like cold starts, middleware or the network path, or raise `max_p95_ms`.",
+            ),
+            Check::Concurrency => (
+                "Concurrency",
+                "Basic queries sent at once all succeed, within `concurrency_budget_ms`.",
+                docs!("concurrency"),
+                "Check the connection limits, worker counts and rate limits in front of the server, or lower `concurrency`.",
+            ),
+            Check::CacheControl => (
+                "Response caching",
+                "Authenticated responses have every directive of `cache_control` in `Cache-Control`.",
+                docs!("response-caching"),
+                "Send `Cache-Control: no-store` (or whatever `cache_control` lists) on responses to authenticated requests, e.g., from a response plugin or the gateway.",
+            ),
+            Check::CdnCache => (
+                "CDN caching",
+                "A persisted query sent twice as a `GET` is a cache hit the second time, but never with auth.",
+                docs!("cdn-caching"),
+                "Let the CDN cache `GET`s of persisted queries (e.g., with `Cache-Control: public, max-age=60`) and bypass the cache for requests with credentials.",
+            ),
+            Check::PersistedQueryHash => (
+                "Persisted query hashes",
+                "A query can't be registered under a hash which isn't its SHA-256.",
+                docs!("persisted-query-hashes"),
+                "Check the `sha256Hash` of a persisted query against its document before storing it.",
+            ),
+            Check::ContentTypes => (
+                "Content types",
+                "Exactly the content types of `content_types` are accepted.",
+                docs!("content-types"),
+                "Reject `POST`s labeled `text/plain` or without a `Content-Type`, which browsers send cross-site without a preflight, e.g., by turning on CSRF prevention.",
+            ),
+            Check::UnknownFieldRejected => (
+                "Unknown fields",
+                "A query of a field which doesn't exist is rejected during validation.",
+                docs!("unknown-fields"),
+                "Validate operations against the schema before executing them, and answer validation errors with a 4xx or GraphQL errors rather than a 5xx.",
+            ),
+            Check::AcceptNegotiated => (
+                "Accept negotiated",
+                "`Accept` headers are honored as the GraphQL over HTTP spec says.",
+                docs!("compliance"),
+                "Answer with the media type asked for, or a 406 for one the server can't produce. Upgrading the GraphQL server usually fixes this.",
+            ),
+            Check::OptionsHandled => (
+                "OPTIONS handled",
+                "The CORS preflight of a cross-origin `POST` doesn't get a 5xx.",
+                docs!("compliance"),
+                "Handle `OPTIONS` with CORS middleware in front of the GraphQL handler, answering with the allowed methods.",
+            ),
+            Check::HeadHandled => (
+                "HEAD handled",
+                "A `HEAD` doesn't get a 5xx or a body.",
+                docs!("compliance"),
+                "Answer `HEAD` like a `GET` without the body, or with a 405.",
+            ),
+            Check::NoConnectionClose => (
+                "No `Connection: close`",
+                "Responses don't ask for the connection to be closed.",
+                docs!("compliance"),
+                "Stop sending `Connection: close`, checking the keep-alive settings of the server and any proxy in front of it.",
+            ),
+            Check::MalformedJsonRejected => (
+                "Malformed JSON rejected",
+                "A body which isn't valid JSON gets a 4xx with GraphQL errors.",
+                docs!("robustness"),
+                "Catch errors parsing the body and answer them with a 400 and an `errors` array.",
+            ),
+            Check::MissingQueryRejected => (
+                "Missing query rejected",
+                "A request without a `query` gets a 4xx with GraphQL errors.",
+                docs!("robustness"),
+                "Check the shape of a request before executing it, answering one without a `query` with a 400.",
+            ),
+            Check::EmptyQueryRejected => (
+                "Empty query rejected",
+                "An empty `query` gets a 4xx with GraphQL errors.",
+                docs!("robustness"),
+                "Answer a `query` which doesn't parse with a 400 and the syntax error.",
+            ),
+            Check::UnicodeHandled => (
+                "Unicode handled",
+                "Queries with multi-byte unicode and an embedded null are answered.",
+                docs!("robustness"),
+                "Make sure request bodies are decoded as UTF-8 everywhere, including by proxies and loggers in front of the server.",
+            ),
+            Check::WrongVariablesRejected => (
+                "Wrong variables rejected",
+                "Variables of the wrong type fail coercion, rather than being coerced or crashing the server.",
+                docs!("robustness"),
+                "Leave variables to the coercion of your GraphQL library instead of converting them before execution.",
+            ),
+            Check::InvalidAuthRejected => (
+                "Invalid auth rejected",
+                "A request with the `invalid_auth` header, like an expired token, is rejected.",
+                docs!("authentication-enforced"),
+                "Verify credentials on every request, including their signature and expiry, rather than only checking that there are some.",
+            ),
+            Check::AuthSemantics => (
+                "Unauthenticated vs. unauthorized",
+                "No credentials get a `401`, and valid ones without access get a `403`.",
+                docs!("unauthenticated-vs-unauthorized"),
+                "Answer missing or invalid credentials with a `401` (or `UNAUTHENTICATED`) and missing permissions with a `403` (or `FORBIDDEN`).",
+            ),
+            Check::RoleAccess => (
+                "Role-based access",
+                "Each role of `access` can run exactly the operations it's allowed to.",
+                docs!("role-based-access"),
+                "Fix the authorization rules guarding the operations in the failure, e.g., directives or checks in resolvers.",
+            ),
+            Check::FederationImports => (
+                "Federation imports",
+                "Every Federation 2 directive the subgraph uses is imported, from a version which has it.",
+                docs!("federation-imports"),
+                "Add the directives to the `import` of the `@link`, or raise the federation version in its `url`.",
+            ),
+            Check::EntitiesResolvable => (
+                "Entity resolution",
+                "An entity of each type with a `@key` can be looked up through `_entities`.",
+                docs!("entity-resolution"),
+                "Add a reference resolver (e.g., `__resolveReference`) for the types in the failure, or provide real keys in `entity_representations`.",
+            ),
+            Check::SmokeQuery => (
+                "Smoke query",
+                "A query through the router is answered and reaches every subgraph of `smoke_subgraphs`.",
+                docs!("smoke-queries"),
+                "Check the health of the subgraphs which weren't queried, and that the supergraph schema of the router is up to date.",
+            ),
+            Check::RouterHealth => (
+                "Router health",
+                "The health check of the Apollo Router says it's live and ready.",
+                docs!("apollo-router-health-checks"),
+                "Check the logs of the router. If the router is healthy but the basic query fails, a subgraph behind it is probably down.",
+            ),
+            Check::SchemaMatches => (
+                "Schemas match",
+                "The schema matches the one of `compare_url`, as far as `compare_allow` requires.",
+                docs!("comparing-two-endpoints"),
+                "Deploy the same schema to both endpoints, or loosen `compare_allow` if the difference is expected.",
+            ),
+            Check::Stable => (
+                "Stable",
+                "Every check has the same outcome in each of `stability_runs` runs.",
+                docs!("flakiness"),
+                "Look for a replica, cache or dependency which only fails sometimes, e.g., by checking each instance behind the load balancer.",
+            ),
+            Check::ApolloServerStackTracesHidden => (
+                "Apollo Server stack traces hidden",
+                "Errors don't include `extensions.stacktrace`.",
+                docs!("server-profiles"),
+                "Set `NODE_ENV=production`, or `includeStacktraceInErrorResponses: false` in the `ApolloServer` options.",
+            ),
+            Check::ApolloServerCsrfPrevention => (
+                "Apollo Server CSRF prevention",
+                "Simple `GET` queries, which browsers send cross-site, aren't answered.",
+                docs!("server-profiles"),
+                "Set `csrfPrevention: true` in the `ApolloServer` options.",
+            ),
+            Check::YogaErrorsMasked => (
+                "Yoga errors masked",
+                "Errors don't include `extensions.originalError`.",
+                docs!("server-profiles"),
+                "Leave `maskedErrors` on in `createYoga`, and don't run Yoga in development mode.",
+            ),
+            Check::YogaGraphiqlDisabled => (
+                "Yoga GraphiQL disabled",
+                "GraphiQL isn't served to browsers.",
+                docs!("server-profiles"),
+                "Set `graphiql: false` in `createYoga` for production.",
+            ),
+            Check::HotChocolateCostAnalysis => (
+                "Hot Chocolate cost analysis",
+                "The cost of a query is reported when asked for with `GraphQL-Cost: report`.",
+                docs!("server-profiles"),
+                "Turn cost analysis on, with limits, so that expensive queries are rejected before they run.",
+            ),
+            Check::HotChocolateBananaCakePopDisabled => (
+                "Hot Chocolate Banana Cake Pop disabled",
+                "Banana Cake Pop isn't served to browsers.",
+                docs!("server-profiles"),
+                "Turn the tool off in production with `Tool.Enable = false` in the `GraphQLServerOptions` of `MapGraphQL`.",
+            ),
+            Check::GraphQLJavaDepthLimited => (
+                "graphql-java depth limited",
+                "A query nested 20 levels deep isn't answered.",
+                docs!("server-profiles"),
+                "Add a `MaxQueryDepthInstrumentation` when building `GraphQL`.",
+            ),
+            Check::HasuraConsoleDisabled => (
+                "Hasura console disabled",
+                "`/console` isn't served.",
+                docs!("server-profiles"),
+                "Set `HASURA_GRAPHQL_ENABLE_CONSOLE=false`.",
+            ),
+            Check::HasuraAdminSecretRequired => (
+                "Hasura admin secret required",
+                "The metadata API doesn't answer without credentials.",
+                docs!("server-profiles"),
+                "Set `HASURA_GRAPHQL_ADMIN_SECRET`.",
+            ),
+            Check::HasuraDevModeDisabled => (
+                "Hasura dev mode disabled",
+                "Errors don't include `extensions.internal`.",
+                docs!("server-profiles"),
+                "Set `HASURA_GRAPHQL_DEV_MODE=false`.",
+            ),
+            Check::HasuraMetadataApiDisabled => (
+                "Hasura metadata API disabled",
+                "`/v1/metadata` doesn't exist on the same host.",
+                docs!("server-profiles"),
+                "Leave `metadata` out of `HASURA_GRAPHQL_ENABLED_APIS`, e.g., `HASURA_GRAPHQL_ENABLED_APIS=graphql`.",
+            ),
+            Check::SchemaValid => (
+                "Schema valid",
+                "The SDL parses.",
+                docs!("checking-sdl-without-a-server"),
+                "Fix the syntax error at the position in the failure.",
+            ),
+            Check::SchemaLint => (
+                "Schema lint",
+                "Names have the usual casing and every `@deprecated` has a `reason`.",
+                docs!("checking-sdl-without-a-server"),
+                "Rename what the failure lists to the usual casing, and give every `@deprecated` a `reason`.",
+            ),
+            Check::BreakingChanges => (
+                "Breaking changes",
+                "Nothing in `schema_baseline` was removed or changed, and nothing required was added.",
+                docs!("checking-sdl-without-a-server"),
+                "Deprecate what you meant to remove and keep it until clients stop using it, and make new arguments and input fields optional.",
+            ),
+        };
+        CheckInfo {
+            title,
+            description,
+            docs_url,
+            remediation,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test_metadata {
+    use super::*;
+
+    /// The anchors GitHub gives the headings of the README.
+    fn readme_anchors() -> Vec<String> {
+        include_str!("../README.md")
+            .lines()
+            .filter_map(|line| line.strip_prefix("## ").or(line.strip_prefix("### ")))
+            .map(|heading| {
+                heading
+                    .to_lowercase()
+                    .chars()
+                    .filter(|c| c.is_alphanumeric() || *c == ' ' || *c == '-')
+                    .map(|c| if c == ' ' { '-' } else { c })
+                    .collect()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn docs_exist() {
+        let anchors = readme_anchors();
+        for check in Check::ALL {
+            let info = check.info();
+            let (_, anchor) = info.docs_url.split_once('#').unwrap();
+            assert!(
+                anchors.iter().any(|known| known == anchor),
+                "{} links to a missing section: {anchor}",
+                check.name()
+            );
+            assert!(!info.title.is_empty() && !info.description.is_empty());
+            assert!(info.remediation.ends_with('.'));
+        }
+    }
+}
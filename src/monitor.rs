@@ -0,0 +1,167 @@
+use std::collections::BTreeMap;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use serde::Serialize;
+
+use crate::{serialize_millis, CheckReport, Checker, Severity};
+
+/// How often a [`Monitor`] runs the checks unless told otherwise.
+pub const DEFAULT_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Runs the checks over and over for a while, e.g., right after a risky deploy or during a traffic
+/// migration, to find out how often the endpoint was actually up rather than whether it was up
+/// once.
+///
+/// ```no_run
+/// use std::time::Duration;
+///
+/// use graphql_check_action::{Checker, Monitor};
+///
+/// let report = Monitor::new(
+///     Checker::new("https://example.com/graphql"),
+///     Duration::from_secs(600),
+/// )
+/// .interval(Duration::from_secs(10))
+/// .report();
+/// println!("{:.1}% available", report.availability());
+/// ```
+#[derive(Clone, Debug)]
+pub struct Monitor<'a> {
+    checker: Checker<'a>,
+    duration: Duration,
+    interval: Duration,
+}
+
+impl<'a> Monitor<'a> {
+    /// Monitors with `checker` for `duration`, starting a run every [`DEFAULT_INTERVAL`]. There's
+    /// always at least one run.
+    pub fn new(checker: Checker<'a>, duration: Duration) -> Self {
+        Self {
+            checker,
+            duration,
+            interval: DEFAULT_INTERVAL,
+        }
+    }
+
+    /// How long from the start of one run to the start of the next. A run which takes longer
+    /// than this is followed by the next one straight away.
+    #[must_use]
+    pub const fn interval(mut self, interval: Duration) -> Self {
+        self.interval = interval;
+        self
+    }
+
+    /// Runs the checks until `duration` is up, returning how every run went.
+    pub fn report(&self) -> MonitorReport {
+        self.run_with(&mut |_| {})
+    }
+
+    /// Like [`Monitor::report`], handing over each run as soon as it's finished.
+    pub fn run_with(&self, on_run: &mut dyn FnMut(&CheckReport)) -> MonitorReport {
+        let start = Instant::now();
+        let mut report = MonitorReport::default();
+        loop {
+            let run_start = Instant::now();
+            let run = self.checker.report();
+            report.record(&run);
+            on_run(&run);
+            let next = run_start + self.interval;
+            if next.duration_since(start) >= self.duration {
+                break;
+            }
+            thread::sleep(next.saturating_duration_since(Instant::now()));
+        }
+        report.duration = start.elapsed();
+        report
+    }
+}
+
+/// How every run of a [`Monitor`] went.
+#[derive(Clone, Debug, Default, Eq, PartialEq, Serialize)]
+pub struct MonitorReport {
+    pub runs: u32,
+    /// Runs in which no check failed, not counting the ones which only warn.
+    pub passed: u32,
+    /// How many runs failed with each kind of error, by [`Error::code`](crate::Error::code).
+    pub error_counts: BTreeMap<String, u32>,
+    /// How long monitoring took, wall-clock.
+    #[serde(rename = "duration_ms", serialize_with = "serialize_millis")]
+    pub duration: Duration,
+}
+
+impl MonitorReport {
+    /// The percentage of runs which passed, 100 if there weren't any.
+    pub fn availability(&self) -> f64 {
+        if self.runs == 0 {
+            return 100.0;
+        }
+        f64::from(self.passed) * 100.0 / f64::from(self.runs)
+    }
+
+    fn record(&mut self, run: &CheckReport) {
+        self.runs += 1;
+        if run.passed() {
+            self.passed += 1;
+        }
+        let mut codes: Vec<_> = run
+            .checks
+            .iter()
+            .filter(|result| result.severity == Severity::Error)
+            .filter_map(|result| Some(result.error()?.code()))
+            .collect();
+        codes.sort_unstable();
+        codes.dedup();
+        for code in codes {
+            *self.error_counts.entry(code.to_string()).or_default() += 1;
+        }
+    }
+}
+
+#[cfg(test)]
+mod test_monitor {
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::sync::Arc;
+
+    use serde_json::json;
+
+    use super::*;
+    use crate::testing::{query, MockTransport};
+    use crate::HttpResponse;
+
+    /// A server which isn't a subgraph, answering with a 503 while `down`.
+    fn checker(down: Arc<AtomicBool>) -> Checker<'static> {
+        Checker::new("https://example.com/graphql")
+            .allow_introspection()
+            .transport(MockTransport::new(move |request| {
+                Ok(if down.load(Ordering::SeqCst) {
+                    HttpResponse::new(503, "Service Unavailable")
+                } else if query(request).is_some_and(|query| query.contains("_service")) {
+                    HttpResponse::json(400, &json!({"errors": [{"message": "Unknown field"}]}))
+                } else {
+                    HttpResponse::json(200, &json!({"data": {"__typename": "Query"}}))
+                })
+            }))
+    }
+
+    #[test]
+    fn availability() {
+        let down = Arc::new(AtomicBool::new(false));
+        let report = Monitor::new(checker(down.clone()), Duration::from_millis(60))
+            .interval(Duration::from_millis(20))
+            .run_with(&mut |_| {
+                down.fetch_xor(true, Ordering::SeqCst);
+            });
+        assert_eq!(report.runs, 3);
+        assert_eq!(report.passed, 2);
+        assert_eq!(report.error_counts.get("bad_status"), Some(&1));
+        assert!((report.availability() - 200.0 / 3.0).abs() < 0.01);
+    }
+
+    #[test]
+    fn at_least_one_run() {
+        let report = Monitor::new(checker(Arc::default()), Duration::ZERO).report();
+        assert_eq!(report.runs, 1);
+        assert!((report.availability() - 100.0).abs() < f64::EPSILON);
+    }
+}
@@ -0,0 +1,135 @@
+use std::fs::OpenOptions;
+use std::io::{self, Write};
+use std::path::Path;
+use std::process;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Step outputs to be written to the file at `GITHUB_OUTPUT`.
+///
+/// Values are written with heredoc-style delimiters so they can contain newlines, and the file is
+/// appended to rather than replaced so outputs written by earlier commands in the step survive.
+#[derive(Debug, Default)]
+pub struct Outputs {
+    values: Vec<(String, String)>,
+}
+
+impl Outputs {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn set(&mut self, name: &str, value: impl Into<String>) {
+        self.values.push((name.to_string(), value.into()));
+    }
+
+    pub fn write_to(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+        file.write_all(self.format().as_bytes())
+    }
+
+    /// Every output as a `name: value` line, for when there's no file to write them to.
+    pub fn summary(&self) -> String {
+        self.values
+            .iter()
+            .map(|(name, value)| format!("{name}: {value}\n"))
+            .collect()
+    }
+
+    fn format(&self) -> String {
+        let seed = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |time| time.as_nanos())
+            ^ u128::from(process::id());
+        self.values
+            .iter()
+            .map(|(name, value)| {
+                let delimiter = delimiter(value, seed);
+                format!("{name}<<{delimiter}\n{value}\n{delimiter}\n")
+            })
+            .collect()
+    }
+}
+
+/// A delimiter which does not appear in `value`, so the value can't end its own heredoc early.
+fn delimiter(value: &str, seed: u128) -> String {
+    (seed..)
+        .map(|n| format!("ghadelimiter_{n:x}"))
+        .find(|delimiter| !value.contains(delimiter.as_str()))
+        .expect("some delimiter is not in the value")
+}
+
+#[cfg(test)]
+mod test_outputs {
+    use std::fs::{read_to_string, remove_file, write};
+
+    use super::*;
+
+    /// Parses the output file format the same way the runner does.
+    fn parse(contents: &str) -> Vec<(String, String)> {
+        let mut lines = contents.lines();
+        let mut values = Vec::new();
+        while let Some(line) = lines.next() {
+            let (name, delimiter) = line.split_once("<<").unwrap();
+            let value = lines
+                .by_ref()
+                .take_while(|line| *line != delimiter)
+                .collect::<Vec<_>>()
+                .join("\n");
+            values.push((name.to_string(), value));
+        }
+        values
+    }
+
+    #[test]
+    fn single_line() {
+        let mut outputs = Outputs::new();
+        outputs.set("error", "Not GraphQL");
+        assert_eq!(
+            parse(&outputs.format()),
+            [("error".to_string(), "Not GraphQL".to_string())]
+        );
+    }
+
+    #[test]
+    fn multiple_multi_line() {
+        let mut outputs = Outputs::new();
+        outputs.set("error", "first\nsecond");
+        outputs.set("warning", "third");
+        assert_eq!(
+            parse(&outputs.format()),
+            [
+                ("error".to_string(), "first\nsecond".to_string()),
+                ("warning".to_string(), "third".to_string())
+            ]
+        );
+    }
+
+    #[test]
+    fn delimiter_not_in_value() {
+        let value = "ghadelimiter_0\nghadelimiter_1";
+        assert_eq!(delimiter(value, 0), "ghadelimiter_2");
+    }
+
+    #[test]
+    fn summary() {
+        let mut outputs = Outputs::new();
+        outputs.set("error", "Not GraphQL");
+        outputs.set("latency_p95_ms", "120");
+        assert_eq!(
+            outputs.summary(),
+            "error: Not GraphQL\nlatency_p95_ms: 120\n"
+        );
+    }
+
+    #[test]
+    fn appends() {
+        let path = std::env::temp_dir().join(format!("outputs-{}", process::id()));
+        write(&path, "existing=value\n").unwrap();
+        let mut outputs = Outputs::new();
+        outputs.set("error", "Not GraphQL");
+        outputs.write_to(&path).unwrap();
+        let contents = read_to_string(&path).unwrap();
+        remove_file(&path).unwrap();
+        assert!(contents.starts_with("existing=value\nerror<<"));
+    }
+}
@@ -0,0 +1,479 @@
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use url::Url;
+
+use crate::http::Context;
+use crate::{
+    authenticate, Auth, Check, CheckResult, Error, Event, HttpRequest, Outcome, UriProblem,
+};
+
+/// The GraphQL server behind the endpoint, which enables extra checks for the ways that server is
+/// commonly misconfigured, see [`Checker::profile`](crate::Checker::profile).
+#[derive(Clone, Copy, Debug, Deserialize, Eq, Hash, PartialEq, Serialize)]
+pub enum Profile {
+    #[serde(rename = "apollo-server")]
+    ApolloServer,
+    #[serde(rename = "yoga")]
+    Yoga,
+    #[serde(rename = "hotchocolate")]
+    HotChocolate,
+    #[serde(rename = "graphql-java")]
+    GraphQLJava,
+    #[serde(rename = "hasura")]
+    Hasura,
+}
+
+impl Profile {
+    pub const ALL: [Profile; 5] = [
+        Profile::ApolloServer,
+        Profile::Yoga,
+        Profile::HotChocolate,
+        Profile::GraphQLJava,
+        Profile::Hasura,
+    ];
+
+    pub const fn name(self) -> &'static str {
+        match self {
+            Profile::ApolloServer => "apollo-server",
+            Profile::Yoga => "yoga",
+            Profile::HotChocolate => "hotchocolate",
+            Profile::GraphQLJava => "graphql-java",
+            Profile::Hasura => "hasura",
+        }
+    }
+
+    pub fn parse(value: &str) -> Result<Self, Error> {
+        Self::ALL
+            .into_iter()
+            .find(|profile| profile.name() == value)
+            .ok_or_else(|| {
+                let names = Self::ALL.map(|profile| format!("`{}`", profile.name()));
+                Error::BadInput(format!(
+                    "unknown profile `{value}`, expected one of {}",
+                    names.join(", ")
+                ))
+            })
+    }
+
+    /// The extra checks this profile runs, in order.
+    pub const fn checks(self) -> &'static [Check] {
+        match self {
+            Profile::ApolloServer => &[
+                Check::ApolloServerStackTracesHidden,
+                Check::ApolloServerCsrfPrevention,
+            ],
+            Profile::Yoga => &[Check::YogaErrorsMasked, Check::YogaGraphiqlDisabled],
+            Profile::HotChocolate => &[
+                Check::HotChocolateCostAnalysis,
+                Check::HotChocolateBananaCakePopDisabled,
+            ],
+            Profile::GraphQLJava => &[Check::GraphQLJavaDepthLimited],
+            Profile::Hasura => &[
+                Check::HasuraConsoleDisabled,
+                Check::HasuraAdminSecretRequired,
+                Check::HasuraDevModeDisabled,
+                Check::HasuraMetadataApiDisabled,
+            ],
+        }
+    }
+}
+
+/// Runs every check of `profile`, reporting each as it starts and finishes.
+pub(crate) fn run_profile_checks(
+    context: &Context,
+    url: &str,
+    auth: &Auth,
+    profile: Profile,
+    on_event: &mut dyn FnMut(Event),
+) {
+    for check in profile.checks() {
+        on_event(Event::Started(*check));
+        let outcome = match check {
+            Check::ApolloServerStackTracesHidden => {
+                error_extension_hidden(context, url, auth, "stacktrace", Error::StackTraces)
+            }
+            Check::ApolloServerCsrfPrevention => apollo_server_csrf_prevention(context, url, auth),
+            Check::YogaErrorsMasked => {
+                error_extension_hidden(context, url, auth, "originalError", Error::ErrorsUnmasked)
+            }
+            Check::YogaGraphiqlDisabled => ide_disabled(
+                context,
+                url,
+                "graphiql",
+                Error::IdeEnabled("GraphiQL".to_string()),
+            ),
+            Check::HotChocolateCostAnalysis => hotchocolate_cost_analysis(context, url, auth),
+            Check::HotChocolateBananaCakePopDisabled => ide_disabled(
+                context,
+                url,
+                "banana cake pop",
+                Error::IdeEnabled("Banana Cake Pop".to_string()),
+            ),
+            Check::GraphQLJavaDepthLimited => graphql_java_depth_limited(context, url, auth),
+            Check::HasuraConsoleDisabled => hasura_console_disabled(context, url),
+            Check::HasuraAdminSecretRequired => hasura_admin_secret_required(context, url),
+            Check::HasuraDevModeDisabled => {
+                error_extension_hidden(context, url, auth, "internal", Error::HasuraDevMode)
+            }
+            Check::HasuraMetadataApiDisabled => hasura_metadata_api_disabled(context, url, auth),
+            _ => Outcome::Skipped,
+        };
+        on_event(Event::Finished(CheckResult::new(*check, outcome)));
+    }
+}
+
+/// Sends a query which can't be parsed, failing with `exposed` if any error in the response has
+/// `extensions.{extension}`. Servers in development mode add details like stack traces there.
+fn error_extension_hidden(
+    context: &Context,
+    url: &str,
+    auth: &Auth,
+    extension: &str,
+    exposed: Error,
+) -> Outcome {
+    let response = authenticate(context, context.post(url), auth)
+        .and_then(|request| context.send(request, Some(&json!({"query": "query{__typename("}))));
+    let body: Value = match response {
+        Ok(response) => serde_json::from_str(&response.body).unwrap_or_default(),
+        Err(err) => return Outcome::Failed(err),
+    };
+    let found = body["errors"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .any(|error| error["extensions"].get(extension).is_some());
+    if found {
+        Outcome::Failed(exposed)
+    } else {
+        Outcome::Passed
+    }
+}
+
+/// Requests `url` like a browser would, failing with `enabled` if the page mentions `name`.
+fn ide_disabled(context: &Context, url: &str, name: &str, enabled: Error) -> Outcome {
+    let request = HttpRequest::new("GET", url).set("Accept", "text/html");
+    match context.send(request, None) {
+        Ok(response) if response.status == 200 && response.body.to_lowercase().contains(name) => {
+            Outcome::Failed(enabled)
+        }
+        Ok(_) => Outcome::Passed,
+        Err(err) => Outcome::Failed(err),
+    }
+}
+
+/// A simple `GET` query, which browsers send cross-site without a preflight, should be blocked
+/// by Apollo Server's `csrfPrevention` unless it has a header a form couldn't set.
+fn apollo_server_csrf_prevention(context: &Context, url: &str, auth: &Auth) -> Outcome {
+    let request = authenticate(context, HttpRequest::new("GET", url), auth)
+        .map(|request| request.query("query", "query{__typename}"));
+    let response = request.and_then(|request| context.send(request, None));
+    match response {
+        Ok(response) => {
+            let body: Value = serde_json::from_str(&response.body).unwrap_or_default();
+            if response.status == 200 && body.pointer("/data/__typename").is_some() {
+                Outcome::Failed(Error::CsrfPreventionDisabled)
+            } else {
+                Outcome::Passed
+            }
+        }
+        Err(err) => Outcome::Failed(err),
+    }
+}
+
+/// Hot Chocolate reports the cost of an operation when asked with `GraphQL-Cost: report`, which
+/// only works when cost analysis is enabled.
+fn hotchocolate_cost_analysis(context: &Context, url: &str, auth: &Auth) -> Outcome {
+    let response = authenticate(context, context.post(url), auth).and_then(|request| {
+        context.send(
+            request.set("GraphQL-Cost", "report"),
+            Some(&json!({"query": "query{__typename}"})),
+        )
+    });
+    match response {
+        Ok(response) => {
+            let body: Value = serde_json::from_str(&response.body).unwrap_or_default();
+            if body.pointer("/extensions/operationCost").is_some() {
+                Outcome::Passed
+            } else {
+                Outcome::Failed(Error::CostAnalysisDisabled)
+            }
+        }
+        Err(err) => Outcome::Failed(err),
+    }
+}
+
+/// How deeply nested the query in [`graphql_java_depth_limited`] is, more than any real client
+/// should need.
+const DEPTH: usize = 20;
+
+/// graphql-java has no depth limit unless `MaxQueryDepthInstrumentation` is added, so a query
+/// nested [`DEPTH`] levels deep is answered.
+fn graphql_java_depth_limited(context: &Context, url: &str, auth: &Auth) -> Outcome {
+    let nested = "ofType{".repeat(DEPTH - 3);
+    let query = format!(
+        "query{{__schema{{queryType{{{nested}name{}}}}}}}",
+        "}".repeat(DEPTH - 3)
+    );
+    let response = authenticate(context, context.post(url), auth)
+        .and_then(|request| context.send(request, Some(&json!({ "query": query }))));
+    match response {
+        Ok(response) => {
+            let body: Value = serde_json::from_str(&response.body).unwrap_or_default();
+            if body.get("errors").is_none() && body.pointer("/data/__schema").is_some() {
+                Outcome::Failed(Error::NoDepthLimit(DEPTH))
+            } else {
+                Outcome::Passed
+            }
+        }
+        Err(err) => Outcome::Failed(err),
+    }
+}
+
+/// `path` on the same host as `url`, where Hasura serves everything besides GraphQL.
+fn sibling(url: &str, path: &str) -> Result<String, Error> {
+    Url::parse(url)
+        .and_then(|url| url.join(path))
+        .map(String::from)
+        .map_err(|err| Error::BadUri(UriProblem::Invalid(err.to_string())))
+}
+
+/// A metadata API request which only an admin can make, and which changes nothing.
+fn export_metadata(context: &Context, url: &str, auth: &Auth) -> Result<u16, Error> {
+    let request = authenticate(context, context.post(&sibling(url, "/v1/metadata")?), auth)?;
+    let body = json!({"type": "export_metadata", "version": 2, "args": {}});
+    Ok(context.send(request, Some(&body))?.status)
+}
+
+/// The console lets anyone who can reach it browse (and with the admin secret, change) the
+/// database, it should be disabled in production.
+fn hasura_console_disabled(context: &Context, url: &str) -> Outcome {
+    let response = sibling(url, "/console")
+        .and_then(|console| context.send(HttpRequest::new("GET", &console), None));
+    match response {
+        Ok(response) if response.status == 200 => Outcome::Failed(Error::HasuraConsoleEnabled),
+        Ok(_) => Outcome::Passed,
+        Err(err) => Outcome::Failed(err),
+    }
+}
+
+/// Without an admin secret, every request is made as the admin role. An admin-only request with
+/// no credentials at all tells the two apart, unless the metadata API is disabled, in which case
+/// there's nothing to ask.
+fn hasura_admin_secret_required(context: &Context, url: &str) -> Outcome {
+    match export_metadata(context, url, &Auth::Disabled) {
+        Ok(200) => Outcome::Failed(Error::HasuraAdminSecretNotRequired),
+        Ok(404) => Outcome::Skipped,
+        Ok(_) => Outcome::Passed,
+        Err(err) => Outcome::Failed(err),
+    }
+}
+
+/// The metadata API should be left out of `HASURA_GRAPHQL_ENABLED_APIS` on a public endpoint,
+/// it's only needed while deploying.
+fn hasura_metadata_api_disabled(context: &Context, url: &str, auth: &Auth) -> Outcome {
+    match export_metadata(context, url, auth) {
+        Ok(404) => Outcome::Passed,
+        Ok(_) => Outcome::Failed(Error::HasuraMetadataApiExposed),
+        Err(err) => Outcome::Failed(err),
+    }
+}
+
+#[cfg(test)]
+mod test_hasura {
+    use super::*;
+    use crate::testing::context;
+    use crate::HttpResponse;
+
+    /// Hasura with its defaults: console, metadata API and dev mode on, no admin secret.
+    fn defaults() -> Context {
+        context(|request| {
+            Ok(match request.url.as_str() {
+                "https://example.com/console" => HttpResponse::new(200, "<html>"),
+                "https://example.com/v1/metadata" => HttpResponse::json(200, &json!({})),
+                _ => HttpResponse::json(
+                    200,
+                    &json!({"errors": [{
+                        "message": "parsing failed",
+                        "extensions": {"code": "validation-failed", "internal": {}},
+                    }]}),
+                ),
+            })
+        })
+    }
+
+    /// Hasura with only the GraphQL API enabled and an admin secret set.
+    fn hardened() -> Context {
+        context(|request| {
+            Ok(match request.url.as_str() {
+                "https://example.com/v1/graphql" => HttpResponse::json(
+                    200,
+                    &json!({"errors": [{
+                        "message": "parsing failed",
+                        "extensions": {"code": "validation-failed"},
+                    }]}),
+                ),
+                _ => HttpResponse::new(404, "resource does not exist"),
+            })
+        })
+    }
+
+    fn outcomes(context: &Context) -> Vec<Outcome> {
+        let mut outcomes = Vec::new();
+        run_profile_checks(
+            context,
+            "https://example.com/v1/graphql",
+            &Auth::Disabled,
+            Profile::Hasura,
+            &mut |event| {
+                if let Event::Finished(result) = event {
+                    outcomes.push(result.outcome);
+                }
+            },
+        );
+        outcomes
+    }
+
+    #[test]
+    fn defaults_fail() {
+        assert_eq!(
+            outcomes(&defaults()),
+            [
+                Outcome::Failed(Error::HasuraConsoleEnabled),
+                Outcome::Failed(Error::HasuraAdminSecretNotRequired),
+                Outcome::Failed(Error::HasuraDevMode),
+                Outcome::Failed(Error::HasuraMetadataApiExposed),
+            ]
+        );
+    }
+
+    #[test]
+    fn hardened_passes() {
+        assert_eq!(
+            outcomes(&hardened()),
+            [
+                Outcome::Passed,
+                Outcome::Skipped,
+                Outcome::Passed,
+                Outcome::Passed
+            ]
+        );
+    }
+
+    #[test]
+    fn parse() {
+        for profile in Profile::ALL {
+            assert_eq!(Profile::parse(profile.name()).unwrap(), profile);
+            assert_eq!(
+                serde_json::to_value(profile).unwrap(),
+                json!(profile.name())
+            );
+        }
+        assert!(Profile::parse("postgraphile").is_err());
+    }
+}
+
+#[cfg(test)]
+mod test_profiles {
+    use std::sync::Arc;
+
+    use super::*;
+    use crate::testing::{query, MockTransport};
+    use crate::HttpResponse;
+
+    fn outcomes(profile: Profile, server: MockTransport) -> Vec<Outcome> {
+        let context = Context {
+            transport: Arc::new(server),
+            ..Context::default()
+        };
+        let mut outcomes = Vec::new();
+        run_profile_checks(
+            &context,
+            "https://example.com/graphql",
+            &Auth::Disabled,
+            profile,
+            &mut |event| {
+                if let Event::Finished(result) = event {
+                    outcomes.push(result.outcome);
+                }
+            },
+        );
+        outcomes
+    }
+
+    /// Answers every query and serves `page` to browsers, with `extensions` on every error.
+    fn development(page: &'static str, extensions: Value) -> MockTransport {
+        MockTransport::new(move |request| {
+            if request.header("Accept") == Some("text/html") {
+                return Ok(HttpResponse::new(200, page));
+            }
+            Ok(match query(request) {
+                Some("query{__typename(") => HttpResponse::json(
+                    400,
+                    &json!({"errors": [{"message": "Syntax Error", "extensions": extensions}]}),
+                ),
+                _ => HttpResponse::json(
+                    200,
+                    &json!({"data": {"__typename": "Query", "__schema": {}}}),
+                ),
+            })
+        })
+    }
+
+    #[test]
+    fn apollo_server() {
+        let server = MockTransport::new(|request| {
+            Ok(match (request.method.as_str(), query(request)) {
+                ("GET", _) => HttpResponse::new(400, "blocked as a potential CSRF"),
+                (_, Some("query{__typename(")) => HttpResponse::json(
+                    400,
+                    &json!({"errors": [{"message": "Syntax Error", "extensions": {"stacktrace": []}}]}),
+                ),
+                _ => HttpResponse::json(200, &json!({"data": {"__typename": "Query"}})),
+            })
+        });
+        assert_eq!(
+            outcomes(Profile::ApolloServer, server),
+            [Outcome::Failed(Error::StackTraces), Outcome::Passed]
+        );
+    }
+
+    #[test]
+    fn yoga() {
+        let server = development("<title>Yoga GraphiQL</title>", json!({"originalError": {}}));
+        assert_eq!(
+            outcomes(Profile::Yoga, server),
+            [
+                Outcome::Failed(Error::ErrorsUnmasked),
+                Outcome::Failed(Error::IdeEnabled("GraphiQL".to_string()))
+            ]
+        );
+    }
+
+    #[test]
+    fn hotchocolate() {
+        let server = development("<title>Banana Cake Pop</title>", json!({}));
+        assert_eq!(
+            outcomes(Profile::HotChocolate, server),
+            [
+                Outcome::Failed(Error::CostAnalysisDisabled),
+                Outcome::Failed(Error::IdeEnabled("Banana Cake Pop".to_string()))
+            ]
+        );
+    }
+
+    #[test]
+    fn graphql_java() {
+        let depths = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let seen = depths.clone();
+        let server = MockTransport::new(move |request| {
+            let query = query(request).unwrap_or_default();
+            seen.lock().unwrap().push(query.matches('{').count());
+            Ok(HttpResponse::json(200, &json!({"data": {"__schema": {}}})))
+        });
+        assert_eq!(
+            outcomes(Profile::GraphQLJava, server),
+            [Outcome::Failed(Error::NoDepthLimit(DEPTH))]
+        );
+        assert_eq!(*depths.lock().unwrap(), [DEPTH]);
+    }
+}
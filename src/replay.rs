@@ -0,0 +1,172 @@
+use std::fmt::{self, Debug, Formatter};
+use std::sync::Mutex;
+
+use serde_json::Value;
+
+use crate::{Error, ErrorKind, Exchange, HttpRequest, HttpResponse, Transport, TransportError};
+
+/// A [`Transport`] which answers from recorded [`Exchange`]s instead of sending anything, e.g.,
+/// the `exchanges.json` of a debugging bundle or a file written with `--record`. Replaying lets the
+/// checks be run offline against exactly what a server once answered, to reproduce a bug report or
+/// test the checks deterministically.
+///
+/// Each request gets the first unused exchange with the same method, URL and body, so a request
+/// sent several times (e.g., with and without auth) gets each of its responses in the order they
+/// were recorded. Headers aren't compared, since secrets are redacted from recordings.
+///
+/// ```
+/// use graphql_check_action::{Checker, Replay};
+///
+/// let replay = Replay::from_json("[]").unwrap();
+/// let report = Checker::new("https://example.com/graphql")
+///     .transport(replay)
+///     .report();
+/// assert!(!report.passed());
+/// ```
+pub struct Replay {
+    exchanges: Mutex<Vec<Exchange>>,
+}
+
+impl Replay {
+    pub fn new(exchanges: Vec<Exchange>) -> Self {
+        Self {
+            exchanges: Mutex::new(exchanges),
+        }
+    }
+
+    /// Reads a JSON array of exchanges, as written by `--record`.
+    pub fn from_json(json: &str) -> Result<Self, Error> {
+        serde_json::from_str(json)
+            .map(Self::new)
+            .map_err(|err| Error::BadRecording(err.to_string()))
+    }
+
+    /// How many recorded exchanges haven't been replayed yet.
+    pub fn remaining(&self) -> usize {
+        self.exchanges().len()
+    }
+
+    fn exchanges(&self) -> std::sync::MutexGuard<'_, Vec<Exchange>> {
+        self.exchanges.lock().expect("replay lock poisoned")
+    }
+}
+
+impl Debug for Replay {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Replay")
+            .field("remaining", &self.remaining())
+            .finish()
+    }
+}
+
+impl Transport for Replay {
+    fn send(&self, request: &HttpRequest) -> Result<HttpResponse, Error> {
+        let body = match &request.raw_body {
+            Some(raw_body) => Some(Value::String(raw_body.clone())),
+            None => request.body.clone(),
+        };
+        let mut exchanges = self.exchanges();
+        let position = exchanges.iter().position(|exchange| {
+            exchange.method == request.method
+                && exchange.url == request.url
+                && exchange.request_body == body
+        });
+        let Some(exchange) = position.map(|position| exchanges.remove(position)) else {
+            return Err(Error::CouldNotConnect(TransportError::new(format!(
+                "nothing was recorded for {} {}",
+                request.method, request.url
+            ))));
+        };
+        match (exchange.status, exchange.error) {
+            (Some(status), _) => Ok(HttpResponse {
+                status,
+                headers: exchange.response_headers,
+                body: exchange.response_body.unwrap_or_default(),
+            }),
+            (None, error) => Err(exchange
+                .error_kind
+                .unwrap_or(ErrorKind::CouldNotConnect)
+                .error(error.unwrap_or_default())),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test_replay {
+    use std::time::Duration;
+
+    use serde_json::json;
+
+    use super::*;
+    use crate::testing::MockTransport;
+    use crate::{Auth, Capture, Checker, Outcome};
+
+    #[test]
+    fn replays_a_recording() {
+        let capture = Capture::new(Auth::Disabled);
+        let live = Checker::new("https://example.com/graphql")
+            .allow_introspection()
+            .transport(MockTransport::new(|request| {
+                Ok(if request.body.is_some() {
+                    HttpResponse::json(200, &json!({"data": {"__typename": "Query"}}))
+                        .with_header("Cache-Control", "no-store")
+                } else {
+                    HttpResponse::new(405, "")
+                })
+            }))
+            .cache_control("no-store")
+            .capture(&capture)
+            .report();
+        let recording = serde_json::to_string(&capture.exchanges()).unwrap();
+
+        let replay = Replay::from_json(&recording).unwrap();
+        let replayed = Checker::new("https://example.com/graphql")
+            .allow_introspection()
+            .cache_control("no-store")
+            .transport(replay)
+            .report();
+        let outcomes = |report: &crate::CheckReport| {
+            report
+                .checks
+                .iter()
+                .map(|result| (result.check, result.outcome.clone()))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(outcomes(&replayed), outcomes(&live));
+    }
+
+    #[test]
+    fn nothing_recorded() {
+        let results = Checker::new("https://example.com/graphql")
+            .transport(Replay::new(Vec::new()))
+            .run();
+        assert_eq!(
+            results[0].outcome,
+            Outcome::Failed(Error::CouldNotConnect(TransportError::new(
+                "nothing was recorded for POST https://example.com/graphql"
+            )))
+        );
+    }
+
+    #[test]
+    fn errors() {
+        let timeout = Error::timed_out(Duration::from_millis(5003), Duration::from_secs(5));
+        let refused = Error::ConnectionRefused(TransportError::new("Connection refused"));
+        for error in [timeout, refused] {
+            let kind = ErrorKind::of(&error).unwrap();
+            let replayed = kind.error(error.to_string());
+            assert_eq!(replayed.code(), error.code());
+            if let Error::Timeout { .. } = error {
+                assert_eq!(replayed, error);
+            }
+        }
+    }
+
+    #[test]
+    fn bad_recording() {
+        assert!(matches!(
+            Replay::from_json("{}"),
+            Err(Error::BadRecording(_))
+        ));
+    }
+}
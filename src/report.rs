@@ -0,0 +1,137 @@
+use std::fmt::{self, Display, Formatter};
+
+use itertools::Itertools;
+use serde::{Deserialize, Serialize};
+
+use crate::{Check, CheckResult, Error, Retried, Severity, Throttled};
+
+/// The outcome of a run in a form that can be saved (e.g., as a workflow artifact) and compared
+/// against later runs.
+#[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+pub struct Report {
+    pub endpoint: String,
+    pub failures: Vec<Failure>,
+    /// Requests which were tried more than once, and what each attempt got.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub retried: Vec<Retried>,
+    /// Responses which asked to slow down, i.e., the checks were being rate limited.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub throttled: Vec<Throttled>,
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, Hash, PartialEq, Serialize)]
+pub struct Failure {
+    pub code: String,
+    pub message: String,
+    #[serde(default)]
+    pub severity: Severity,
+    /// The check which failed, if the failure came from one.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub check: Option<Check>,
+    /// The last request the check sent, see [`CheckResult::request`].
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub request: Option<String>,
+    /// What usually fixes a failure of the check, see [`CheckInfo`](crate::CheckInfo).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub remediation: Option<String>,
+    /// Where the check is explained, see [`CheckInfo`](crate::CheckInfo).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub docs_url: Option<String>,
+}
+
+impl Report {
+    pub fn new(endpoint: &str, errors: &[Error]) -> Self {
+        Self {
+            endpoint: endpoint.to_string(),
+            failures: errors.iter().unique().map(Failure::from).collect(),
+            retried: Vec::new(),
+            throttled: Vec::new(),
+        }
+    }
+
+    pub fn from_json(json: &str) -> Result<Self, Error> {
+        serde_json::from_str(json).map_err(|err| Error::BadReport(err.to_string()))
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).expect("Report is always serializable")
+    }
+}
+
+impl Failure {
+    /// A failure of `error` which matters as much as `severity`, rather than the error's default.
+    pub fn new(error: &Error, severity: Severity) -> Self {
+        Self {
+            code: error.code().to_string(),
+            message: error.to_string(),
+            severity,
+            check: None,
+            request: None,
+            remediation: None,
+            docs_url: None,
+        }
+    }
+
+    /// The failure of a check, or `None` if it didn't fail.
+    pub fn of_check(result: &CheckResult) -> Option<Self> {
+        let info = result.check.info();
+        Some(Self {
+            check: Some(result.check),
+            request: result.request.clone(),
+            remediation: Some(info.remediation.to_string()),
+            docs_url: Some(info.docs_url.to_string()),
+            ..Self::new(result.error()?, result.severity)
+        })
+    }
+}
+
+/// The message, after the name of the check if it came from one, e.g.,
+/// `introspection_disabled: Introspection is enabled…`.
+impl Display for Failure {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self.check {
+            Some(check) => write!(f, "{}: {}", check.name(), self.message),
+            None => f.write_str(&self.message),
+        }
+    }
+}
+
+impl From<&Error> for Failure {
+    fn from(error: &Error) -> Self {
+        Self::new(error, error.severity())
+    }
+}
+
+#[cfg(test)]
+mod test_report {
+    use super::*;
+
+    #[test]
+    fn round_trip() {
+        let report = Report::new(
+            "https://example.com/graphql",
+            &[
+                Error::IntrospectionEnabled,
+                Error::BadStatus {
+                    status: 500,
+                    excerpt: "Internal Server Error".to_string(),
+                },
+            ],
+        );
+        assert_eq!(Report::from_json(&report.to_json()), Ok(report));
+    }
+
+    #[test]
+    fn duplicates_removed() {
+        let report = Report::new("", &[Error::Cancelled, Error::Cancelled]);
+        assert_eq!(report.failures.len(), 1);
+    }
+
+    #[test]
+    fn bad_json() {
+        assert!(matches!(
+            Report::from_json("not a report"),
+            Err(Error::BadReport(_))
+        ));
+    }
+}
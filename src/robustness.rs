@@ -0,0 +1,408 @@
+use serde_json::{json, Value};
+
+use crate::http::{Context, HttpResponse};
+use crate::{authenticate, Auth, Check, CheckResult, Error, Event, Outcome};
+
+/// The checks run by [`Checker::robustness`](crate::Checker::robustness), in order.
+pub(crate) const ROBUSTNESS_CHECKS: &[Check] = &[
+    Check::MalformedJsonRejected,
+    Check::MissingQueryRejected,
+    Check::EmptyQueryRejected,
+    Check::UnicodeHandled,
+    Check::WrongVariablesRejected,
+];
+
+/// Sent by [`Check::WrongVariablesRejected`] unless given another operation: a string where a
+/// `Boolean!` is expected, using only what every schema has.
+fn default_wrong_variables() -> Value {
+    json!({
+        "query": "query($skip: Boolean!) { __typename @skip(if: $skip) }",
+        "variables": {"skip": "yes"},
+    })
+}
+
+/// Strings which lossy middleware tends to mangle, sent by [`Check::UnicodeHandled`], with what
+/// each is for the error message.
+const PAYLOADS: [(&str, &str); 3] = [
+    ("multi-byte unicode", "héllo wörld 你好 🚀 👩‍💻"),
+    (
+        "characters next to the surrogate range",
+        "\u{D7FF}\u{E000}\u{FFFD}\u{FFFF}\u{10000}\u{10FFFF}",
+    ),
+    ("an embedded null", "before\0after"),
+];
+
+/// Surrounds a payload sent where a server will quote it back, to find it in the error.
+const ECHO_MARKER: &str = "~~";
+
+/// Runs every robustness check, reporting each as it starts and finishes.
+pub(crate) fn run_robustness_checks(
+    context: &Context,
+    url: &str,
+    auth: &Auth,
+    wrong_variables: Option<&Value>,
+    on_event: &mut dyn FnMut(Event),
+) {
+    for check in ROBUSTNESS_CHECKS {
+        on_event(Event::Started(*check));
+        let outcome = match check {
+            Check::MalformedJsonRejected => malformed_json_rejected(context, url, auth),
+            Check::MissingQueryRejected => {
+                request_error(context, url, auth, &json!({}), "a body without a `query`")
+            }
+            Check::EmptyQueryRejected => request_error(
+                context,
+                url,
+                auth,
+                &json!({"query": ""}),
+                "an empty `query`",
+            ),
+            Check::UnicodeHandled => unicode_handled(context, url, auth),
+            Check::WrongVariablesRejected => request_error(
+                context,
+                url,
+                auth,
+                &wrong_variables
+                    .cloned()
+                    .unwrap_or_else(default_wrong_variables),
+                "variables of the wrong type",
+            ),
+            _ => Outcome::Skipped,
+        };
+        on_event(Event::Finished(CheckResult::new(*check, outcome)));
+    }
+}
+
+/// Sends a body which is cut off part way through, so isn't valid JSON. That must get a 4xx
+/// response with GraphQL errors, not a 5xx or a dropped connection.
+fn malformed_json_rejected(context: &Context, url: &str, auth: &Auth) -> Outcome {
+    let response = authenticate(context, context.post(url), auth).and_then(|request| {
+        context.send_raw(
+            request.set("Content-Type", "application/json"),
+            r#"{"query": "query{__typename}""#,
+        )
+    });
+    match response {
+        Ok(response) => rejected(&response, false)
+            .map_err(|problem| Error::BadRequestMishandled {
+                request: "a body which isn't valid JSON".to_string(),
+                problem,
+            })
+            .into(),
+        Err(err) => Outcome::Failed(err),
+    }
+}
+
+/// Sends `body`, which can't be executed, as JSON. The GraphQL-over-HTTP spec allows a
+/// server to answer that with a 200 for `application/json` responses, as long as it has errors and
+/// no `data`.
+fn request_error(
+    context: &Context,
+    url: &str,
+    auth: &Auth,
+    body: &Value,
+    description: &str,
+) -> Outcome {
+    let response = authenticate(context, context.post(url), auth)
+        .and_then(|request| context.send(request, Some(body)));
+    match response {
+        Ok(response) => rejected(&response, true)
+            .map_err(|problem| Error::BadRequestMishandled {
+                request: description.to_string(),
+                problem,
+            })
+            .into(),
+        Err(err) => Outcome::Failed(err),
+    }
+}
+
+/// Sends each of [`PAYLOADS`] as a variable of a query for `__typename`, and in a comment of it
+/// where GraphQL allows, failing unless every one is answered. A 5xx or a syntax error here usually
+/// means something between the client and the GraphQL engine re-encoded the request lossily.
+///
+/// Each payload is then sent again as an `Int` variable, between [`ECHO_MARKER`]s. Most servers
+/// quote an invalid value back in their error, so if one does, the value it quotes must be the
+/// payload.
+fn unicode_handled(context: &Context, url: &str, auth: &Auth) -> Outcome {
+    for (description, payload) in PAYLOADS {
+        match payload_problem(context, url, auth, payload) {
+            Ok(None) => {}
+            Ok(Some(problem)) => {
+                return Outcome::Failed(Error::PayloadMishandled {
+                    payload: description.to_string(),
+                    problem,
+                })
+            }
+            Err(err) => return Outcome::Failed(err),
+        }
+    }
+    Outcome::Passed
+}
+
+/// What went wrong with `payload`, see [`unicode_handled`].
+fn payload_problem(
+    context: &Context,
+    url: &str,
+    auth: &Auth,
+    payload: &str,
+) -> Result<Option<String>, Error> {
+    let comment = if payload.contains(char::is_control) {
+        ""
+    } else {
+        payload
+    };
+    let body = json!({
+        "query": format!("query($payload: String) {{\n  # {comment}\n  __typename\n}}"),
+        "variables": {"payload": payload},
+    });
+    let response = authenticate(context, context.post(url), auth)
+        .and_then(|request| context.send(request, Some(&body)))?;
+    if response.status >= 500 {
+        return Ok(Some(format!(
+            "it responded with status {}",
+            response.status
+        )));
+    }
+    let body: Value = serde_json::from_str(&response.body).unwrap_or_default();
+    if !body
+        .pointer("/data/__typename")
+        .is_some_and(Value::is_string)
+    {
+        return Ok(Some(match body.get("errors") {
+            Some(errors) => format!("it responded with errors {errors}"),
+            None => format!("it responded with status {} and no `data`", response.status),
+        }));
+    }
+    let body = json!({
+        "query": "query($payload: Int) { __typename }",
+        "variables": {"payload": format!("{ECHO_MARKER}{payload}{ECHO_MARKER}")},
+    });
+    let response = authenticate(context, context.post(url), auth)
+        .and_then(|request| context.send(request, Some(&body)))?;
+    if response.status >= 500 {
+        return Ok(Some(format!(
+            "it responded with status {} when it was the wrong type",
+            response.status
+        )));
+    }
+    // Servers which quote values in JSON escape control characters.
+    let escaped = Value::from(payload).to_string();
+    let escaped = &escaped[1..escaped.len() - 1];
+    Ok(echoed(&response)
+        .filter(|echoed| echoed != payload && echoed != escaped)
+        .map(|echoed| format!("it was echoed back as {echoed:?}")))
+}
+
+/// The first value quoted between [`ECHO_MARKER`]s in the message of a GraphQL error in
+/// `response`, if any.
+fn echoed(response: &HttpResponse) -> Option<String> {
+    let body: Value = serde_json::from_str(&response.body).ok()?;
+    body["errors"]
+        .as_array()?
+        .iter()
+        .filter_map(|error| error["message"].as_str())
+        .find_map(|message| {
+            let (_, rest) = message.split_once(ECHO_MARKER)?;
+            let (echoed, _) = rest.split_once(ECHO_MARKER)?;
+            Some(echoed.to_string())
+        })
+}
+
+/// Whether `response` rejects a request as a request error: a 4xx status, or a 200 if `allow_ok`,
+/// with at least one GraphQL error and no `data`. Otherwise, what was wrong with it.
+fn rejected(response: &HttpResponse, allow_ok: bool) -> Result<(), String> {
+    let status = response.status;
+    if status >= 500 || (status < 400 && !(allow_ok && status == 200)) {
+        return Err(format!("it responded with status {status}"));
+    }
+    let body: Value = serde_json::from_str(&response.body).unwrap_or_default();
+    if body.get("data").is_some_and(|data| !data.is_null()) {
+        return Err("it responded with `data`".to_string());
+    }
+    if body["errors"].as_array().is_none_or(Vec::is_empty) {
+        return Err("the response had no GraphQL `errors`".to_string());
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test_run_robustness_checks {
+    use serde_json::json;
+
+    use super::*;
+    use crate::testing::context;
+
+    fn run(handler: fn(&crate::HttpRequest) -> HttpResponse) -> Vec<CheckResult> {
+        let context = context(move |request| Ok(handler(request)));
+        let mut results = Vec::new();
+        run_robustness_checks(
+            &context,
+            "https://example.com/graphql",
+            &Auth::Disabled,
+            None,
+            &mut |event| {
+                if let Event::Finished(result) = event {
+                    results.push(result);
+                }
+            },
+        );
+        results
+    }
+
+    #[test]
+    fn robust() {
+        let results = run(|request| match crate::testing::query(request) {
+            Some(query) if query.contains("$payload") => {
+                HttpResponse::json(200, &json!({"data": {"__typename": "Query"}}))
+            }
+            _ => HttpResponse::json(400, &json!({"errors": [{"message": "Bad request"}]})),
+        });
+        assert!(results
+            .iter()
+            .all(|result| result.outcome == Outcome::Passed));
+    }
+
+    #[test]
+    fn malformed_json_crashes() {
+        let results = run(|request| {
+            if request.raw_body.is_some() {
+                HttpResponse::new(500, "SyntaxError: Unexpected end of JSON input")
+            } else {
+                HttpResponse::json(400, &json!({"errors": [{"message": "Bad request"}]}))
+            }
+        });
+        assert_eq!(
+            results[0].outcome,
+            Outcome::Failed(Error::BadRequestMishandled {
+                request: "a body which isn't valid JSON".to_string(),
+                problem: "it responded with status 500".to_string(),
+            })
+        );
+    }
+
+    #[test]
+    fn empty_query_executed() {
+        let results = run(|request| match crate::testing::query(request) {
+            Some("") => HttpResponse::json(200, &json!({"data": null})),
+            _ => HttpResponse::json(200, &json!({"errors": [{"message": "Bad request"}]})),
+        });
+        let outcomes: Vec<_> = results
+            .into_iter()
+            .take(3)
+            .map(|result| result.outcome)
+            .collect();
+        assert_eq!(
+            outcomes,
+            [
+                Outcome::Failed(Error::BadRequestMishandled {
+                    request: "a body which isn't valid JSON".to_string(),
+                    problem: "it responded with status 200".to_string(),
+                }),
+                Outcome::Passed,
+                Outcome::Failed(Error::BadRequestMishandled {
+                    request: "an empty `query`".to_string(),
+                    problem: "the response had no GraphQL `errors`".to_string(),
+                }),
+            ]
+        );
+    }
+
+    #[test]
+    fn null_truncated() {
+        let results = run(|request| {
+            let variables = request.body.as_ref().and_then(|body| body.get("variables"));
+            match variables.and_then(|variables| variables["payload"].as_str()) {
+                Some(payload) if payload.contains('\0') => HttpResponse::new(500, "Bad string"),
+                Some(_) => HttpResponse::json(200, &json!({"data": {"__typename": "Query"}})),
+                None => HttpResponse::json(400, &json!({"errors": [{"message": "Bad request"}]})),
+            }
+        });
+        assert_eq!(
+            results[3].outcome,
+            Outcome::Failed(Error::PayloadMishandled {
+                payload: "an embedded null".to_string(),
+                problem: "it responded with status 500".to_string(),
+            })
+        );
+    }
+
+    /// A server which quotes invalid values back in errors, as `mangle` leaves them.
+    fn quoting(request: &crate::HttpRequest, mangle: fn(&str) -> String) -> HttpResponse {
+        let variables = request.body.as_ref().and_then(|body| body.get("variables"));
+        match (
+            crate::testing::query(request),
+            variables.and_then(|variables| variables["payload"].as_str()),
+        ) {
+            (Some(query), Some(payload)) if query.contains("Int") => HttpResponse::json(
+                200,
+                &json!({"errors": [{"message": format!(
+                    "Variable \"$payload\" got invalid value {}; Int cannot represent non-integer value",
+                    mangle(payload)
+                )}]}),
+            ),
+            (_, Some(_)) => HttpResponse::json(200, &json!({"data": {"__typename": "Query"}})),
+            _ => HttpResponse::json(400, &json!({"errors": [{"message": "Bad request"}]})),
+        }
+    }
+
+    #[test]
+    fn echoed_intact() {
+        let results = run(|request| quoting(request, |payload| Value::from(payload).to_string()));
+        assert_eq!(results[3].outcome, Outcome::Passed);
+    }
+
+    #[test]
+    fn echoed_mangled() {
+        let results = run(|request| {
+            quoting(request, |payload| {
+                payload.replace(|char: char| !char.is_ascii(), "?")
+            })
+        });
+        assert_eq!(
+            results[3].outcome,
+            Outcome::Failed(Error::PayloadMishandled {
+                payload: "multi-byte unicode".to_string(),
+                problem: "it was echoed back as \"h?llo w?rld ?? ? ???\"".to_string(),
+            })
+        );
+    }
+
+    #[test]
+    fn wrong_variables_coerced() {
+        let results = run(|request| match crate::testing::query(request) {
+            Some(query) if query.contains("__typename") => {
+                HttpResponse::json(200, &json!({"data": {"__typename": "Query"}}))
+            }
+            _ => HttpResponse::json(400, &json!({"errors": [{"message": "Bad request"}]})),
+        });
+        assert_eq!(
+            results[4].outcome,
+            Outcome::Failed(Error::BadRequestMishandled {
+                request: "variables of the wrong type".to_string(),
+                problem: "it responded with `data`".to_string(),
+            })
+        );
+    }
+
+    #[test]
+    fn rejections() {
+        let errors = json!({"errors": [{"message": "Bad request"}]});
+        assert_eq!(rejected(&HttpResponse::json(400, &errors), false), Ok(()));
+        assert_eq!(rejected(&HttpResponse::json(200, &errors), true), Ok(()));
+        assert_eq!(
+            rejected(&HttpResponse::json(200, &errors), false),
+            Err("it responded with status 200".to_string())
+        );
+        assert_eq!(
+            rejected(&HttpResponse::new(400, "Bad Request"), false),
+            Err("the response had no GraphQL `errors`".to_string())
+        );
+        assert_eq!(
+            rejected(
+                &HttpResponse::json(200, &json!({"data": {"__typename": "Query"}, "errors": []})),
+                true
+            ),
+            Err("it responded with `data`".to_string())
+        );
+    }
+}
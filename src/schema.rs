@@ -0,0 +1,860 @@
+use std::collections::BTreeSet;
+use std::fmt::Display;
+
+use graphql_parser::schema::{Definition, Field, InputValue, TypeDefinition, TypeExtension};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+use crate::federation::{check_imports, parse_subgraph_schema};
+use crate::http::Context;
+use crate::lint::lint;
+use crate::{post_json, Auth, Check, CheckResult, Error, Outcome};
+
+/// The standard introspection query, as sent by most GraphQL tooling.
+pub const INTROSPECTION_QUERY: &str = r#"query IntrospectionQuery {
+  __schema {
+    queryType { name }
+    mutationType { name }
+    subscriptionType { name }
+    types { ...FullType }
+    directives {
+      name
+      description
+      locations
+      args { ...InputValue }
+    }
+  }
+}
+
+fragment FullType on __Type {
+  kind
+  name
+  description
+  fields(includeDeprecated: true) {
+    name
+    description
+    args { ...InputValue }
+    type { ...TypeRef }
+    isDeprecated
+    deprecationReason
+  }
+  inputFields { ...InputValue }
+  interfaces { ...TypeRef }
+  enumValues(includeDeprecated: true) {
+    name
+    description
+    isDeprecated
+    deprecationReason
+  }
+  possibleTypes { ...TypeRef }
+}
+
+fragment InputValue on __InputValue {
+  name
+  description
+  type { ...TypeRef }
+  defaultValue
+}
+
+fragment TypeRef on __Type {
+  kind
+  name
+  ofType {
+    kind
+    name
+    ofType {
+      kind
+      name
+      ofType {
+        kind
+        name
+        ofType {
+          kind
+          name
+          ofType {
+            kind
+            name
+            ofType {
+              kind
+              name
+              ofType {
+                kind
+                name
+              }
+            }
+          }
+        }
+      }
+    }
+  }
+}"#;
+
+/// A schema fetched from a live endpoint.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Schema {
+    /// The SDL returned by a federation subgraph's `_service { sdl }` field.
+    Sdl(String),
+    /// The `data` of a response to [`INTROSPECTION_QUERY`].
+    Introspection(Value),
+}
+
+impl Schema {
+    /// Reads a saved schema: SDL, or the JSON of an introspection response with or without its
+    /// `data` wrapper (like `introspection.json` in an artifact bundle).
+    pub fn parse(text: &str) -> Result<Self, Error> {
+        if !text.trim_start().starts_with('{') {
+            return Ok(Schema::Sdl(text.to_string()));
+        }
+        let mut json: Value =
+            serde_json::from_str(text).map_err(|err| Error::BadSchema(err.to_string()))?;
+        let data = match json.get_mut("data") {
+            Some(data) => data.take(),
+            None => json,
+        };
+        if data.get("__schema").is_some_and(Value::is_object) {
+            Ok(Schema::Introspection(data))
+        } else {
+            Err(Error::BadSchema(
+                "JSON is not an introspection result, it has no `__schema`".to_string(),
+            ))
+        }
+    }
+
+    /// Every type, field, argument, enum value, interface and union member in the schema, with
+    /// their types, as lines like `Query.user(id: ID!)`. Two schemas with the same coordinates
+    /// are the same to clients, however they're formatted or ordered, and whichever way they were
+    /// fetched. Built-in scalars and introspection types are left out, since SDL doesn't list them.
+    pub fn coordinates(&self) -> Result<BTreeSet<String>, Error> {
+        match self {
+            Schema::Sdl(sdl) => sdl_coordinates(sdl),
+            Schema::Introspection(data) => Ok(introspection_coordinates(data)),
+        }
+    }
+
+    /// The schema as a typed AST, for analyses of your own. Introspection is turned into the same
+    /// AST as SDL, without built-in scalars, directives and introspection types. Needs the
+    /// `schema-ast` feature.
+    #[cfg(feature = "schema-ast")]
+    pub fn document(&self) -> Result<graphql_parser::schema::Document<'static, String>, Error> {
+        match self {
+            Schema::Sdl(sdl) => parse_subgraph_schema(sdl),
+            Schema::Introspection(data) => crate::ast::introspection_document(data),
+        }
+    }
+}
+
+/// SDL as-is, introspection as pretty-printed JSON.
+impl Display for Schema {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Schema::Sdl(sdl) => write!(f, "{sdl}"),
+            Schema::Introspection(data) => {
+                let json = serde_json::to_string_pretty(data).map_err(|_| std::fmt::Error)?;
+                write!(f, "{json}")
+            }
+        }
+    }
+}
+
+/// How a schema changed, see [`diff`].
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct SchemaDiff {
+    /// Coordinates only in the new schema.
+    pub added: Vec<String>,
+    /// Coordinates only in the old schema.
+    pub removed: Vec<String>,
+}
+
+impl SchemaDiff {
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty()
+    }
+}
+
+/// One line for each change, `- ` for removed and `+ ` for added.
+impl Display for SchemaDiff {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for removed in &self.removed {
+            writeln!(f, "- {removed}")?;
+        }
+        for added in &self.added {
+            writeln!(f, "+ {added}")?;
+        }
+        Ok(())
+    }
+}
+
+/// Compares the [coordinates](Schema::coordinates) of two schemas, which don't have to be the same
+/// kind: a saved SDL baseline can be compared with a live endpoint which only has introspection.
+pub fn diff(old: &Schema, new: &Schema) -> Result<SchemaDiff, Error> {
+    let old = old.coordinates()?;
+    let new = new.coordinates()?;
+    Ok(SchemaDiff {
+        added: new.difference(&old).cloned().collect(),
+        removed: old.difference(&new).cloned().collect(),
+    })
+}
+
+/// What in `new` would break clients of `old`: anything removed or changed (which shows up as a
+/// removed [coordinate](Schema::coordinates)), and required arguments or input fields added to
+/// fields and input types which were already there.
+pub fn breaking_changes(old: &Schema, new: &Schema) -> Result<Vec<String>, Error> {
+    let old = old.coordinates()?;
+    let new = new.coordinates()?;
+    let removed = old
+        .difference(&new)
+        .map(|coordinate| format!("removed `{coordinate}`"));
+    let required = new
+        .difference(&old)
+        .filter(|coordinate| is_required_addition(coordinate, &old))
+        .map(|coordinate| format!("added required `{coordinate}`"));
+    Ok(removed.chain(required).collect())
+}
+
+/// Whether `coordinate` is a required argument of a field in `old`, or a required field of an
+/// input type in `old`, which existing clients don't send.
+fn is_required_addition(coordinate: &str, old: &BTreeSet<String>) -> bool {
+    if let Some((field, _)) = coordinate.split_once('(') {
+        let prefix = format!("{field}: ");
+        return coordinate.ends_with("!)")
+            && old.iter().any(|existing| existing.starts_with(&prefix));
+    }
+    coordinate.ends_with('!')
+        && coordinate
+            .split_once('.')
+            .is_some_and(|(type_name, _)| old.contains(&format!("input {type_name}")))
+}
+
+/// Which differences between two live schemas are fine, see
+/// [`Checker::compare_schema`](crate::Checker::compare_schema).
+#[derive(Clone, Copy, Debug, Default, Deserialize, Eq, Hash, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AllowedChanges {
+    /// The schemas must be the same.
+    #[default]
+    None,
+    /// The endpoint may have more than the other one, but nothing may be removed or changed.
+    Additions,
+    /// Anything but [breaking changes](breaking_changes).
+    NonBreaking,
+}
+
+impl AllowedChanges {
+    pub const ALL: [AllowedChanges; 3] = [
+        AllowedChanges::None,
+        AllowedChanges::Additions,
+        AllowedChanges::NonBreaking,
+    ];
+
+    pub const fn name(self) -> &'static str {
+        match self {
+            AllowedChanges::None => "none",
+            AllowedChanges::Additions => "additions",
+            AllowedChanges::NonBreaking => "non_breaking",
+        }
+    }
+
+    pub fn parse(value: &str) -> Result<Self, Error> {
+        Self::ALL
+            .into_iter()
+            .find(|allowed| allowed.name() == value)
+            .ok_or_else(|| {
+                let names = Self::ALL.map(|allowed| format!("`{}`", allowed.name()));
+                Error::BadInput(format!(
+                    "unknown change class `{value}`, expected one of {}",
+                    names.join(", ")
+                ))
+            })
+    }
+}
+
+/// The whole diff from `old` to `new` if they differ in a way which isn't `allowed`.
+pub fn changes_beyond(
+    old: &Schema,
+    new: &Schema,
+    allowed: AllowedChanges,
+) -> Result<Option<SchemaDiff>, Error> {
+    let diff = diff(old, new)?;
+    let beyond = match allowed {
+        AllowedChanges::None => !diff.is_empty(),
+        AllowedChanges::Additions => {
+            !diff.removed.is_empty() || !breaking_changes(old, new)?.is_empty()
+        }
+        AllowedChanges::NonBreaking => !breaking_changes(old, new)?.is_empty(),
+    };
+    Ok(beyond.then_some(diff))
+}
+
+/// Fetches the schemas of `url` and `compare_url` with the same auth, failing with every
+/// difference if `url` has changes to the schema of `compare_url` which aren't `allowed`.
+pub(crate) fn check_schema_matches(
+    context: &Context,
+    url: &str,
+    auth: &Auth,
+    compare_url: &str,
+    allowed: AllowedChanges,
+) -> Outcome {
+    let (Some(live), Some(other)) = (
+        fetch_schema_with(context, url, auth),
+        fetch_schema_with(context, compare_url, auth),
+    ) else {
+        return Outcome::Failed(Error::SchemaUnavailable);
+    };
+    match changes_beyond(&other, &live, allowed) {
+        Ok(None) => Outcome::Passed,
+        Ok(Some(diff)) => Outcome::Failed(Error::SchemaDrift {
+            expected: format!("`{compare_url}`"),
+            changes: diff.to_string().lines().map(String::from).collect(),
+        }),
+        Err(err) => Outcome::Failed(err),
+    }
+}
+
+/// Runs the checks which only need a schema on `sdl`, without sending any requests, e.g., to gate
+/// a pull request before the schema is deployed anywhere: that it parses, that its federation
+/// imports are right, that it follows the usual naming conventions and, given a `baseline`, that
+/// it has no [breaking changes](breaking_changes). Everything else is skipped if it doesn't parse.
+pub fn check_sdl(sdl: &str, baseline: Option<&Schema>) -> Vec<CheckResult> {
+    let document = match parse_subgraph_schema(sdl) {
+        Ok(document) => document,
+        Err(err) => {
+            return vec![
+                CheckResult::new(Check::SchemaValid, Outcome::Failed(err)),
+                CheckResult::new(Check::FederationImports, Outcome::Skipped),
+                CheckResult::new(Check::SchemaLint, Outcome::Skipped),
+                CheckResult::new(Check::BreakingChanges, Outcome::Skipped),
+            ];
+        }
+    };
+    let violations = lint(&document);
+    let lint_outcome = if violations.is_empty() {
+        Outcome::Passed
+    } else {
+        Outcome::Failed(Error::LintViolations(violations))
+    };
+    let breaking_outcome =
+        match baseline.map(|baseline| breaking_changes(baseline, &Schema::Sdl(sdl.to_string()))) {
+            None => Outcome::Skipped,
+            Some(Ok(changes)) if changes.is_empty() => Outcome::Passed,
+            Some(Ok(changes)) => Outcome::Failed(Error::BreakingChanges(changes)),
+            Some(Err(err)) => Outcome::Failed(err),
+        };
+    vec![
+        CheckResult::new(Check::SchemaValid, Outcome::Passed),
+        CheckResult::new(Check::FederationImports, check_imports(sdl).into()),
+        CheckResult::new(Check::SchemaLint, lint_outcome),
+        CheckResult::new(Check::BreakingChanges, breaking_outcome),
+    ]
+}
+
+/// Fetches the schema of the endpoint, preferring the subgraph SDL and falling back to
+/// introspection. Returns `None` if neither is available.
+pub fn fetch_schema(url: &str, auth: Auth) -> Option<Schema> {
+    fetch_schema_with(&Context::default(), url, &auth)
+}
+
+pub(crate) fn fetch_schema_with(context: &Context, url: &str, auth: &Auth) -> Option<Schema> {
+    let sdl = post_json(
+        context,
+        url,
+        auth,
+        json!({ "query": "query{_service{sdl}}" }),
+    )
+    .ok()
+    .and_then(|body| {
+        body.pointer("/data/_service/sdl")?
+            .as_str()
+            .map(String::from)
+    });
+    if let Some(sdl) = sdl {
+        return Some(Schema::Sdl(sdl));
+    }
+    post_json(context, url, auth, json!({ "query": INTROSPECTION_QUERY }))
+        .ok()
+        .and_then(|mut body| {
+            let data = body.get_mut("data")?.take();
+            data.get("__schema")?
+                .is_object()
+                .then_some(Schema::Introspection(data))
+        })
+}
+
+/// Scalars every schema has, which introspection lists but SDL doesn't.
+pub(crate) const BUILT_IN_SCALARS: [&str; 5] = ["String", "Int", "Float", "Boolean", "ID"];
+
+fn introspection_coordinates(data: &Value) -> BTreeSet<String> {
+    fn array(value: &Value) -> &[Value] {
+        value.as_array().map_or(&[], Vec::as_slice)
+    }
+    fn type_ref(value: &Value) -> String {
+        match value["kind"].as_str() {
+            Some("NON_NULL") => format!("{}!", type_ref(&value["ofType"])),
+            Some("LIST") => format!("[{}]", type_ref(&value["ofType"])),
+            _ => value["name"].as_str().unwrap_or_default().to_string(),
+        }
+    }
+
+    let mut coordinates = BTreeSet::new();
+    for ty in array(&data["__schema"]["types"]) {
+        let Some(name) = ty["name"].as_str() else {
+            continue;
+        };
+        if name.starts_with("__") || BUILT_IN_SCALARS.contains(&name) {
+            continue;
+        }
+        let kind = match ty["kind"].as_str() {
+            Some("OBJECT") => "type",
+            Some("INTERFACE") => "interface",
+            Some("UNION") => "union",
+            Some("ENUM") => "enum",
+            Some("INPUT_OBJECT") => "input",
+            _ => "scalar",
+        };
+        coordinates.insert(format!("{kind} {name}"));
+        for interface in array(&ty["interfaces"]) {
+            coordinates.insert(format!("{name} implements {}", type_ref(interface)));
+        }
+        for field in array(&ty["fields"]) {
+            let field_name = field["name"].as_str().unwrap_or_default();
+            coordinates.insert(format!("{name}.{field_name}: {}", type_ref(&field["type"])));
+            for arg in array(&field["args"]) {
+                coordinates.insert(format!(
+                    "{name}.{field_name}({}: {})",
+                    arg["name"].as_str().unwrap_or_default(),
+                    type_ref(&arg["type"])
+                ));
+            }
+        }
+        for field in array(&ty["inputFields"]) {
+            coordinates.insert(format!(
+                "{name}.{}: {}",
+                field["name"].as_str().unwrap_or_default(),
+                type_ref(&field["type"])
+            ));
+        }
+        for value in array(&ty["enumValues"]) {
+            coordinates.insert(format!(
+                "{name}.{}",
+                value["name"].as_str().unwrap_or_default()
+            ));
+        }
+        if kind == "union" {
+            for member in array(&ty["possibleTypes"]) {
+                coordinates.insert(format!("{name} = {}", type_ref(member)));
+            }
+        }
+    }
+    coordinates
+}
+
+fn sdl_coordinates(sdl: &str) -> Result<BTreeSet<String>, Error> {
+    let document = parse_subgraph_schema(sdl)?;
+    let mut coordinates = SdlCoordinates::default();
+    for definition in &document.definitions {
+        match definition {
+            Definition::TypeDefinition(TypeDefinition::Scalar(ty)) => {
+                coordinates.add_type("scalar", &ty.name);
+            }
+            Definition::TypeDefinition(TypeDefinition::Object(ty)) => {
+                coordinates.add_type("type", &ty.name);
+                coordinates.add_interfaces(&ty.name, &ty.implements_interfaces);
+                coordinates.add_fields(&ty.name, &ty.fields);
+            }
+            Definition::TypeDefinition(TypeDefinition::Interface(ty)) => {
+                coordinates.add_type("interface", &ty.name);
+                coordinates.add_interfaces(&ty.name, &ty.implements_interfaces);
+                coordinates.add_fields(&ty.name, &ty.fields);
+            }
+            Definition::TypeDefinition(TypeDefinition::Union(ty)) => {
+                coordinates.add_type("union", &ty.name);
+                coordinates.add_members(&ty.name, &ty.types);
+            }
+            Definition::TypeDefinition(TypeDefinition::Enum(ty)) => {
+                coordinates.add_type("enum", &ty.name);
+                coordinates.add_values(&ty.name, ty.values.iter().map(|value| &value.name));
+            }
+            Definition::TypeDefinition(TypeDefinition::InputObject(ty)) => {
+                coordinates.add_type("input", &ty.name);
+                coordinates.add_input_fields(&ty.name, &ty.fields);
+            }
+            // What `extend schema` is parsed as in a subgraph.
+            Definition::TypeExtension(TypeExtension::Scalar(ty)) if ty.name.starts_with("__") => {}
+            Definition::TypeExtension(TypeExtension::Scalar(ty)) => {
+                coordinates.add_type("scalar", &ty.name);
+            }
+            Definition::TypeExtension(TypeExtension::Object(ty)) => {
+                coordinates.add_type("type", &ty.name);
+                coordinates.add_interfaces(&ty.name, &ty.implements_interfaces);
+                coordinates.add_fields(&ty.name, &ty.fields);
+            }
+            Definition::TypeExtension(TypeExtension::Interface(ty)) => {
+                coordinates.add_type("interface", &ty.name);
+                coordinates.add_interfaces(&ty.name, &ty.implements_interfaces);
+                coordinates.add_fields(&ty.name, &ty.fields);
+            }
+            Definition::TypeExtension(TypeExtension::Union(ty)) => {
+                coordinates.add_type("union", &ty.name);
+                coordinates.add_members(&ty.name, &ty.types);
+            }
+            Definition::TypeExtension(TypeExtension::Enum(ty)) => {
+                coordinates.add_type("enum", &ty.name);
+                coordinates.add_values(&ty.name, ty.values.iter().map(|value| &value.name));
+            }
+            Definition::TypeExtension(TypeExtension::InputObject(ty)) => {
+                coordinates.add_type("input", &ty.name);
+                coordinates.add_input_fields(&ty.name, &ty.fields);
+            }
+            Definition::SchemaDefinition(_) | Definition::DirectiveDefinition(_) => {}
+        }
+    }
+    Ok(coordinates.0)
+}
+
+/// The same lines as [`introspection_coordinates`], gathered from SDL. Extensions add to the type
+/// they extend.
+#[derive(Default)]
+struct SdlCoordinates(BTreeSet<String>);
+
+impl SdlCoordinates {
+    fn add_type(&mut self, kind: &str, name: &str) {
+        self.0.insert(format!("{kind} {name}"));
+    }
+
+    fn add_interfaces(&mut self, name: &str, interfaces: &[String]) {
+        for interface in interfaces {
+            self.0.insert(format!("{name} implements {interface}"));
+        }
+    }
+
+    fn add_fields(&mut self, name: &str, fields: &[Field<String>]) {
+        for field in fields {
+            let field_name = &field.name;
+            self.0
+                .insert(format!("{name}.{field_name}: {}", field.field_type));
+            for arg in &field.arguments {
+                self.0.insert(format!(
+                    "{name}.{field_name}({}: {})",
+                    arg.name, arg.value_type
+                ));
+            }
+        }
+    }
+
+    fn add_input_fields(&mut self, name: &str, fields: &[InputValue<String>]) {
+        for field in fields {
+            self.0
+                .insert(format!("{name}.{}: {}", field.name, field.value_type));
+        }
+    }
+
+    fn add_values<'a>(&mut self, name: &str, values: impl Iterator<Item = &'a String>) {
+        for value in values {
+            self.0.insert(format!("{name}.{value}"));
+        }
+    }
+
+    fn add_members(&mut self, name: &str, members: &[String]) {
+        for member in members {
+            self.0.insert(format!("{name} = {member}"));
+        }
+    }
+}
+
+#[cfg(test)]
+mod test_diff {
+    use super::*;
+
+    const SDL: &str = r#"
+        type Query {
+            user(id: ID!): User
+        }
+
+        type User implements Node {
+            id: ID!
+            name: String
+        }
+
+        interface Node {
+            id: ID!
+        }
+
+        extend type Query {
+            search(term: String!): [SearchResult!]!
+        }
+
+        union SearchResult = User
+
+        enum Role { ADMIN MEMBER }
+
+        input UserFilter {
+            role: Role = MEMBER
+        }
+    "#;
+
+    fn named(kind: &str, name: &str) -> Value {
+        json!({"kind": kind, "name": name, "ofType": null})
+    }
+
+    fn non_null(of_type: Value) -> Value {
+        json!({"kind": "NON_NULL", "name": null, "ofType": of_type})
+    }
+
+    fn introspection() -> Value {
+        json!({"__schema": {"types": [
+            {
+                "kind": "OBJECT",
+                "name": "Query",
+                "interfaces": [],
+                "fields": [
+                    {
+                        "name": "user",
+                        "args": [{"name": "id", "type": non_null(named("SCALAR", "ID"))}],
+                        "type": named("OBJECT", "User"),
+                    },
+                    {
+                        "name": "search",
+                        "args": [{"name": "term", "type": non_null(named("SCALAR", "String"))}],
+                        "type": non_null(json!({
+                            "kind": "LIST",
+                            "name": null,
+                            "ofType": non_null(named("UNION", "SearchResult")),
+                        })),
+                    },
+                ],
+            },
+            {
+                "kind": "OBJECT",
+                "name": "User",
+                "interfaces": [named("INTERFACE", "Node")],
+                "fields": [
+                    {"name": "id", "args": [], "type": non_null(named("SCALAR", "ID"))},
+                    {"name": "name", "args": [], "type": named("SCALAR", "String")},
+                ],
+            },
+            {
+                "kind": "INTERFACE",
+                "name": "Node",
+                "interfaces": [],
+                "fields": [{"name": "id", "args": [], "type": non_null(named("SCALAR", "ID"))}],
+                "possibleTypes": [named("OBJECT", "User")],
+            },
+            {
+                "kind": "UNION",
+                "name": "SearchResult",
+                "possibleTypes": [named("OBJECT", "User")],
+            },
+            {"kind": "ENUM", "name": "Role", "enumValues": [{"name": "ADMIN"}, {"name": "MEMBER"}]},
+            {
+                "kind": "INPUT_OBJECT",
+                "name": "UserFilter",
+                "inputFields": [{"name": "role", "type": named("ENUM", "Role")}],
+            },
+            {"kind": "SCALAR", "name": "String"},
+            {"kind": "OBJECT", "name": "__Type", "fields": []},
+        ]}})
+    }
+
+    #[test]
+    fn sdl_matches_introspection() {
+        let diff = diff(
+            &Schema::Sdl(SDL.to_string()),
+            &Schema::Introspection(introspection()),
+        )
+        .unwrap();
+        assert!(diff.is_empty(), "{diff}");
+    }
+
+    #[test]
+    fn changes() {
+        let new = SDL
+            .replace("name: String", "displayName: String")
+            .replace("ADMIN MEMBER", "ADMIN MEMBER GUEST");
+        let diff = diff(&Schema::Sdl(SDL.to_string()), &Schema::Sdl(new)).unwrap();
+        assert_eq!(
+            diff.to_string(),
+            "- User.name: String\n+ Role.GUEST\n+ User.displayName: String\n"
+        );
+    }
+
+    #[test]
+    fn parse() {
+        let wrapped = json!({"data": introspection()}).to_string();
+        assert_eq!(
+            Schema::parse(&wrapped).unwrap(),
+            Schema::Introspection(introspection())
+        );
+        assert_eq!(Schema::parse(SDL).unwrap(), Schema::Sdl(SDL.to_string()));
+        assert!(matches!(
+            Schema::parse(r#"{"data": null}"#),
+            Err(Error::BadSchema(_))
+        ));
+        assert!(matches!(
+            Schema::Sdl("type {".to_string()).coordinates(),
+            Err(Error::BadSchema(_))
+        ));
+    }
+}
+
+#[cfg(test)]
+mod test_check_sdl {
+    use super::*;
+
+    const BASELINE: &str = r"
+        type Query {
+            user(id: ID!): User
+        }
+
+        type User {
+            id: ID!
+            name: String
+        }
+
+        input UserFilter {
+            name: String
+        }
+    ";
+
+    fn outcomes(results: &[CheckResult]) -> Vec<(Check, Outcome)> {
+        results
+            .iter()
+            .map(|result| (result.check, result.outcome.clone()))
+            .collect()
+    }
+
+    #[test]
+    fn passes() {
+        let baseline = Schema::Sdl(BASELINE.to_string());
+        let sdl = format!("{BASELINE}\nextend type Query {{ users(first: Int): [User!]! }}");
+        assert_eq!(
+            outcomes(&check_sdl(&sdl, Some(&baseline))),
+            [
+                (Check::SchemaValid, Outcome::Passed),
+                (Check::FederationImports, Outcome::Passed),
+                (Check::SchemaLint, Outcome::Passed),
+                (Check::BreakingChanges, Outcome::Passed),
+            ]
+        );
+    }
+
+    #[test]
+    fn invalid() {
+        let results = check_sdl("type Query {", None);
+        assert!(matches!(
+            results[0].outcome,
+            Outcome::Failed(Error::BadSchema(_))
+        ));
+        assert!(results[1..]
+            .iter()
+            .all(|result| result.outcome == Outcome::Skipped));
+    }
+
+    #[test]
+    fn breaking() {
+        let old = Schema::Sdl(BASELINE.to_string());
+        let new = Schema::Sdl(
+            r"
+            type Query {
+                user(id: ID!, tenant: ID!): User
+            }
+
+            type User {
+                id: ID!
+            }
+
+            input UserFilter {
+                name: String
+                tenant: ID!
+            }
+            "
+            .to_string(),
+        );
+        assert_eq!(
+            breaking_changes(&old, &new).unwrap(),
+            [
+                "removed `User.name: String`",
+                "added required `Query.user(tenant: ID!)`",
+                "added required `UserFilter.tenant: ID!`",
+            ]
+        );
+    }
+}
+
+#[cfg(test)]
+mod test_compare_schema {
+    use super::*;
+    use crate::testing::{query, MockTransport};
+    use crate::{Checker, HttpResponse};
+
+    const PRODUCTION: &str =
+        "type Query { user(id: ID!): User } type User { id: ID! name: String }";
+
+    fn sdl(sdl: &str) -> Schema {
+        Schema::Sdl(sdl.to_string())
+    }
+
+    #[test]
+    fn change_classes() {
+        let old = sdl(PRODUCTION);
+        let added = sdl(&PRODUCTION.replace("name: String", "name: String email: String"));
+        let required = sdl(&PRODUCTION.replace("id: ID!)", "id: ID!, tenant: ID!)"));
+        let optional = sdl(&PRODUCTION.replace("id: ID!)", "id: ID!, tenant: ID)"));
+        let beyond = |new: &Schema, allowed| changes_beyond(&old, new, allowed).unwrap().is_some();
+
+        assert!(!beyond(&old, AllowedChanges::None));
+        assert!(beyond(&added, AllowedChanges::None));
+        assert!(!beyond(&added, AllowedChanges::Additions));
+        assert!(beyond(&required, AllowedChanges::Additions));
+        assert!(!beyond(&optional, AllowedChanges::Additions));
+        assert!(!beyond(&optional, AllowedChanges::NonBreaking));
+        assert!(beyond(&required, AllowedChanges::NonBreaking));
+    }
+
+    #[test]
+    fn checker() {
+        let transport = MockTransport::new(|request| {
+            let sdl = if request.url.contains("staging") {
+                PRODUCTION.replace("name: String", "fullName: String")
+            } else {
+                PRODUCTION.to_string()
+            };
+            Ok(
+                if query(request).is_some_and(|query| query.contains("_service")) {
+                    HttpResponse::json(200, &json!({"data": {"_service": {"sdl": sdl}}}))
+                } else {
+                    HttpResponse::json(200, &json!({"data": {"__typename": "Query"}}))
+                },
+            )
+        });
+        let report = Checker::new("https://staging.example.com/graphql")
+            .compare_schema("https://example.com/graphql", AllowedChanges::Additions)
+            .transport(transport)
+            .report();
+        let result = report
+            .checks
+            .iter()
+            .find(|result| result.check == Check::SchemaMatches)
+            .unwrap();
+        assert_eq!(
+            result.outcome,
+            Outcome::Failed(Error::SchemaDrift {
+                expected: "`https://example.com/graphql`".to_string(),
+                changes: vec![
+                    "- User.name: String".to_string(),
+                    "+ User.fullName: String".to_string(),
+                ],
+            })
+        );
+    }
+
+    #[test]
+    fn parse() {
+        assert_eq!(
+            AllowedChanges::parse("non_breaking"),
+            Ok(AllowedChanges::NonBreaking)
+        );
+        assert!(matches!(
+            AllowedChanges::parse("breaking"),
+            Err(Error::BadInput(_))
+        ));
+    }
+}
@@ -0,0 +1,113 @@
+use itertools::Itertools;
+
+use crate::{Check, CheckResult, Error, Outcome};
+
+/// What a check's outcome was in one run, coarse enough that runs can be compared: whether it
+/// passed, was skipped, or the code of the error it failed with.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub(crate) struct Signature {
+    check: Check,
+    outcome: &'static str,
+}
+
+impl Signature {
+    pub(crate) fn new(result: &CheckResult) -> Self {
+        Self {
+            check: result.check,
+            outcome: match &result.outcome {
+                Outcome::Passed => "passed",
+                Outcome::Skipped => "skipped",
+                Outcome::Failed(err) => err.code(),
+            },
+        }
+    }
+}
+
+/// Compares the outcome of every check across `runs`, failing with each check whose outcome
+/// changed, e.g., one which failed once out of several runs.
+pub(crate) fn check_stability(runs: &[Vec<Signature>]) -> Outcome {
+    let all = || runs.iter().flatten();
+    if all().any(|signature| signature.outcome == Error::Cancelled.code()) {
+        return Outcome::Failed(Error::Cancelled);
+    }
+    let changed: Vec<String> = all()
+        .map(|signature| signature.check)
+        .unique()
+        .filter_map(|check| {
+            let outcomes: Vec<&str> = runs
+                .iter()
+                .map(|run| {
+                    run.iter()
+                        .find(|signature| signature.check == check)
+                        .map_or("missing", |signature| signature.outcome)
+                })
+                .collect();
+            let differs = outcomes.iter().unique().count() > 1;
+            differs.then(|| format!("{}: {}", check.name(), outcomes.join(", ")))
+        })
+        .collect();
+    if changed.is_empty() {
+        Outcome::Passed
+    } else {
+        Outcome::Failed(Error::Flaky(changed))
+    }
+}
+
+#[cfg(test)]
+mod test_check_stability {
+    use std::time::Duration;
+
+    use super::*;
+
+    fn run(outcomes: Vec<(Check, Outcome)>) -> Vec<Signature> {
+        outcomes
+            .into_iter()
+            .map(|(check, outcome)| Signature::new(&CheckResult::new(check, outcome)))
+            .collect()
+    }
+
+    #[test]
+    fn stable() {
+        let runs = [
+            run(vec![
+                (Check::BasicQuery, Outcome::Passed),
+                (Check::AuthEnforced, Outcome::Skipped),
+            ]),
+            run(vec![
+                (Check::BasicQuery, Outcome::Passed),
+                (Check::AuthEnforced, Outcome::Skipped),
+            ]),
+        ];
+        assert_eq!(check_stability(&runs), Outcome::Passed);
+    }
+
+    #[test]
+    fn flaky() {
+        let runs = [
+            run(vec![(Check::BasicQuery, Outcome::Passed)]),
+            run(vec![(
+                Check::BasicQuery,
+                Outcome::Failed(Error::timed_out(
+                    Duration::from_secs(1),
+                    Duration::from_secs(1),
+                )),
+            )]),
+            run(vec![(Check::BasicQuery, Outcome::Passed)]),
+        ];
+        assert_eq!(
+            check_stability(&runs),
+            Outcome::Failed(Error::Flaky(vec![
+                "basic_query: passed, timeout, passed".to_string()
+            ]))
+        );
+    }
+
+    #[test]
+    fn cancelled() {
+        let runs = [
+            run(vec![(Check::BasicQuery, Outcome::Passed)]),
+            run(vec![(Check::BasicQuery, Outcome::Failed(Error::Cancelled))]),
+        ];
+        assert_eq!(check_stability(&runs), Outcome::Failed(Error::Cancelled));
+    }
+}
@@ -0,0 +1,187 @@
+use graphql_check_action::{CheckResult, Error, Severity};
+use serde::Serialize;
+use serde_json::{json, Value};
+use ureq::Agent;
+
+use crate::cli::VERSION;
+
+const PLATFORM_API_URL: &str = "https://api.apollographql.com/api/graphql";
+
+const CUSTOM_CHECK_CALLBACK: &str = r"mutation CustomCheckCallback($graphId: ID!, $name: String!, $input: CustomCheckCallbackInput!) {
+  graph(id: $graphId) {
+    variant(name: $name) {
+      customCheckCallback(input: $input) {
+        __typename
+        ... on PermissionError { message }
+        ... on TaskError { message }
+        ... on ValidationError { message }
+      }
+    }
+  }
+}";
+
+/// A minimal client for the part of Apollo's Platform API used to report a custom check.
+pub struct Studio {
+    agent: Agent,
+    api_key: String,
+    graph_id: String,
+    variant: String,
+}
+
+impl Studio {
+    /// `graph_ref` is `graph-id@variant`, the variant defaulting to `current` like in Rover.
+    pub fn new(agent: Agent, api_key: &str, graph_ref: &str) -> Self {
+        let (graph_id, variant) = graph_ref.split_once('@').unwrap_or((graph_ref, "current"));
+        Self {
+            agent,
+            api_key: api_key.to_string(),
+            graph_id: graph_id.to_string(),
+            variant: variant.to_string(),
+        }
+    }
+
+    /// Completes the custom check task of a schema check with the results of this run, so they show
+    /// up in Studio next to the other checks. `workflow_id` and `task_id` come from the webhook
+    /// GraphOS sent when the check started.
+    pub fn complete_custom_check(
+        &self,
+        workflow_id: &str,
+        task_id: &str,
+        failed: bool,
+        violations: &[Violation],
+    ) -> Result<(), Error> {
+        let body: Value = self
+            .agent
+            .post(PLATFORM_API_URL)
+            .set("x-api-key", &self.api_key)
+            .set("apollographql-client-name", "graphql-check-action")
+            .set("apollographql-client-version", VERSION)
+            .send_json(json!({
+                "query": CUSTOM_CHECK_CALLBACK,
+                "variables": {
+                    "graphId": self.graph_id,
+                    "name": self.variant,
+                    "input": {
+                        "workflowId": workflow_id,
+                        "taskId": task_id,
+                        "status": if failed { "FAILURE" } else { "SUCCESS" },
+                        "violations": violations,
+                    },
+                },
+            }))
+            .map_err(|err| match err {
+                ureq::Error::Status(status, _) => {
+                    Error::Studio(format!("got status code {status}"))
+                }
+                ureq::Error::Transport(transport) => Error::Studio(transport.to_string()),
+            })?
+            .into_json()
+            .map_err(|err| Error::Studio(err.to_string()))?;
+        callback_error(&body).map_or(Ok(()), |reason| Err(Error::Studio(reason)))
+    }
+}
+
+/// Why the callback was rejected, if it was. Problems are returned as errors in the response or
+/// as one of the error types of the mutation's result union.
+fn callback_error(body: &Value) -> Option<String> {
+    if let Some(message) = body.pointer("/errors/0/message").and_then(Value::as_str) {
+        return Some(message.to_string());
+    }
+    let result = body.pointer("/data/graph/variant/customCheckCallback");
+    let Some(result) = result.filter(|result| !result.is_null()) else {
+        return Some("the graph or variant was not found".to_string());
+    };
+    result
+        .get("message")
+        .and_then(Value::as_str)
+        .map(String::from)
+}
+
+/// A problem reported to a custom check.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize)]
+pub struct Violation {
+    level: &'static str,
+    message: String,
+    rule: String,
+}
+
+pub fn violations(results: &[CheckResult], other_errors: &[Error]) -> Vec<Violation> {
+    results
+        .iter()
+        .filter_map(|result| Some((result.check.name(), result.error()?, result.severity)))
+        .chain(
+            other_errors
+                .iter()
+                .map(|err| (err.code(), err, err.severity())),
+        )
+        .map(|(rule, err, severity)| Violation {
+            level: match severity {
+                Severity::Error => "ERROR",
+                Severity::Warning => "WARNING",
+            },
+            message: err.to_string(),
+            rule: rule.to_string(),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod test_studio {
+    use graphql_check_action::{new_agent, Check, Outcome};
+
+    use super::*;
+
+    #[test]
+    fn graph_ref() {
+        let studio = Studio::new(new_agent(), "key", "my-graph@staging");
+        assert_eq!(studio.graph_id, "my-graph");
+        assert_eq!(studio.variant, "staging");
+        assert_eq!(
+            Studio::new(new_agent(), "key", "my-graph").variant,
+            "current"
+        );
+    }
+
+    #[test]
+    fn only_failures() {
+        let results = [
+            CheckResult::new(Check::BasicQuery, Outcome::Passed),
+            CheckResult::new(Check::BasicQuery, Outcome::Failed(Error::NotASubgraph)),
+        ];
+        assert_eq!(
+            violations(&results, &[Error::Cancelled]),
+            vec![
+                Violation {
+                    level: "ERROR",
+                    message: "GraphQL endpoint is not a subgraph".to_string(),
+                    rule: Check::BasicQuery.name().to_string(),
+                },
+                Violation {
+                    level: "ERROR",
+                    message: Error::Cancelled.to_string(),
+                    rule: "cancelled".to_string(),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn rejected() {
+        assert_eq!(
+            callback_error(&json!({"data": {"graph": {"variant": {
+                "customCheckCallback": {"__typename": "CustomCheckResult"}
+            }}}})),
+            None
+        );
+        assert_eq!(
+            callback_error(&json!({"data": {"graph": {"variant": {
+                "customCheckCallback": {"__typename": "TaskError", "message": "task already completed"}
+            }}}})),
+            Some("task already completed".to_string())
+        );
+        assert_eq!(
+            callback_error(&json!({"data": {"graph": null}})),
+            Some("the graph or variant was not found".to_string())
+        );
+    }
+}
@@ -0,0 +1,87 @@
+//! Helpers for testing code which runs checks, without a live server.
+
+use std::fmt::{self, Debug, Formatter};
+#[cfg(test)]
+use std::sync::Arc;
+use std::sync::Mutex;
+
+#[cfg(test)]
+use crate::http::Context;
+use crate::{Error, HttpRequest, HttpResponse, Transport};
+
+type Handler = dyn Fn(&HttpRequest) -> Result<HttpResponse, Error> + Send + Sync;
+
+/// A [`Transport`] which answers every request by calling a function instead of sending it,
+/// keeping every request so tests can make assertions about them.
+///
+/// ```
+/// use graphql_check_action::testing::MockTransport;
+/// use graphql_check_action::{Checker, HttpResponse, Outcome};
+/// use serde_json::json;
+///
+/// let transport = MockTransport::new(|_request| {
+///     Ok(HttpResponse::json(200, &json!({"data": {"__typename": "Query"}})))
+/// });
+/// let results = Checker::new("https://example.com/graphql")
+///     .allow_introspection()
+///     .transport(transport)
+///     .run();
+/// assert_eq!(results[0].outcome, Outcome::Passed);
+/// ```
+pub struct MockTransport {
+    handler: Box<Handler>,
+    requests: Mutex<Vec<HttpRequest>>,
+}
+
+impl MockTransport {
+    pub fn new(
+        handler: impl Fn(&HttpRequest) -> Result<HttpResponse, Error> + Send + Sync + 'static,
+    ) -> Self {
+        Self {
+            handler: Box::new(handler),
+            requests: Mutex::default(),
+        }
+    }
+
+    /// Every request sent so far, in order.
+    pub fn requests(&self) -> Vec<HttpRequest> {
+        self.requests
+            .lock()
+            .expect("mock transport lock poisoned")
+            .clone()
+    }
+}
+
+impl Debug for MockTransport {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("MockTransport")
+            .field("requests", &self.requests)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Transport for MockTransport {
+    fn send(&self, request: &HttpRequest) -> Result<HttpResponse, Error> {
+        self.requests
+            .lock()
+            .expect("mock transport lock poisoned")
+            .push(request.clone());
+        (self.handler)(request)
+    }
+}
+
+/// A [`Context`] which sends its requests to a [`MockTransport`] calling `handler`.
+#[cfg(test)]
+pub(crate) fn context(
+    handler: impl Fn(&HttpRequest) -> Result<HttpResponse, Error> + Send + Sync + 'static,
+) -> Context {
+    Context {
+        transport: Arc::new(MockTransport::new(handler)),
+        ..Context::default()
+    }
+}
+
+/// The text of the GraphQL query in a request's body, if there is one.
+pub fn query(request: &HttpRequest) -> Option<&str> {
+    request.body.as_ref()?.get("query")?.as_str()
+}
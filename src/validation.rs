@@ -0,0 +1,120 @@
+use itertools::Itertools;
+use serde_json::{json, Value};
+
+use crate::graphql::{ErrorCode, GraphQLError};
+use crate::http::Context;
+use crate::{make_request, Auth, Error, Outcome};
+
+/// A field no schema should have.
+const UNKNOWN_FIELD_QUERY: &str = "query{__typename graphqlCheckUnknownField}";
+
+/// Queries a field which doesn't exist, expecting the whole operation to be rejected with a
+/// validation error instead of crashing the server or running the rest of it. If the errors have
+/// codes, one of them must be `GRAPHQL_VALIDATION_FAILED`.
+pub(crate) fn check_unknown_field(context: &Context, url: &str, auth: &Auth) -> Outcome {
+    let request = match make_request(context, url, auth) {
+        Ok(request) => request,
+        Err(err) => return Outcome::Failed(err),
+    };
+    let response = match context.send(request, Some(&json!({ "query": UNKNOWN_FIELD_QUERY }))) {
+        Ok(response) => response,
+        Err(err) => return Outcome::Failed(err),
+    };
+    if response.status >= 500 {
+        return Outcome::Failed(Error::BadStatus {
+            status: response.status,
+            excerpt: response.excerpt(),
+        });
+    }
+    let Ok(body) = serde_json::from_str::<Value>(&response.body) else {
+        return Outcome::Failed(Error::not_graphql(&response));
+    };
+    if body.get("data").is_some_and(|data| !data.is_null()) {
+        return Outcome::Failed(Error::UnknownFieldNotRejected(
+            "the rest of the query was executed".to_string(),
+        ));
+    }
+    let errors = body
+        .get("errors")
+        .map(GraphQLError::parse_all)
+        .unwrap_or_default();
+    if errors.is_empty() {
+        return Outcome::Failed(Error::UnknownFieldNotRejected(format!(
+            "the response had no errors (status {})",
+            response.status
+        )));
+    }
+    let codes: Vec<ErrorCode> = errors.iter().filter_map(GraphQLError::kind).collect();
+    if codes.is_empty() || codes.contains(&ErrorCode::GraphQLValidationFailed) {
+        Outcome::Passed
+    } else {
+        Outcome::Failed(Error::UnknownFieldNotRejected(format!(
+            "the error code was {} instead of {}",
+            codes.iter().join(", "),
+            ErrorCode::GraphQLValidationFailed
+        )))
+    }
+}
+
+#[cfg(test)]
+mod test_check_unknown_field {
+    use super::*;
+    use crate::testing::context;
+    use crate::HttpResponse;
+
+    fn check(status: u16, body: Value) -> Outcome {
+        let context = context(move |_| Ok(HttpResponse::json(status, &body)));
+        check_unknown_field(&context, "https://example.com/graphql", &Auth::Disabled)
+    }
+
+    #[test]
+    fn rejected() {
+        let error = json!({
+            "message": "Cannot query field \"graphqlCheckUnknownField\" on type \"Query\".",
+            "extensions": {"code": "GRAPHQL_VALIDATION_FAILED"}
+        });
+        assert_eq!(check(400, json!({ "errors": [error] })), Outcome::Passed);
+        assert_eq!(
+            check(200, json!({"errors": [{"message": "Unknown field"}]})),
+            Outcome::Passed
+        );
+    }
+
+    #[test]
+    fn partially_executed() {
+        assert_eq!(
+            check(
+                200,
+                json!({
+                    "data": {"__typename": "Query", "graphqlCheckUnknownField": null},
+                    "errors": [{"message": "Unknown field"}]
+                })
+            ),
+            Outcome::Failed(Error::UnknownFieldNotRejected(
+                "the rest of the query was executed".to_string()
+            ))
+        );
+    }
+
+    #[test]
+    fn wrong_code() {
+        assert_eq!(
+            check(
+                400,
+                json!({"errors": [{"message": "Oops", "extensions": {"code": "INTERNAL_SERVER_ERROR"}}]})
+            ),
+            Outcome::Failed(Error::UnknownFieldNotRejected(
+                "the error code was INTERNAL_SERVER_ERROR instead of GRAPHQL_VALIDATION_FAILED"
+                    .to_string()
+            ))
+        );
+    }
+
+    #[test]
+    fn server_error() {
+        assert!(matches!(
+            check(500, json!({"errors": [{"message": "Oops"}]})),
+            Outcome::Failed(Error::BadStatus { status: 500, .. })
+        ));
+    }
+}